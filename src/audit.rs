@@ -0,0 +1,351 @@
+use std::{
+    fmt::Display,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+
+use crate::{
+    config::LOG_FILE_NAME,
+    fil0crypt::fil_space_crypt_t,
+    fil0fil, fsp0fsp,
+    log::{self, FORMAT_ENCRYPTED, Redo},
+    mach,
+    tablespace::MmapTablespaceReader,
+};
+
+/// Encryption status of the installation found in a data directory.
+#[derive(Debug)]
+pub struct AuditReport {
+    pub redo: Option<RedoAudit>,
+    pub tablespaces: Vec<TablespaceAudit>,
+}
+
+#[derive(Debug)]
+pub struct RedoAudit {
+    pub path: PathBuf,
+    pub version: u32,
+    pub encrypted: bool,
+    /// Whether the log is in the latest (10.8) format supported by this tool.
+    pub is_latest: bool,
+}
+
+#[derive(Debug)]
+pub struct TablespaceAudit {
+    pub path: PathBuf,
+    pub space_id: u32,
+    pub flags: u32,
+    /// Crypt data stored on page 0, if any.
+    pub crypt: Option<fil_space_crypt_t>,
+    /// Number of pages carrying a non-zero key version.
+    pub encrypted_pages: usize,
+    pub pages: usize,
+    /// Set if the tablespace could not be inspected.
+    pub error: Option<String>,
+}
+
+impl RedoAudit {
+    pub fn readable(&self) -> bool {
+        self.is_latest && !self.encrypted
+    }
+}
+
+impl TablespaceAudit {
+    pub fn encrypted(&self) -> bool {
+        self.encrypted_pages > 0 || self.crypt.as_ref().is_some_and(|c| c.is_encrypted())
+    }
+
+    pub fn readable(&self) -> bool {
+        self.error.is_none() && self.encrypted_pages == 0
+    }
+}
+
+impl AuditReport {
+    pub fn encrypted(&self) -> bool {
+        self.redo.as_ref().is_some_and(|r| r.encrypted)
+            || self.tablespaces.iter().any(|t| t.encrypted())
+    }
+
+    pub fn readable(&self) -> bool {
+        self.redo.as_ref().is_none_or(|r| r.readable())
+            && self.tablespaces.iter().all(|t| t.readable())
+    }
+}
+
+/// Checks the redo log format and all tablespaces of a data directory for encryption.
+pub fn audit_data_dir(dir: &Path) -> anyhow::Result<AuditReport> {
+    if !dir.is_dir() {
+        bail!("data directory {} does not exist", dir.display());
+    }
+
+    let log_path = dir.join(LOG_FILE_NAME);
+    let redo = if log_path.exists() {
+        Some(audit_redo(&log_path)?)
+    } else {
+        None
+    };
+
+    let mut tablespaces = vec![];
+    for path in find_tablespaces(dir)? {
+        tablespaces.push(audit_tablespace(&path));
+    }
+
+    Ok(AuditReport { redo, tablespaces })
+}
+
+pub fn audit_redo(path: &Path) -> anyhow::Result<RedoAudit> {
+    let mut buf = vec![0u8; log::START_OFFSET as usize];
+    std::fs::File::open(path)
+        .and_then(|mut f| f.read_exact(&mut buf))
+        .with_context(|| format!("read log file header at {}", path.display()))?;
+
+    let hdr = Redo::parse_header(&buf)?;
+
+    // An encrypted 10.8 log keeps FORMAT_10_8 in the header: only the crypt info after
+    // the creator, which the checkpoint parsing reads, tells it is encrypted.
+    let encrypted = if log::is_latest(hdr.version) {
+        Redo::inspect(&buf)?.1.encrypted
+    } else {
+        hdr.version & FORMAT_ENCRYPTED != 0
+    };
+
+    Ok(RedoAudit {
+        path: path.to_path_buf(),
+        version: hdr.version,
+        encrypted,
+        is_latest: log::is_latest(hdr.version),
+    })
+}
+
+pub fn audit_tablespace(path: &Path) -> TablespaceAudit {
+    let mut audit = TablespaceAudit {
+        path: path.to_path_buf(),
+        space_id: 0,
+        flags: 0,
+        crypt: None,
+        encrypted_pages: 0,
+        pages: 0,
+        error: None,
+    };
+
+    if let Err(err) = audit_tablespace_pages(&mut audit) {
+        audit.error = Some(format!("{err:#}"));
+    }
+
+    audit
+}
+
+fn audit_tablespace_pages(audit: &mut TablespaceAudit) -> anyhow::Result<()> {
+    let mut buf = [0u8; (fsp0fsp::FSP_HEADER_OFFSET + fsp0fsp::FSP_SPACE_FLAGS + 4) as usize];
+    std::fs::File::open(&audit.path)
+        .and_then(|mut f| f.read_exact(&mut buf))
+        .with_context(|| format!("read first page at {}", audit.path.display()))?;
+
+    audit.space_id = mach::mach_read_from_4(&buf[fil0fil::FIL_PAGE_SPACE_ID as usize..]);
+    audit.flags = mach::mach_read_from_4(
+        &buf[(fsp0fsp::FSP_HEADER_OFFSET + fsp0fsp::FSP_SPACE_FLAGS) as usize..],
+    );
+
+    let logical_size = fil0fil::logical_size(audit.flags);
+    if logical_size == 0 {
+        bail!("invalid tablespace flags {:#x}", audit.flags);
+    }
+
+    let mmap_reader = MmapTablespaceReader::open(&audit.path, logical_size)?;
+    let reader = mmap_reader.reader_with_flags_override(audit.flags)?;
+
+    audit.pages = reader.npages() as usize;
+    audit.crypt = fil_space_crypt_t::from_page(reader.page(0)?.buf(), audit.flags);
+
+    for page_no in 1..audit.pages as u32 {
+        if reader.page(page_no)?.is_encrypted() {
+            audit.encrypted_pages += 1;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the system, undo and file-per-table tablespaces of a data directory,
+/// including the ones in the database subdirectories.
+fn find_tablespaces(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+
+    for entry in std::fs::read_dir(dir).with_context(|| format!("read dir {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        if path.is_dir() {
+            for entry in std::fs::read_dir(&path)? {
+                let path = entry?.path();
+                if path.is_file() && path.extension().is_some_and(|ext| ext == "ibd") {
+                    paths.push(path);
+                }
+            }
+        } else if name.starts_with("ibdata")
+            || (name.starts_with("undo") && name[4..].chars().all(|c| c.is_ascii_digit()))
+            || path.extension().is_some_and(|ext| ext == "ibd")
+        {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+
+    Ok(paths)
+}
+
+impl Display for AuditReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.redo {
+            Some(redo) => writeln!(
+                f,
+                "redo {}: format={:#x}, encrypted={}, readable={}",
+                redo.path.display(),
+                redo.version,
+                redo.encrypted,
+                redo.readable()
+            )?,
+            None => writeln!(f, "redo: not found")?,
+        }
+
+        for ts in &self.tablespaces {
+            if let Some(err) = &ts.error {
+                writeln!(f, "tablespace {}: ERROR: {err}", ts.path.display())?;
+                continue;
+            }
+
+            writeln!(
+                f,
+                "tablespace {}: space_id={}, flags={:#x}, encrypted={} ({}/{} pages), \
+                 readable={}",
+                ts.path.display(),
+                ts.space_id,
+                ts.flags,
+                ts.encrypted(),
+                ts.encrypted_pages,
+                ts.pages,
+                ts.readable()
+            )?;
+        }
+
+        write!(
+            f,
+            "encrypted={}, readable={}",
+            self.encrypted(),
+            self.readable()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        fil0crypt::{CRYPT_MAGIC, CRYPT_SCHEME_1, fsp_header_get_encryption_offset},
+        log::{
+            CHECKPOINT_1, FIRST_LSN, LOG_CRYPT_KEY_ID, LOG_CRYPT_KEY_VERSION, LOG_CRYPT_MSG,
+            LOG_DEFAULT_ENCRYPTION_KEY, LOG_HEADER_CRC, LOG_HEADER_CREATOR_END, MY_AES_BLOCK_SIZE,
+            RedoHeader,
+        },
+    };
+
+    const PAGE_SIZE: usize = 16384;
+    const FLAGS: u32 = 0x15;
+
+    fn make_tablespace(path: &Path, space_id: u32, encrypted: bool) {
+        let mut buf = vec![0u8; PAGE_SIZE * 3];
+
+        for page_no in 0..3 {
+            let page = &mut buf[page_no * PAGE_SIZE..];
+            mach::mach_write_to_4(
+                &mut page[fil0fil::FIL_PAGE_OFFSET as usize..],
+                page_no as u32,
+            )
+            .unwrap();
+            mach::mach_write_to_4(&mut page[fil0fil::FIL_PAGE_SPACE_ID as usize..], space_id)
+                .unwrap();
+        }
+
+        let fsp = fsp0fsp::FSP_HEADER_OFFSET as usize;
+        mach::mach_write_to_4(&mut buf[fsp + fsp0fsp::FSP_SPACE_ID as usize..], space_id).unwrap();
+        mach::mach_write_to_4(&mut buf[fsp + fsp0fsp::FSP_SPACE_FLAGS as usize..], FLAGS).unwrap();
+
+        if encrypted {
+            let offset = fsp_header_get_encryption_offset(PAGE_SIZE, 0);
+            buf[offset..offset + CRYPT_MAGIC.len()].copy_from_slice(&CRYPT_MAGIC);
+            buf[offset + CRYPT_MAGIC.len()] = CRYPT_SCHEME_1;
+            buf[offset + CRYPT_MAGIC.len() + 1] = 16;
+
+            // key version of page 2
+            mach::mach_write_to_4(
+                &mut buf[PAGE_SIZE * 2 + fil0fil::FIL_PAGE_FCRC32_KEY_VERSION as usize..],
+                1,
+            )
+            .unwrap();
+        }
+
+        std::fs::write(path, buf).unwrap();
+    }
+
+    #[test]
+    fn test_audit_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut log = vec![0u8; FIRST_LSN as usize];
+        let hdr = RedoHeader::build_unencrypted_header_10_8(FIRST_LSN, "test_creator").unwrap();
+        log[..hdr.len()].copy_from_slice(&hdr);
+        let cp =
+            RedoHeader::build_unencrypted_header_10_8_checkpoint(FIRST_LSN, FIRST_LSN).unwrap();
+        log[CHECKPOINT_1..CHECKPOINT_1 + cp.len()].copy_from_slice(&cp);
+        std::fs::write(dir.path().join(LOG_FILE_NAME), log).unwrap();
+
+        std::fs::create_dir(dir.path().join("db")).unwrap();
+        make_tablespace(&dir.path().join("db").join("plain.ibd"), 1, false);
+        make_tablespace(&dir.path().join("db").join("secret.ibd"), 2, true);
+
+        let report = audit_data_dir(dir.path()).unwrap();
+
+        let redo = report.redo.as_ref().unwrap();
+        assert!(!redo.encrypted);
+        assert!(redo.readable());
+
+        assert_eq!(report.tablespaces.len(), 2);
+        let plain = &report.tablespaces[0];
+        assert!(plain.path.ends_with("plain.ibd"));
+        assert!(plain.error.is_none());
+        assert!(!plain.encrypted());
+        assert_eq!(plain.pages, 3);
+
+        let secret = &report.tablespaces[1];
+        assert!(secret.path.ends_with("secret.ibd"));
+        assert!(secret.encrypted());
+        assert_eq!(secret.encrypted_pages, 1);
+        assert_eq!(secret.crypt.as_ref().unwrap().scheme, CRYPT_SCHEME_1);
+        assert!(!secret.readable());
+
+        assert!(report.encrypted());
+        assert!(!report.readable());
+
+        // An encrypted 10.8 log keeps FORMAT_10_8 and carries the crypt info instead.
+        let path = dir.path().join(LOG_FILE_NAME);
+        let mut log = std::fs::read(&path).unwrap();
+        let info = &mut log[LOG_HEADER_CREATOR_END..];
+        mach::mach_write_to_4(&mut info[LOG_CRYPT_KEY_ID..], LOG_DEFAULT_ENCRYPTION_KEY).unwrap();
+        mach::mach_write_to_4(&mut info[LOG_CRYPT_KEY_VERSION..], 1).unwrap();
+        info[LOG_CRYPT_MSG..LOG_CRYPT_MSG + MY_AES_BLOCK_SIZE].fill(0x5a);
+        let crc = crc32c::crc32c(&log[..LOG_HEADER_CRC]);
+        mach::mach_write_to_4(&mut log[LOG_HEADER_CRC..], crc).unwrap();
+        std::fs::write(&path, log).unwrap();
+
+        let redo = audit_redo(&path).unwrap();
+        assert_eq!(redo.version, log::FORMAT_10_8);
+        assert!(redo.is_latest);
+        assert!(redo.encrypted);
+        assert!(!redo.readable());
+    }
+}
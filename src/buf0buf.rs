@@ -2,7 +2,10 @@ use std::io::{Error, ErrorKind, Result};
 
 use crc32c::crc32c;
 
-use crate::{Lsn, fil0fil, log::FIRST_LSN, mach, page_buf::PageBuf};
+use crate::{Lsn, fil0fil, fsp0types, log::FIRST_LSN, mach, page_buf::PageBuf, page0zip, ut0ut};
+
+/// Value of a checksum field when innodb_checksum_algorithm=none was used.
+pub const BUF_NO_CHECKSUM_MAGIC: u32 = 0xDEADBEEF;
 
 /// Check whether a page is newer than the durable LSN.
 /// Returns whether the FIL_PAGE_LSN is invalid (ahead of the durable LSN).
@@ -90,159 +93,283 @@ pub fn buf_page_is_corrupted(page: &PageBuf, check_lsn: Option<Lsn>) -> Result<(
         return Ok(());
     }
 
-    todo!("Implement buf_page_is_corrupted for non-full_crc32 pages");
-
-    /*
-      const ulint zip_size = fil_space_t::zip_size(fsp_flags);
-      const uint16_t page_type = fil_page_get_type(read_buf);
-
-      /* We can trust page type if page compression is set on tablespace
-      flags because page compression flag means file must have been
-      created with 10.1 (later than 5.5 code base). In 10.1 page
-      compressed tables do not contain post compression checksum and
-      FIL_PAGE_END_LSN_OLD_CHKSUM field stored. Note that space can
-      be null if we are in fil_check_first_page() and first page
-      is not compressed or encrypted. Page checksum is verified
-      after decompression (i.e. normally pages are already
-      decompressed at this stage). */
-      if ((page_type == FIL_PAGE_PAGE_COMPRESSED ||
-           page_type == FIL_PAGE_PAGE_COMPRESSED_ENCRYPTED)
-    #ifndef UNIV_INNOCHECKSUM
-          && FSP_FLAGS_HAS_PAGE_COMPRESSION(fsp_flags)
-    #endif
-      ) {
-      check_lsn:
-        return
-    #ifndef UNIV_INNOCHECKSUM
-          buf_page_check_lsn(check_lsn, read_buf)
-          ? CORRUPTED_FUTURE_LSN :
-    #endif
-          NOT_CORRUPTED;
-      }
-
-      static_assert(FIL_PAGE_LSN % 4 == 0, "alignment");
-      static_assert(FIL_PAGE_END_LSN_OLD_CHKSUM % 4 == 0, "alignment");
-
-      if (!zip_size
-          && memcmp_aligned<4>(read_buf + FIL_PAGE_LSN + 4,
-             read_buf + srv_page_size
-             - FIL_PAGE_END_LSN_OLD_CHKSUM + 4, 4)) {
-        /* Stored log sequence numbers at the start and the end
-        of page do not match */
-
-        return CORRUPTED_OTHER;
-      }
-
-      /* Check whether the checksum fields have correct values */
-
-      if (zip_size) {
-        if (!page_zip_verify_checksum(read_buf, zip_size)) {
-          return CORRUPTED_OTHER;
+    let zip_size = fil0fil::zip_size(page.flags());
+    let page_type = fil0fil::fil_page_get_type(page.buf());
+
+    // We can trust page type if page compression is set on tablespace flags because page
+    // compression flag means file must have been created with 10.1 (later than 5.5 code
+    // base). In 10.1 page compressed tables do not contain post compression checksum and
+    // FIL_PAGE_END_LSN_OLD_CHKSUM field stored.
+    if (page_type == fil0fil::FIL_PAGE_PAGE_COMPRESSED
+        || page_type == fil0fil::FIL_PAGE_PAGE_COMPRESSED_ENCRYPTED)
+        && fsp0types::FSP_FLAGS_HAS_PAGE_COMPRESSION(page.flags()) != 0
+    {
+        return check_page_lsn(page, check_lsn);
+    }
+
+    let page_size = page.len();
+    let end_lsn_offset = page_size - fil0fil::FIL_PAGE_END_LSN_OLD_CHKSUM as usize;
+
+    // Stored log sequence numbers at the start and the end of page do not match.
+    if zip_size == 0
+        && page.read_4(fil0fil::FIL_PAGE_LSN as usize + 4) != page.read_4(end_lsn_offset + 4)
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "InnoDB: Page is corrupted (other) (LSN mismatch)",
+        ));
+    }
+
+    if zip_size != 0 {
+        if !page0zip::page_zip_verify_checksum(page.buf()) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "InnoDB: Page is corrupted (other) (compressed page checksum mismatch)",
+            ));
         }
-        goto check_lsn;
-      }
-
-      const uint32_t checksum_field1 = mach_read_from_4(
-        read_buf + FIL_PAGE_SPACE_OR_CHKSUM);
-
-      const uint32_t checksum_field2 = mach_read_from_4(
-        read_buf + srv_page_size - FIL_PAGE_END_LSN_OLD_CHKSUM);
-
-      static_assert(FIL_PAGE_LSN % 8 == 0, "alignment");
-
-      /* A page filled with NUL bytes is considered not corrupted.
-      Before MariaDB Server 10.1.25 (MDEV-12113) or 10.2.2 (or MySQL 5.7),
-      the FIL_PAGE_FILE_FLUSH_LSN field may have been written nonzero
-      for the first page of each file of the system tablespace.
-      We want to ignore it for the system tablespace, but because
-      we do not know the expected tablespace here, we ignore the
-      field for all data files, except for
-      innodb_checksum_algorithm=full_crc32 which we handled above. */
-      if (!checksum_field1 && !checksum_field2) {
-        /* Checksum fields can have valid value as zero.
-        If the page is not empty then do the checksum
-        calculation for the page. */
-        bool all_zeroes = true;
-        for (size_t i = 0; i < srv_page_size; i++) {
-    #ifndef UNIV_INNOCHECKSUM
-          if (i == FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION) {
-            i += 8;
-          }
-    #endif
-          if (read_buf[i]) {
-            all_zeroes = false;
-            break;
-          }
+
+        return check_page_lsn(page, check_lsn);
+    }
+
+    let checksum_field1 = page.read_4(fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize);
+    let checksum_field2 = page.read_4(end_lsn_offset);
+
+    // A page filled with NUL bytes is considered not corrupted. Before MariaDB Server
+    // 10.1.25 (MDEV-12113) or 10.2.2 (or MySQL 5.7), the FIL_PAGE_FILE_FLUSH_LSN field may
+    // have been written nonzero for the first page of each file of the system tablespace.
+    // We ignore the field for all data files.
+    if checksum_field1 == 0 && checksum_field2 == 0 {
+        let flush_lsn = fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize;
+        if page[..flush_lsn].iter().all(|&b| b == 0)
+            && page[flush_lsn + 8..].iter().all(|&b| b == 0)
+        {
+            return Ok(());
         }
+    }
 
-        if (all_zeroes) {
-          return NOT_CORRUPTED;
+    // innodb_checksum_algorithm is not known here, so like any non-strict_* setting
+    // allow any of the algorithms to match.
+    if checksum_field1 == BUF_NO_CHECKSUM_MAGIC && checksum_field2 == BUF_NO_CHECKSUM_MAGIC {
+        return check_page_lsn(page, check_lsn);
+    }
+
+    let crc32 = buf_calc_page_crc32(page.buf());
+    let crc32_matches = checksum_field1 == crc32 && checksum_field2 == crc32;
+
+    // Very old versions of InnoDB only stored 8 byte lsn to the start and the end of the
+    // page.
+    if checksum_field2 != page.read_4(fil0fil::FIL_PAGE_LSN as usize)
+        && checksum_field2 != BUF_NO_CHECKSUM_MAGIC
+        && !crc32_matches
+        && checksum_field2 != buf_calc_page_old_checksum(page.buf())
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "InnoDB: Page is corrupted (other) (checksum field2 mismatch)",
+        ));
+    }
+
+    match checksum_field1 {
+        0 | BUF_NO_CHECKSUM_MAGIC => {}
+        _ if !crc32_matches && checksum_field1 != buf_calc_page_new_checksum(page.buf()) => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "InnoDB: Page is corrupted (other) (checksum field1 mismatch)",
+            ));
         }
-      }
-
-    #ifndef UNIV_INNOCHECKSUM
-      switch (srv_checksum_algorithm) {
-      case SRV_CHECKSUM_ALGORITHM_STRICT_FULL_CRC32:
-      case SRV_CHECKSUM_ALGORITHM_STRICT_CRC32:
-    #endif /* !UNIV_INNOCHECKSUM */
-        if (!buf_page_is_checksum_valid_crc32(read_buf,
-                      checksum_field1,
-                      checksum_field2)) {
-          return CORRUPTED_OTHER;
+        _ => {}
+    }
+
+    check_page_lsn(page, check_lsn)
+}
+
+fn check_page_lsn(page: &PageBuf, check_lsn: Option<Lsn>) -> Result<()> {
+    match check_lsn {
+        Some(current_lsn) => buf_page_check_lsn(page, current_lsn),
+        None => Ok(()),
+    }
+}
+
+/// Calculates the CRC-32C checksum of a page that is not in the full_crc32 format.
+/// FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION is excluded, because it is written after
+/// the checksum on the first page of the system tablespace.
+/// Reference: buf0checksum.cc:buf_calc_page_crc32().
+pub fn buf_calc_page_crc32(page: &[u8]) -> u32 {
+    let c1 = crc32c(
+        &page[fil0fil::FIL_PAGE_OFFSET as usize
+            ..fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize],
+    );
+    let c2 = crc32c(
+        &page[fil0fil::FIL_PAGE_DATA as usize
+            ..page.len() - fil0fil::FIL_PAGE_END_LSN_OLD_CHKSUM as usize],
+    );
+
+    c1 ^ c2
+}
+
+/// Calculates the innodb checksum of a page that is stored in FIL_PAGE_SPACE_OR_CHKSUM.
+/// Reference: buf0checksum.cc:buf_calc_page_new_checksum().
+pub fn buf_calc_page_new_checksum(page: &[u8]) -> u32 {
+    // Since the field FIL_PAGE_FILE_FLUSH_LSN, and in versions <= 4.1.x
+    // FIL_PAGE_ARCH_LOG_NO_OR_SPACE_ID, are written outside the buffer pool to the first
+    // pages of data files, we have to skip them in the page checksum calculation. We must
+    // also skip the field FIL_PAGE_SPACE_OR_CHKSUM where the checksum is stored, and also
+    // the last 8 bytes of page because there we store the old formula checksum.
+    let checksum = ut0ut::ut_fold_binary(
+        &page[fil0fil::FIL_PAGE_OFFSET as usize
+            ..fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize],
+    )
+    .wrapping_add(ut0ut::ut_fold_binary(
+        &page[fil0fil::FIL_PAGE_DATA as usize
+            ..page.len() - fil0fil::FIL_PAGE_END_LSN_OLD_CHKSUM as usize],
+    ));
+
+    checksum as u32
+}
+
+/// Calculates the old formula checksum of a page that is stored in the page trailer.
+/// Reference: buf0checksum.cc:buf_calc_page_old_checksum().
+pub fn buf_calc_page_old_checksum(page: &[u8]) -> u32 {
+    ut0ut::ut_fold_binary(&page[..fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize]) as u32
+}
+
+/// Checksum algorithm a page was written with, see innodb_checksum_algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageChecksumAlgorithm {
+    FullCrc32,
+    Crc32,
+    Innodb,
+    None,
+}
+
+impl std::fmt::Display for PageChecksumAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PageChecksumAlgorithm::FullCrc32 => "full_crc32",
+            PageChecksumAlgorithm::Crc32 => "crc32",
+            PageChecksumAlgorithm::Innodb => "innodb",
+            PageChecksumAlgorithm::None => "none",
+        })
+    }
+}
+
+/// Stored and calculated checksums of a page, as reported by innochecksum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageChecksum {
+    pub page_no: u32,
+    pub full_crc32: bool,
+    /// The full_crc32 checksum at the end of the page, or FIL_PAGE_SPACE_OR_CHKSUM.
+    pub field1: u32,
+    /// The old-style checksum in the page trailer. None for full_crc32 pages.
+    pub field2: Option<u32>,
+    pub calculated: u32,
+    /// The algorithm the stored checksums match. None for pages without a checksum (all
+    /// zeroes or page_compressed) and for pages matching no algorithm.
+    pub algorithm: Option<PageChecksumAlgorithm>,
+    /// Whether the page is not corrupted, see `buf_page_is_corrupted()`.
+    pub valid: bool,
+}
+
+impl PageChecksum {
+    /// Verifies the page with `buf_page_is_corrupted()`, accepting any checksum algorithm,
+    /// and reports the algorithm the stored checksums match.
+    pub fn from_page(page: &PageBuf) -> PageChecksum {
+        let valid = buf_page_is_corrupted(page, None).is_ok();
+
+        if fil0fil::full_crc32(page.flags()) {
+            let (page_size, _, _) = buf_page_full_crc32_size(page);
+            let page_size = page_size.min(page.len());
+            let checksum_offset = page_size - fil0fil::FIL_PAGE_FCRC32_CHECKSUM as usize;
+            let field1 = mach::mach_read_from_4(&page[checksum_offset..]);
+            let calculated = crc32c(&page[..checksum_offset]);
+
+            return PageChecksum {
+                page_no: page.page_no(),
+                full_crc32: true,
+                field1,
+                field2: None,
+                calculated,
+                algorithm: (field1 == calculated).then_some(PageChecksumAlgorithm::FullCrc32),
+                valid,
+            };
         }
-        goto check_lsn;
-    #ifndef UNIV_INNOCHECKSUM
-      default:
-        if (checksum_field1 == BUF_NO_CHECKSUM_MAGIC
-            && checksum_field2 == BUF_NO_CHECKSUM_MAGIC) {
-          goto check_lsn;
+
+        let field1 = mach::mach_read_from_4(&page[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..]);
+        let field2 = mach::mach_read_from_4(
+            &page[page.len() - fil0fil::FIL_PAGE_END_LSN_OLD_CHKSUM as usize..],
+        );
+
+        if fil0fil::zip_size(page.flags()) != 0 {
+            // ROW_FORMAT=COMPRESSED pages store a single checksum, see
+            // page_zip_verify_checksum().
+            let calculated = page0zip::page_zip_calc_checksum(page.buf(), false);
+            let algorithm = if field1 == calculated {
+                Some(PageChecksumAlgorithm::Crc32)
+            } else if field1 == BUF_NO_CHECKSUM_MAGIC {
+                Some(PageChecksumAlgorithm::None)
+            } else if field1 == page0zip::page_zip_calc_checksum(page.buf(), true) {
+                Some(PageChecksumAlgorithm::Innodb)
+            } else {
+                None
+            };
+
+            return PageChecksum {
+                page_no: page.page_no(),
+                full_crc32: false,
+                field1,
+                field2: Some(field2),
+                calculated,
+                algorithm,
+                valid,
+            };
         }
 
-        const uint32_t crc32 = buf_calc_page_crc32(read_buf);
-
-        /* Very old versions of InnoDB only stored 8 byte lsn to the
-        start and the end of the page. */
-
-        /* Since innodb_checksum_algorithm is not strict_* allow
-        any of the algos to match for the old field */
-
-        if (checksum_field2
-            != mach_read_from_4(read_buf + FIL_PAGE_LSN)
-            && checksum_field2 != BUF_NO_CHECKSUM_MAGIC) {
-
-          DBUG_EXECUTE_IF(
-            "page_intermittent_checksum_mismatch", {
-            static int page_counter;
-            if (mach_read_from_4(FIL_PAGE_OFFSET
-                     + read_buf)
-                && page_counter++ == 6)
-              return CORRUPTED_OTHER;
-          });
-
-          if ((checksum_field1 != crc32
-               || checksum_field2 != crc32)
-              && checksum_field2
-              != buf_calc_page_old_checksum(read_buf)) {
-            return CORRUPTED_OTHER;
-          }
+        let calculated = buf_calc_page_crc32(page);
+        let algorithm = if field1 == calculated && field2 == calculated {
+            Some(PageChecksumAlgorithm::Crc32)
+        } else if field1 == BUF_NO_CHECKSUM_MAGIC && field2 == BUF_NO_CHECKSUM_MAGIC {
+            Some(PageChecksumAlgorithm::None)
+        } else if field1 == buf_calc_page_new_checksum(page) {
+            Some(PageChecksumAlgorithm::Innodb)
+        } else {
+            None
+        };
+
+        PageChecksum {
+            page_no: page.page_no(),
+            full_crc32: false,
+            field1,
+            field2: Some(field2),
+            calculated,
+            algorithm,
+            valid,
         }
+    }
+}
 
-        switch (checksum_field1) {
-        case 0:
-        case BUF_NO_CHECKSUM_MAGIC:
-          break;
-        default:
-          if ((checksum_field1 != crc32
-               || checksum_field2 != crc32)
-              && checksum_field1
-              != buf_calc_page_new_checksum(read_buf)) {
-            return CORRUPTED_OTHER;
-          }
+impl std::fmt::Display for PageChecksum {
+    /// Formats the page like the innochecksum --log output, followed by the verdict.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let verdict = match (self.valid, self.algorithm) {
+            (true, Some(algorithm)) => format!("OK ({algorithm})"),
+            (true, None) => "OK".to_string(),
+            (false, _) => "BAD".to_string(),
+        };
+
+        match self.field2 {
+            None => write!(
+                f,
+                "page::{}; full_crc32 calculated = {}; recorded checksum = {}; {verdict}",
+                self.page_no, self.calculated, self.field1
+            ),
+            Some(field2) => write!(
+                f,
+                "page::{}; crc32 calculated = {}; recorded checksum field1 = {} recorded \
+                 checksum field2 = {}; {verdict}",
+                self.page_no, self.calculated, self.field1, field2
+            ),
         }
-      }
-    #endif /* !UNIV_INNOCHECKSUM */
-      goto check_lsn;
-    */
+    }
 }
 
 /// Get the compressed or uncompressed size of a full_crc32 page.
@@ -277,3 +404,180 @@ fn buf_page_full_crc32_size(page: &PageBuf) -> (usize, bool, bool) {
 
     (page_size, compressed, corrupted)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_buf::{make_page_footer, make_page_header};
+
+    #[test]
+    fn test_legacy_page_corrupted() {
+        let page_size = 16384;
+        let trailer = page_size - fil0fil::FIL_PAGE_END_LSN_OLD_CHKSUM as usize;
+
+        let mut page = vec![0u8; page_size];
+        make_page_header(&mut page, 5, 3, fil0fil::FIL_PAGE_INDEX, 1000, 0x15).unwrap();
+        for (i, b) in page[fil0fil::FIL_PAGE_DATA as usize..trailer]
+            .iter_mut()
+            .enumerate()
+        {
+            *b = (i % 251) as u8;
+        }
+        mach::mach_write_to_4(&mut page[trailer + 4..], 1000).unwrap();
+
+        let with_checksums = |field1: u32, field2: u32| {
+            let mut page = page.clone();
+            mach::mach_write_to_4(
+                &mut page[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..],
+                field1,
+            )
+            .unwrap();
+            mach::mach_write_to_4(&mut page[trailer..], field2).unwrap();
+            page
+        };
+        let corrupted = |page: &[u8]| buf_page_is_corrupted(&PageBuf::new(0, page), None);
+
+        let crc32 = buf_calc_page_crc32(&page);
+        let crc32_page = with_checksums(crc32, crc32);
+        assert!(corrupted(&crc32_page).is_ok());
+
+        // the old formula covers FIL_PAGE_SPACE_OR_CHKSUM, so it is calculated last.
+        let new_checksum = buf_calc_page_new_checksum(&page);
+        let old_checksum = buf_calc_page_old_checksum(&with_checksums(new_checksum, 0));
+        let innodb_page = with_checksums(new_checksum, old_checksum);
+        assert!(corrupted(&innodb_page).is_ok());
+
+        let none_page = with_checksums(BUF_NO_CHECKSUM_MAGIC, BUF_NO_CHECKSUM_MAGIC);
+        assert!(corrupted(&none_page).is_ok());
+
+        for mut bad in [crc32_page, innodb_page] {
+            bad[fil0fil::FIL_PAGE_DATA as usize + 100] ^= 1;
+            assert_eq!(corrupted(&bad).unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+
+        // the low 4 bytes of FIL_PAGE_LSN differ from the trailer.
+        let mut bad = with_checksums(crc32, crc32);
+        mach::mach_write_to_4(&mut bad[trailer + 4..], 999).unwrap();
+        assert!(corrupted(&bad).is_err());
+
+        // an all-zero page is valid, even with FIL_PAGE_FILE_FLUSH_LSN written.
+        let mut zero = vec![0u8; page_size];
+        mach::mach_write_to_8(
+            &mut zero[fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize..],
+            12345,
+        )
+        .unwrap();
+        assert!(corrupted(&zero).is_ok());
+
+        // The empty root page 3 of the clustered index of a table in space 5, as it is
+        // written with innodb_checksum_algorithm=innodb: the FIL header, the page header,
+        // the infimum and supremum records and the page directory and trailer, all other
+        // bytes are zero.
+        #[rustfmt::skip]
+        const PAGE_HEAD: [u8; 120] = [
+            0xf8, 0x71, 0x48, 0xcf, // FIL_PAGE_SPACE_OR_CHKSUM
+            0x00, 0x00, 0x00, 0x03, // FIL_PAGE_OFFSET
+            0xff, 0xff, 0xff, 0xff, // FIL_PAGE_PREV
+            0xff, 0xff, 0xff, 0xff, // FIL_PAGE_NEXT
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x7a, 0x41, // FIL_PAGE_LSN
+            0x45, 0xbf, // FIL_PAGE_TYPE
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // FIL_PAGE_FILE_FLUSH_LSN
+            0x00, 0x00, 0x00, 0x05, // FIL_PAGE_ARCH_LOG_NO_OR_SPACE_ID
+            0x00, 0x02, 0x00, 0x78, 0x80, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2c, // PAGE_INDEX_ID
+            0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0xf2, // PAGE_BTR_SEG_LEAF
+            0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0x32, // PAGE_BTR_SEG_TOP
+            0x01, 0x00, 0x02, 0x00, 0x0d, b'i', b'n', b'f', b'i', b'm', b'u', b'm', 0x00,
+            0x01, 0x00, 0x0b, 0x00, 0x00, b's', b'u', b'p', b'r', b'e', b'm', b'u', b'm',
+        ];
+        #[rustfmt::skip]
+        const PAGE_TAIL: [u8; 12] = [
+            0x00, 0x70, 0x00, 0x63, // PAGE_DIR slots of the supremum and infimum
+            0xb6, 0x51, 0x30, 0x60, // FIL_PAGE_END_LSN_OLD_CHKSUM
+            0x00, 0x2c, 0x7a, 0x41, // low 32 bits of FIL_PAGE_LSN
+        ];
+
+        let mut stored = vec![0u8; page_size];
+        stored[..PAGE_HEAD.len()].copy_from_slice(&PAGE_HEAD);
+        stored[page_size - PAGE_TAIL.len()..].copy_from_slice(&PAGE_TAIL);
+        assert_eq!(buf_calc_page_new_checksum(&stored), 0xf87148cf);
+        assert_eq!(buf_calc_page_old_checksum(&stored), 0xb6513060);
+        assert_eq!(buf_calc_page_crc32(&stored), 0xa2a24062);
+        assert!(corrupted(&stored).is_ok());
+
+        stored[page_size / 2] ^= 1;
+        assert_eq!(
+            corrupted(&stored).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_page_checksum_report() {
+        let page_size = 16384;
+
+        let mut full = vec![0u8; page_size];
+        make_page_header(&mut full, 5, 3, fil0fil::FIL_PAGE_INDEX, 1000, 0x15).unwrap();
+        make_page_footer(&mut full).unwrap();
+
+        // the same page in the crc32 format of tablespace flags 0.
+        let mut legacy = full.clone();
+        legacy[page_size - 8..].fill(0);
+        mach::mach_write_to_4(&mut legacy[page_size - 4..], 1000).unwrap();
+        let crc32 = buf_calc_page_crc32(&legacy);
+        mach::mach_write_to_4(&mut legacy[0..], crc32).unwrap();
+        mach::mach_write_to_4(&mut legacy[page_size - 8..], crc32).unwrap();
+
+        let mut bad = legacy.clone();
+        bad[100] ^= 1;
+
+        // the old formula covers FIL_PAGE_SPACE_OR_CHKSUM, so it is calculated last.
+        let mut innodb = legacy.clone();
+        let new_checksum = buf_calc_page_new_checksum(&innodb);
+        mach::mach_write_to_4(&mut innodb[0..], new_checksum).unwrap();
+        let old_checksum = buf_calc_page_old_checksum(&innodb);
+        mach::mach_write_to_4(&mut innodb[page_size - 8..], old_checksum).unwrap();
+
+        let mut none = legacy.clone();
+        mach::mach_write_to_4(&mut none[0..], BUF_NO_CHECKSUM_MAGIC).unwrap();
+        mach::mach_write_to_4(&mut none[page_size - 8..], BUF_NO_CHECKSUM_MAGIC).unwrap();
+
+        let mut sys_page0 = vec![0u8; page_size];
+        mach::mach_write_to_8(
+            &mut sys_page0[fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize..],
+            12345,
+        )
+        .unwrap();
+
+        let report = [
+            PageChecksum::from_page(&PageBuf::new(0x15, &full)),
+            PageChecksum::from_page(&PageBuf::new(0, &legacy)),
+            PageChecksum::from_page(&PageBuf::new(0, &bad)),
+            PageChecksum::from_page(&PageBuf::new(0, &innodb)),
+            PageChecksum::from_page(&PageBuf::new(0, &none)),
+            PageChecksum::from_page(&PageBuf::new(0, &sys_page0)),
+        ]
+        .iter()
+        .map(|checksum| checksum.to_string())
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            report,
+            vec![
+                "page::3; full_crc32 calculated = 4148639143; recorded checksum = 4148639143; OK \
+                 (full_crc32)",
+                "page::3; crc32 calculated = 3613280239; recorded checksum field1 = 3613280239 \
+                 recorded checksum field2 = 3613280239; OK (crc32)",
+                "page::3; crc32 calculated = 3571560155; recorded checksum field1 = 3613280239 \
+                 recorded checksum field2 = 3613280239; BAD",
+                "page::3; crc32 calculated = 3613280239; recorded checksum field1 = 701299442 \
+                 recorded checksum field2 = 1871398365; OK (innodb)",
+                "page::3; crc32 calculated = 3613280239; recorded checksum field1 = 3735928559 \
+                 recorded checksum field2 = 3735928559; OK (none)",
+                "page::0; crc32 calculated = 536728786; recorded checksum field1 = 0 recorded checksum \
+                 field2 = 0; OK",
+            ]
+        );
+    }
+}
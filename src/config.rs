@@ -21,6 +21,33 @@ pub struct Config {
         group = "redo_log_file_path"
     )]
     pub srv_log_file_path: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub tablespace: TablespaceConfig,
+}
+
+/// The options of the tablespace commands, shared with the redo log ones through
+/// [`Config`].
+#[derive(clap::Args)]
+pub struct TablespaceConfig {
+    #[clap(
+        long = "page-size",
+        help = "Page size in bytes (default: 16384)",
+        default_value = "16384"
+    )]
+    pub page_size: usize,
+
+    #[clap(
+        long = "undo-log-dir",
+        help = "Path to the undo logs directory (Undo Log)"
+    )]
+    pub undo_log_dir: Option<PathBuf>,
+
+    #[clap(
+        long = "data-dir",
+        help = "Path to the data directory (default for the log group and undo logs)"
+    )]
+    pub data_dir: Option<PathBuf>,
 }
 
 impl Config {
@@ -41,6 +68,10 @@ impl Config {
                 .to_path_buf());
         }
 
+        if let Some(ref path) = self.tablespace.data_dir {
+            return Ok(path.clone());
+        }
+
         Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             "Log file directory not specified",
@@ -56,6 +87,10 @@ impl Config {
             return Ok(path.join(LOG_FILE_NAME));
         }
 
+        if let Some(ref path) = self.tablespace.data_dir {
+            return Ok(path.join(LOG_FILE_NAME));
+        }
+
         Err(std::io::Error::new(
             std::io::ErrorKind::NotFound,
             "Log file path not specified",
@@ -66,3 +101,26 @@ impl Config {
         format!("{LOG_FILE_NAME_PREFIX}{i}")
     }
 }
+
+impl TablespaceConfig {
+    pub fn get_undo_log_dir(&self) -> Result<PathBuf> {
+        if let Some(ref path) = self.undo_log_dir {
+            return Ok(path.clone());
+        }
+
+        if let Some(ref path) = self.data_dir {
+            return Ok(path.clone());
+        }
+
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "Undo log directory not specified",
+        ))
+    }
+
+    pub fn get_data_dir(&self) -> Result<PathBuf> {
+        self.data_dir.clone().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "Data directory not specified")
+        })
+    }
+}
@@ -0,0 +1,361 @@
+//! Field layouts of the on-disk structures, built from the offset constants of each module.
+//! Offsets are absolute page offsets for the given page size.
+
+use std::fmt::Display;
+
+use crate::{fil0fil, fsp0fsp, fsp0types, fut0lst, trx0rseg, trx0sys, trx0undo, univ, wsrep};
+
+/// Structures known to `describe()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Struct {
+    /// Transaction system header page (TRX_SYS_PAGE_NO).
+    TrxSys,
+    /// File space header on page 0.
+    FspHeader,
+    /// Rollback segment header page.
+    TrxRseg,
+    /// Undo log page header and undo log segment header.
+    UndoPage,
+}
+
+/// A field of a structure: name as in the MariaDB sources, page offset and size in bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDesc {
+    pub name: &'static str,
+    pub offset: usize,
+    pub size: usize,
+}
+
+impl FieldDesc {
+    fn new(name: &'static str, offset: u32, size: u32) -> FieldDesc {
+        FieldDesc {
+            name,
+            offset: offset as usize,
+            size: size as usize,
+        }
+    }
+}
+
+impl Display for FieldDesc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>6} {:>6} {:>5}  {}",
+            self.offset,
+            self.offset + self.size - 1,
+            self.size,
+            self.name
+        )
+    }
+}
+
+/// Returns the fields of the structure laid out on a page of the given size.
+pub fn describe(s: Struct, page_size: usize) -> Vec<FieldDesc> {
+    let mut fields = vec![FieldDesc::new("FIL_HEADER", 0, fil0fil::FIL_PAGE_DATA)];
+
+    match s {
+        Struct::TrxSys => describe_trx_sys(&mut fields, page_size),
+        Struct::FspHeader => describe_fsp_header(&mut fields, page_size),
+        Struct::TrxRseg => describe_trx_rseg(&mut fields, page_size),
+        Struct::UndoPage => describe_undo_page(&mut fields),
+    }
+
+    fields.push(FieldDesc::new(
+        "FIL_TRAILER",
+        (page_size - fil0fil::FIL_PAGE_DATA_END as usize) as u32,
+        fil0fil::FIL_PAGE_DATA_END,
+    ));
+
+    fields
+}
+
+fn describe_trx_sys(fields: &mut Vec<FieldDesc>, page_size: usize) {
+    let base = trx0sys::TRX_SYS;
+    let fseg_size = fsp0types::FSEG_HEADER_SIZE as u32;
+
+    fields.push(FieldDesc::new(
+        "TRX_SYS_TRX_ID_STORE",
+        base + trx0sys::TRX_SYS_TRX_ID_STORE,
+        8,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_FSEG_HEADER",
+        base + trx0sys::TRX_SYS_FSEG_HEADER,
+        fseg_size,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_RSEGS",
+        base + trx0sys::TRX_SYS_RSEGS,
+        trx0sys::TRX_SYS_N_RSEGS * trx0sys::TRX_SYS_RSEG_SLOT_SIZE,
+    ));
+
+    let xid = trx0sys::TRX_SYS_WSREP_XID_INFO(page_size);
+    fields.push(FieldDesc::new(
+        "TRX_SYS_WSREP_XID_MAGIC_N_FLD",
+        xid + trx0sys::TRX_SYS_WSREP_XID_MAGIC_N_FLD,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_WSREP_XID_FORMAT",
+        xid + trx0sys::TRX_SYS_WSREP_XID_FORMAT,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_WSREP_XID_GTRID_LEN",
+        xid + trx0sys::TRX_SYS_WSREP_XID_GTRID_LEN,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_WSREP_XID_BQUAL_LEN",
+        xid + trx0sys::TRX_SYS_WSREP_XID_BQUAL_LEN,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_WSREP_XID_DATA",
+        xid + trx0sys::TRX_SYS_WSREP_XID_DATA,
+        wsrep::XIDDATASIZE,
+    ));
+
+    let log = (page_size - trx0sys::TRX_SYS_MYSQL_LOG_INFO_END) as u32;
+    fields.push(FieldDesc::new(
+        "TRX_SYS_MYSQL_LOG_MAGIC_N_FLD",
+        log + trx0sys::TRX_SYS_MYSQL_LOG_MAGIC_N_FLD as u32,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_MYSQL_LOG_OFFSET",
+        log + trx0sys::TRX_SYS_MYSQL_LOG_OFFSET as u32,
+        8,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_MYSQL_LOG_NAME",
+        log + trx0sys::TRX_SYS_MYSQL_LOG_NAME as u32,
+        trx0sys::TRX_SYS_MYSQL_LOG_NAME_LEN as u32,
+    ));
+
+    let dblwr = page_size as u32 - trx0sys::TRX_SYS_DOUBLEWRITE_END;
+    fields.push(FieldDesc::new("TRX_SYS_DOUBLEWRITE_FSEG", dblwr, fseg_size));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_DOUBLEWRITE_MAGIC",
+        dblwr + fseg_size,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_DOUBLEWRITE_BLOCK1",
+        dblwr + fseg_size + 4,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_DOUBLEWRITE_BLOCK2",
+        dblwr + fseg_size + 8,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_SYS_DOUBLEWRITE_REPEAT",
+        dblwr + fseg_size + 12,
+        12,
+    ));
+}
+
+fn describe_fsp_header(fields: &mut Vec<FieldDesc>, page_size: usize) {
+    let base = fsp0fsp::FSP_HEADER_OFFSET;
+    let list = fut0lst::FLST_BASE_NODE_SIZE;
+
+    fields.push(FieldDesc::new(
+        "FSP_SPACE_ID",
+        base + fsp0fsp::FSP_SPACE_ID,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "FSP_NOT_USED",
+        base + fsp0fsp::FSP_NOT_USED,
+        4,
+    ));
+    fields.push(FieldDesc::new("FSP_SIZE", base + fsp0fsp::FSP_SIZE, 4));
+    fields.push(FieldDesc::new(
+        "FSP_FREE_LIMIT",
+        base + fsp0fsp::FSP_FREE_LIMIT,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "FSP_SPACE_FLAGS",
+        base + fsp0fsp::FSP_SPACE_FLAGS,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "FSP_FRAG_N_USED",
+        base + fsp0fsp::FSP_FRAG_N_USED,
+        4,
+    ));
+    fields.push(FieldDesc::new("FSP_FREE", base + fsp0fsp::FSP_FREE, list));
+    fields.push(FieldDesc::new(
+        "FSP_FREE_FRAG",
+        base + fsp0fsp::FSP_FREE_FRAG,
+        list,
+    ));
+    fields.push(FieldDesc::new(
+        "FSP_FULL_FRAG",
+        base + fsp0fsp::FSP_FULL_FRAG,
+        list,
+    ));
+    fields.push(FieldDesc::new("FSP_SEG_ID", base + fsp0fsp::FSP_SEG_ID, 8));
+    fields.push(FieldDesc::new(
+        "FSP_SEG_INODES_FULL",
+        base + fsp0fsp::FSP_SEG_INODES_FULL,
+        list,
+    ));
+    fields.push(FieldDesc::new(
+        "FSP_SEG_INODES_FREE",
+        base + fsp0fsp::FSP_SEG_INODES_FREE,
+        list,
+    ));
+
+    let page_size_shift = univ::page_size_shift(page_size as u32);
+    let xdes_size = fsp0fsp::XDES_SIZE(page_size_shift);
+    let extent_size = fsp0types::FSP_EXTENT_SIZE(page_size_shift);
+    fields.push(FieldDesc::new(
+        "XDES_ARR",
+        fsp0fsp::XDES_ARR_OFFSET,
+        xdes_size * (page_size as u32 / extent_size),
+    ));
+}
+
+fn describe_trx_rseg(fields: &mut Vec<FieldDesc>, page_size: usize) {
+    let base = trx0rseg::TRX_RSEG;
+
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_FORMAT",
+        base + trx0rseg::TRX_RSEG_FORMAT,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_HISTORY_SIZE",
+        base + trx0rseg::TRX_RSEG_HISTORY_SIZE,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_HISTORY",
+        base + trx0rseg::TRX_RSEG_HISTORY,
+        fut0lst::FLST_BASE_NODE_SIZE,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_FSEG_HEADER",
+        base + trx0rseg::TRX_RSEG_FSEG_HEADER,
+        fsp0types::FSEG_HEADER_SIZE as u32,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_UNDO_SLOTS",
+        base + trx0rseg::TRX_RSEG_UNDO_SLOTS,
+        trx0rseg::TRX_RSEG_N_SLOTS(page_size) * trx0rseg::TRX_RSEG_SLOT_SIZE,
+    ));
+
+    let max_trx_id = base + trx0rseg::TRX_RSEG_MAX_TRX_ID(page_size);
+    fields.push(FieldDesc::new("TRX_RSEG_MAX_TRX_ID", max_trx_id, 8));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_BINLOG_OFFSET",
+        max_trx_id + trx0rseg::TRX_RSEG_BINLOG_OFFSET,
+        8,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_BINLOG_NAME",
+        max_trx_id + trx0rseg::TRX_RSEG_BINLOG_NAME_OFFSET,
+        trx0rseg::TRX_RSEG_BINLOG_NAME_LEN,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_WSREP_XID_FORMAT",
+        max_trx_id + trx0rseg::TRX_RSEG_WSREP_XID_FORMAT,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_WSREP_XID_GTRID_LEN",
+        max_trx_id + trx0rseg::TRX_RSEG_WSREP_XID_GTRID_LEN,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_WSREP_XID_BQUAL_LEN",
+        max_trx_id + trx0rseg::TRX_RSEG_WSREP_XID_BQUAL_LEN,
+        4,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_RSEG_WSREP_XID_DATA",
+        max_trx_id + trx0rseg::TRX_RSEG_WSREP_XID_DATA,
+        wsrep::XIDDATASIZE,
+    ));
+}
+
+fn describe_undo_page(fields: &mut Vec<FieldDesc>) {
+    let base = trx0undo::TRX_UNDO_PAGE_HDR;
+
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_PAGE_TYPE",
+        base + trx0undo::TRX_UNDO_PAGE_TYPE,
+        2,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_PAGE_START",
+        base + trx0undo::TRX_UNDO_PAGE_START,
+        2,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_PAGE_FREE",
+        base + trx0undo::TRX_UNDO_PAGE_FREE,
+        2,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_PAGE_NODE",
+        base + trx0undo::TRX_UNDO_PAGE_NODE,
+        fut0lst::FLST_NODE_SIZE,
+    ));
+
+    let seg = trx0undo::TRX_UNDO_SEG_HDR;
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_STATE",
+        seg + trx0undo::TRX_UNDO_STATE,
+        2,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_LAST_LOG",
+        seg + trx0undo::TRX_UNDO_LAST_LOG,
+        2,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_FSEG_HEADER",
+        seg + trx0undo::TRX_UNDO_FSEG_HEADER,
+        fsp0types::FSEG_HEADER_SIZE as u32,
+    ));
+    fields.push(FieldDesc::new(
+        "TRX_UNDO_PAGE_LIST",
+        seg + trx0undo::TRX_UNDO_PAGE_LIST,
+        fut0lst::FLST_BASE_NODE_SIZE,
+    ));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_describe_trx_sys_16k() {
+        let fields = describe(Struct::TrxSys, 16384);
+        let field = |name: &str| fields.iter().find(|f| f.name == name).unwrap().clone();
+
+        assert_eq!(field("FIL_HEADER"), FieldDesc::new("FIL_HEADER", 0, 38));
+        assert_eq!(field("TRX_SYS_TRX_ID_STORE").offset, 38);
+        assert_eq!(field("TRX_SYS_FSEG_HEADER").offset, 46);
+        assert_eq!(field("TRX_SYS_FSEG_HEADER").size, 10);
+        assert_eq!(field("TRX_SYS_RSEGS").offset, 56);
+        assert_eq!(field("TRX_SYS_RSEGS").size, 128 * 8);
+        assert_eq!(field("TRX_SYS_WSREP_XID_MAGIC_N_FLD").offset, 16384 - 3500);
+        assert_eq!(field("TRX_SYS_WSREP_XID_DATA").offset, 16384 - 3500 + 16);
+        assert_eq!(field("TRX_SYS_MYSQL_LOG_MAGIC_N_FLD").offset, 16384 - 1000);
+        assert_eq!(field("TRX_SYS_MYSQL_LOG_NAME").offset, 16384 - 1000 + 12);
+        assert_eq!(field("TRX_SYS_DOUBLEWRITE_FSEG").offset, 16384 - 200);
+        assert_eq!(field("TRX_SYS_DOUBLEWRITE_MAGIC").offset, 16384 - 190);
+        assert_eq!(field("FIL_TRAILER").offset, 16384 - 8);
+
+        // fields are laid out in order and don't overlap.
+        for w in fields.windows(2) {
+            assert!(w[0].offset + w[0].size <= w[1].offset, "{} {}", w[0], w[1]);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt::Debug;
+
+use crate::{fil0fil, fsp0fsp, fsp0types, keyring::Keyring, mach, page_buf::PageBuf, univ};
+
+/// Magic pattern in start of crypt data on page 0
+pub const MAGIC_SZ: usize = 6;
+pub const CRYPT_MAGIC: [u8; MAGIC_SZ] = [b's', 0xE, 0xC, b'R', b'E', b't'];
+
+/// Unencrypted tablespace
+pub const CRYPT_SCHEME_UNENCRYPTED: u8 = 0;
+/// AES-CTR / AES-CBC tablespace encryption
+pub const CRYPT_SCHEME_1: u8 = 1;
+/// IV length of CRYPT_SCHEME_1
+pub const CRYPT_SCHEME_1_IV_LEN: u8 = 16;
+
+/// Tablespace uses the innodb_encrypt_tables setting
+pub const FIL_ENCRYPTION_DEFAULT: u8 = 0;
+/// Tablespace is encrypted (ENCRYPTED=YES)
+pub const FIL_ENCRYPTION_ON: u8 = 1;
+/// Tablespace is not encrypted (ENCRYPTED=NO)
+pub const FIL_ENCRYPTION_OFF: u8 = 2;
+
+/// Size of the crypt data on page 0 with the IV of CRYPT_SCHEME_1_IV_LEN:
+/// magic(6) + type(1) + iv_length(1) + iv(16) + min_key_version(4) + key_id(4) + encryption(1)
+pub const CRYPT_DATA_SIZE: usize = MAGIC_SZ + 2 + CRYPT_SCHEME_1_IV_LEN as usize + 4 + 4 + 1;
+
+/// Returns the offset of the crypt data on page 0.
+/// See fsp_header_get_encryption_offset().
+///
+/// # Arguments
+/// * `page_size` - logical page size (innodb_page_size)
+/// * `zip_size` - ROW_FORMAT=COMPRESSED page size, or 0
+pub fn fsp_header_get_encryption_offset(page_size: usize, zip_size: usize) -> usize {
+    let page_size_shift = univ::page_size_shift(page_size as u32);
+    let xdes_size = fsp0fsp::XDES_SIZE(page_size_shift) as usize;
+    let extent_size = fsp0types::FSP_EXTENT_SIZE(page_size_shift) as usize;
+    let size = if zip_size != 0 { zip_size } else { page_size };
+
+    fsp0fsp::XDES_ARR_OFFSET as usize + xdes_size * size / extent_size
+}
+
+/// Tablespace encryption metadata stored on page 0.
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq, Eq)]
+pub struct fil_space_crypt_t {
+    /// CRYPT_SCHEME_UNENCRYPTED or CRYPT_SCHEME_1
+    pub scheme: u8,
+    pub iv: Vec<u8>,
+    /// Minimum key version used to encrypt the pages of the tablespace
+    pub min_key_version: u32,
+    pub key_id: u32,
+    /// FIL_ENCRYPTION_DEFAULT, FIL_ENCRYPTION_ON or FIL_ENCRYPTION_OFF
+    pub encryption: u8,
+}
+
+impl fil_space_crypt_t {
+    /// Reads the crypt data from page 0 of a tablespace with the given flags. Returns None
+    /// if there is no crypt data magic at the expected offset.
+    /// See fil_space_read_crypt_data().
+    pub fn from_page(page: &[u8], flags: u32) -> Option<fil_space_crypt_t> {
+        let page_size = fil0fil::logical_size(flags);
+        if page_size == 0 {
+            return None;
+        }
+
+        let offset = fsp_header_get_encryption_offset(page_size, fil0fil::zip_size(flags) as usize);
+        if page.len() < offset + CRYPT_DATA_SIZE {
+            return None;
+        }
+
+        fil_space_crypt_t::from_buf(&page[offset..])
+    }
+
+    pub fn from_buf(buf: &[u8]) -> Option<fil_space_crypt_t> {
+        if buf.len() < MAGIC_SZ + 2 || buf[..MAGIC_SZ] != CRYPT_MAGIC {
+            return None;
+        }
+
+        let scheme = buf[MAGIC_SZ];
+        let iv_length = buf[MAGIC_SZ + 1] as usize;
+        let iv_offset = MAGIC_SZ + 2;
+        if buf.len() < iv_offset + iv_length + 4 + 4 + 1 {
+            return None;
+        }
+
+        let iv = buf[iv_offset..iv_offset + iv_length].to_vec();
+        let min_key_version = mach::mach_read_from_4(&buf[iv_offset + iv_length..]);
+        let key_id = mach::mach_read_from_4(&buf[iv_offset + iv_length + 4..]);
+        let encryption = buf[iv_offset + iv_length + 8];
+
+        Some(fil_space_crypt_t {
+            scheme,
+            iv,
+            min_key_version,
+            key_id,
+            encryption,
+        })
+    }
+
+    /// Returns whether the tablespace pages are (or are being) encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption != FIL_ENCRYPTION_OFF
+            && (self.scheme == CRYPT_SCHEME_1 || self.encryption == FIL_ENCRYPTION_ON)
+    }
+
+    /// Looks up the key the page is encrypted with in the keyring. Returns None if the
+    /// page is not encrypted or the keyring does not have the key version of the page.
+    pub fn page_key(&self, page: &PageBuf, keyring: &dyn Keyring) -> Option<Vec<u8>> {
+        keyring.get_key(self.key_id, page.key_version()?)
+    }
+}
+
+impl Debug for fil_space_crypt_t {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("fil_space_crypt_t")
+            .field("scheme", &self.scheme)
+            .field("min_key_version", &self.min_key_version)
+            .field("key_id", &self.key_id)
+            .field("encryption", &self.encryption)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        keyring::MemoryKeyring,
+        page_buf::{make_page_footer, make_page_header},
+    };
+
+    #[test]
+    fn test_page_key() {
+        let crypt = fil_space_crypt_t {
+            scheme: CRYPT_SCHEME_1,
+            iv: vec![0; CRYPT_SCHEME_1_IV_LEN as usize],
+            min_key_version: 1,
+            key_id: 7,
+            encryption: FIL_ENCRYPTION_ON,
+        };
+
+        let mut keyring = MemoryKeyring::new();
+        keyring.insert(7, 1, vec![0x11; 32]);
+        keyring.insert(7, 2, vec![0x22; 32]);
+
+        let mut page = vec![0u8; 16384];
+        make_page_header(&mut page, 5, 3, fil0fil::FIL_PAGE_INDEX, 1000, 0x15).unwrap();
+        make_page_footer(&mut page).unwrap();
+        assert_eq!(crypt.page_key(&PageBuf::new(0x15, &page), &keyring), None);
+
+        mach::mach_write_to_4(
+            &mut page[fil0fil::FIL_PAGE_FCRC32_KEY_VERSION as usize..],
+            2,
+        )
+        .unwrap();
+        let encrypted = PageBuf::new(0x15, &page);
+        assert_eq!(encrypted.key_version(), Some(2));
+        assert_eq!(crypt.page_key(&encrypted, &keyring), Some(vec![0x22; 32]));
+
+        mach::mach_write_to_4(
+            &mut page[fil0fil::FIL_PAGE_FCRC32_KEY_VERSION as usize..],
+            3,
+        )
+        .unwrap();
+        assert_eq!(crypt.page_key(&PageBuf::new(0x15, &page), &keyring), None);
+    }
+}
@@ -24,6 +24,8 @@ pub const FIL_ADDR_SIZE: u32 = 6; /* address size is 6 bytes */
 
 /** File space address */
 #[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct fil_addr_t {
     /** page number within a tablespace */
     pub page: u32,
@@ -32,6 +34,18 @@ pub struct fil_addr_t {
 }
 
 impl fil_addr_t {
+    pub fn new(page: u32, boffset: u16) -> fil_addr_t {
+        fil_addr_t { page, boffset }
+    }
+
+    /// Returns the 'null' address (FIL_NULL page).
+    pub fn null() -> fil_addr_t {
+        fil_addr_t {
+            page: FIL_NULL,
+            boffset: 0,
+        }
+    }
+
     /// Create a fil_addr_t from a byte slice.
     /// The slice must be at least FIL_ADDR_SIZE bytes long.
     pub fn from_buf(buf: &[u8]) -> fil_addr_t {
@@ -44,19 +58,9 @@ impl fil_addr_t {
     pub fn is_empty(&self) -> bool {
         self.page == FIL_NULL
     }
-}
 
-impl Default for fil_addr_t {
-    fn default() -> Self {
-        fil_addr_t {
-            page: FIL_NULL,
-            boffset: 0,
-        }
-    }
-}
-
-impl Read for fil_addr_t {
-    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    /// Writes the address into the first FIL_ADDR_SIZE bytes of the buffer.
+    pub fn write_to(&self, buf: &mut [u8]) -> std::io::Result<usize> {
         if buf.len() < FIL_ADDR_SIZE as usize {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
@@ -71,6 +75,18 @@ impl Read for fil_addr_t {
     }
 }
 
+impl Default for fil_addr_t {
+    fn default() -> Self {
+        fil_addr_t::null()
+    }
+}
+
+impl Read for fil_addr_t {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.write_to(buf)
+    }
+}
+
 impl Debug for fil_addr_t {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.page == FIL_NULL {
@@ -221,7 +237,7 @@ Note: FIL_PAGE_TYPE_INSTANT maps to the same as FIL_PAGE_INDEX. */
 pub const FIL_PAGE_TYPE_LAST: u16 = FIL_PAGE_TYPE_UNKNOWN;
 
 #[allow(non_camel_case_types)]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u16)]
 pub enum fil_page_type_t {
     PageCompressedEncrypted = FIL_PAGE_PAGE_COMPRESSED_ENCRYPTED,
@@ -316,20 +332,8 @@ pub fn logical_size(flags: u32) -> usize {
     match page_ssize {
         3 => 4096,
         4 => 8192,
-        5 => {
-            if full_crc32(flags) {
-                16384
-            } else {
-                0
-            }
-        }
-        0 => {
-            if full_crc32(flags) {
-                0
-            } else {
-                16384
-            }
-        }
+        5 if full_crc32(flags) => 16384,
+        0 if !full_crc32(flags) => 16384,
         6 => 32768,
         7 => 65536,
         _ => 0,
@@ -376,6 +380,18 @@ pub fn physical_size(flags: u32, page_size: usize) -> usize {
     }
 }
 
+/// Determine the expected size of a tablespace file.
+///
+/// # Arguments
+/// * `flags` - tablespace flags (FSP_SPACE_FLAGS)
+/// * `n_pages` - number of pages in the tablespace (FSP_SIZE)
+///
+/// # Returns
+/// The file size in bytes, or 0 if the flags are invalid
+pub fn expected_file_size(flags: u32, n_pages: u32) -> u64 {
+    physical_size(flags, logical_size(flags)) as u64 * n_pages as u64
+}
+
 /// Validate the tablespace flags for full crc32 format.
 ///
 /// # Arguments
@@ -478,6 +494,28 @@ pub fn fil_page_get_type(page: &[u8]) -> u16 {
     mach::mach_read_from_2(&page[FIL_PAGE_TYPE as usize..])
 }
 
+/// Strips the memory-only bits (DATA_DIR, COMPRESSION_LEVEL) from the tablespace flags.
+/// These bits only exist in fil_space_t::flags and must not be interpreted when they are
+/// found in the on-disk FSP_SPACE_FLAGS.
+pub fn disk_flags(flags: u32) -> u32 {
+    flags & !fsp0types::FSP_FLAGS_MEM_MASK
+}
+
+/// Returns whether the tablespace was created with DATA DIRECTORY, given the on-disk
+/// FSP_SPACE_FLAGS. The DATA_DIR bit is never written to disk (the server derives it from
+/// the .isl file or the data file path), so this is always false even if bit 27 happens
+/// to be set in the raw page bytes.
+pub fn disk_flags_has_data_dir(_flags: u32) -> bool {
+    false
+}
+
+/// Returns whether the tablespace was created with DATA DIRECTORY, given the in-memory
+/// fil_space_t::flags. Do not pass the flags read from page 0 here, use
+/// `disk_flags_has_data_dir()` instead.
+pub fn mem_flags_has_data_dir(flags: u32) -> bool {
+    fsp0types::FSP_FLAGS_HAS_DATA_DIR(flags) != 0
+}
+
 pub fn tablespace_flags_to_string(flags: u32) -> String {
     let mut parts = Vec::new();
 
@@ -524,3 +562,61 @@ pub fn tablespace_flags_to_string(flags: u32) -> String {
 
     parts.join("|")
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fil_addr_t_round_trip() {
+        let mut buf = [0u8; FIL_ADDR_SIZE as usize + 2];
+
+        for addr in [
+            fil_addr_t::new(0, 0),
+            fil_addr_t::new(49, 44),
+            fil_addr_t::new(FIL_NULL - 1, u16::MAX),
+            fil_addr_t::null(),
+        ] {
+            assert_eq!(addr.write_to(&mut buf).unwrap(), FIL_ADDR_SIZE as usize);
+            assert_eq!(fil_addr_t::from_buf(&buf), addr);
+        }
+
+        fil_addr_t::null().write_to(&mut buf).unwrap();
+        assert_eq!(&buf[..6], &[0xff, 0xff, 0xff, 0xff, 0, 0]);
+        assert!(fil_addr_t::from_buf(&buf).is_empty());
+        assert_eq!(fil_addr_t::default(), fil_addr_t::null());
+
+        assert!(fil_addr_t::null().write_to(&mut buf[..5]).is_err());
+    }
+
+    #[test]
+    fn test_expected_file_size() {
+        // full_crc32, 16K pages.
+        assert_eq!(expected_file_size(0x15, 768), 768 * 16384);
+
+        // ROW_FORMAT=COMPRESSED with KEY_BLOCK_SIZE=8 in a 16K page size instance.
+        let flags = fsp0types::FSP_FLAGS_MASK_POST_ANTELOPE
+            | (4 << fsp0types::FSP_FLAGS_POS_ZIP_SSIZE)
+            | fsp0types::FSP_FLAGS_MASK_ATOMIC_BLOBS;
+        assert_eq!(zip_size(flags), 8192);
+        assert_eq!(expected_file_size(flags, 768), 768 * 8192);
+
+        // u32::MAX pages do not overflow.
+        assert_eq!(expected_file_size(0x15, u32::MAX), u32::MAX as u64 * 16384);
+    }
+
+    #[test]
+    fn test_disk_flags_data_dir() {
+        let data_dir = 1u32 << fsp0types::FSP_FLAGS_MEM_DATA_DIR;
+
+        for raw in [0x15, 0x15 | data_dir, 0x21 | data_dir, u32::MAX] {
+            assert!(!disk_flags_has_data_dir(raw), "{raw:#x}");
+            assert_eq!(disk_flags(raw) & data_dir, 0, "{raw:#x}");
+            assert!(!tablespace_flags_to_string(raw).contains("DATA_DIR"));
+        }
+
+        assert_eq!(disk_flags(0x15 | data_dir), 0x15);
+        assert!(mem_flags_has_data_dir(0x15 | data_dir));
+        assert!(!mem_flags_has_data_dir(0x15));
+    }
+}
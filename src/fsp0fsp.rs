@@ -123,6 +123,7 @@ pub const FSP_FREE_ADD: u32 = 4;
 
 #[allow(non_camel_case_types)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct fsp_header_t {
     /// space id
     pub space_id: u32,
@@ -198,6 +199,11 @@ impl fsp_header_t {
             seg_inodes_free,
         }
     }
+
+    /// Returns the number of extents in the FSP_FREE list.
+    pub fn free_extents(&self) -> u32 {
+        self.free_extens.len
+    }
 }
 
 /* @defgroup File Segment Inode Constants (moved from fsp0fsp.c) @{ */
@@ -210,10 +216,6 @@ purge we assume that a segment having only one currently used page can be
 freed in a few steps, so that the freeing cannot fill the file buffer with
 bufferfixed file pages. */
 
-#[allow(non_camel_case_types)]
-#[allow(dead_code)]
-type fseg_inode_t = u8;
-
 /// the list node for linking segment inode pages
 pub const FSEG_INODE_PAGE_NODE: u32 = fsp0types::FSEG_PAGE_DATA;
 
@@ -259,6 +261,80 @@ pub const FSEG_FREE_LIST_LIMIT: u32 = 40;
 pub const FSEG_FREE_LIST_MAX_LEN: u32 = 4;
 // @}
 
+/// Number of segment inodes which fit on a single page
+#[allow(non_snake_case)]
+pub fn FSP_SEG_INODES_PER_PAGE(page_size: u32) -> u32 {
+    let page_size_shift = univ::page_size_shift(page_size);
+    (page_size - FSEG_ARR_OFFSET - 10) / FSEG_INODE_SIZE(page_size_shift)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct fseg_inode_t {
+    /// segment id, 0 if the inode is unused
+    pub seg_id: u64,
+    /// number of used segment pages in the FSEG_NOT_FULL list
+    pub not_full_n_used: u32,
+    /// list of free extents of this segment
+    pub free: fut0lst::flst_base_node_t,
+    /// list of partially free extents
+    pub not_full: fut0lst::flst_base_node_t,
+    /// list of full extents
+    pub full: fut0lst::flst_base_node_t,
+    /// FSEG_MAGIC_N matches FSEG_MAGIC_N_BYTES
+    pub magic_ok: bool,
+    /// individual pages of the segment in fragment extents, FIL_NULL for unused slots
+    pub frag_arr: Vec<u32>,
+}
+
+impl fseg_inode_t {
+    /// Reads a segment inode from the given buffer.
+    /// The buffer must be at least `FSEG_INODE_SIZE(page_size_shift)` bytes long.
+    pub fn from_buf(buf: &[u8], page_size_shift: u32) -> fseg_inode_t {
+        assert!(buf.len() >= FSEG_INODE_SIZE(page_size_shift) as usize);
+
+        let frag_arr = (0..FSEG_FRAG_ARR_N_SLOTS(page_size_shift))
+            .map(|i| FSEG_FRAG_ARR + i * FSEG_FRAG_SLOT_SIZE)
+            .map(|offset| crate::mach::mach_read_from_4(&buf[offset as usize..]))
+            .collect();
+
+        fseg_inode_t {
+            seg_id: crate::mach::mach_read_from_8(&buf[FSEG_ID as usize..]),
+            not_full_n_used: crate::mach::mach_read_from_4(&buf[FSEG_NOT_FULL_N_USED as usize..]),
+            free: fut0lst::flst_base_node_t::from_buf(&buf[FSEG_FREE as usize..]),
+            not_full: fut0lst::flst_base_node_t::from_buf(&buf[FSEG_NOT_FULL as usize..]),
+            full: fut0lst::flst_base_node_t::from_buf(&buf[FSEG_FULL as usize..]),
+            magic_ok: buf[FSEG_MAGIC_N as usize..FSEG_MAGIC_N as usize + 4] == FSEG_MAGIC_N_BYTES,
+            frag_arr,
+        }
+    }
+
+    /// Returns true if the inode is in use.
+    pub fn is_used(&self) -> bool {
+        self.seg_id != 0
+    }
+
+    /// Returns the page numbers of the used fragment page slots.
+    pub fn frag_pages(&self) -> impl Iterator<Item = u32> + '_ {
+        self.frag_arr
+            .iter()
+            .copied()
+            .filter(|&page_no| page_no != fil0fil::FIL_NULL)
+    }
+}
+
+/// Reads the segment inode array of an inode page (FIL_PAGE_INODE).
+pub fn fseg_inode_page_entries(page: &[u8]) -> Vec<fseg_inode_t> {
+    let page_size_shift = univ::page_size_shift(page.len() as u32);
+    let inode_size = FSEG_INODE_SIZE(page_size_shift) as usize;
+
+    (0..FSP_SEG_INODES_PER_PAGE(page.len() as u32) as usize)
+        .map(|i| FSEG_ARR_OFFSET as usize + i * inode_size)
+        .map(|offset| fseg_inode_t::from_buf(&page[offset..], page_size_shift))
+        .collect()
+}
+
 /* @defgroup Extent Descriptor Constants (moved from fsp0fsp.c) @{ */
 
 /*			EXTENT DESCRIPTOR
@@ -295,6 +371,18 @@ pub const XDES_FULL_FRAG: u32 = 3; /* extent is in full fragment list of
 space */
 pub const XDES_FSEG: u32 = 4; /* extent belongs to a segment */
 
+/// Returns the name of the XDES_STATE value.
+pub fn xdes_state_name(state: u32) -> &'static str {
+    match state {
+        0 => "UNUSED",
+        XDES_FREE => "FREE",
+        XDES_FREE_FRAG => "FREE_FRAG",
+        XDES_FULL_FRAG => "FULL_FRAG",
+        XDES_FSEG => "FSEG",
+        _ => "UNKNOWN",
+    }
+}
+
 /// File extent data structure size in bytes. */
 #[allow(non_snake_case)]
 pub fn XDES_SIZE(page_size_shift: u32) -> u32 {
@@ -311,3 +399,221 @@ pub const XDES_SIZE_MIN: u32 =
 
 /// Offset of the descriptor array on a descriptor page */
 pub const XDES_ARR_OFFSET: u32 = FSP_HEADER_OFFSET + FSP_HEADER_SIZE;
+
+/// Extent descriptor entry.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct xdes_entry_t {
+    /// The identifier of the segment to which this extent belongs.
+    pub seg_id: u64,
+    /// XDES_FREE, XDES_FREE_FRAG, XDES_FULL_FRAG, XDES_FSEG or 0 if unused.
+    pub state: u32,
+    /// (XDES_FREE_BIT, XDES_CLEAN_BIT) of each page of the extent.
+    pub bitmap: Vec<(bool, bool)>,
+}
+
+impl xdes_entry_t {
+    /// Reads an extent descriptor from the given buffer.
+    /// The buffer must be at least `XDES_SIZE(page_size_shift)` bytes long.
+    pub fn from_buf(buf: &[u8], page_size_shift: u32) -> xdes_entry_t {
+        assert!(buf.len() >= XDES_SIZE(page_size_shift) as usize);
+
+        let bit = |nth: u32| {
+            let byte = buf[(XDES_BITMAP + nth / 8) as usize];
+            (byte >> (nth % 8)) & 1 != 0
+        };
+
+        let bitmap = (0..fsp0types::FSP_EXTENT_SIZE(page_size_shift))
+            .map(|i| {
+                (
+                    bit(i * XDES_BITS_PER_PAGE + XDES_FREE_BIT),
+                    bit(i * XDES_BITS_PER_PAGE + XDES_CLEAN_BIT),
+                )
+            })
+            .collect();
+
+        xdes_entry_t {
+            seg_id: crate::mach::mach_read_from_8(&buf[XDES_ID as usize..]),
+            state: crate::mach::mach_read_from_4(&buf[XDES_STATE as usize..]),
+            bitmap,
+        }
+    }
+
+    /// Returns the number of free pages of the extent according to XDES_FREE_BIT.
+    pub fn n_free(&self) -> usize {
+        self.bitmap.iter().filter(|(free, _)| *free).count()
+    }
+}
+
+impl std::fmt::Display for xdes_entry_t {
+    /// Formats the extent state and the page bitmap with one character per page:
+    /// `.` for a free page and `#` for a used one.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "state={} ({}), seg_id={}, free={}/{}, pages=",
+            xdes_state_name(self.state),
+            self.state,
+            self.seg_id,
+            self.n_free(),
+            self.bitmap.len()
+        )?;
+
+        for (free, _) in &self.bitmap {
+            f.write_str(if *free { "." } else { "#" })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the extent descriptor array of a descriptor page (FIL_PAGE_TYPE_XDES or page 0).
+/// A descriptor page describes the `page_size` pages following it.
+pub fn xdes_page_entries(page: &[u8]) -> Vec<xdes_entry_t> {
+    let page_size_shift = univ::page_size_shift(page.len() as u32);
+    let xdes_size = XDES_SIZE(page_size_shift) as usize;
+    let n_entries = page.len() / fsp0types::FSP_EXTENT_SIZE(page_size_shift) as usize;
+
+    (0..n_entries)
+        .map(|i| XDES_ARR_OFFSET as usize + i * xdes_size)
+        .map(|offset| xdes_entry_t::from_buf(&page[offset..], page_size_shift))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fsp_header_free_extents() {
+        let mut page = vec![0u8; 16384];
+        let hdr = FSP_HEADER_OFFSET as usize;
+        crate::mach::mach_write_to_4(&mut page[hdr + FSP_SPACE_ID as usize..], 5).unwrap();
+        crate::mach::mach_write_to_4(&mut page[hdr + FSP_SIZE as usize..], 768).unwrap();
+        crate::mach::mach_write_to_4(&mut page[hdr + FSP_FREE_LIMIT as usize..], 704).unwrap();
+        crate::mach::mach_write_to_4(&mut page[hdr + FSP_FRAG_N_USED as usize..], 9).unwrap();
+        crate::mach::mach_write_to_4(&mut page[hdr + FSP_FREE as usize..], 3).unwrap();
+        crate::mach::mach_write_to_4(&mut page[hdr + FSP_FREE_FRAG as usize..], 1).unwrap();
+        crate::mach::mach_write_to_8(&mut page[hdr + FSP_SEG_ID as usize..], 42).unwrap();
+
+        let header = fsp_header_t::from_page(&page);
+        assert_eq!(header.space_id, 5);
+        assert_eq!(header.space_pages, 768);
+        assert_eq!(header.free_limit, 704);
+        assert_eq!(header.free_frag_pages, 9);
+        assert_eq!(header.free_frag.len, 1);
+        assert_eq!(header.full_frag.len, 0);
+        assert_eq!(header.seg_id, 42);
+        assert_eq!(header.free_extents(), 3);
+    }
+
+    #[test]
+    fn test_fsp_header_fresh_tablespace() {
+        // The FSP header of page 0 of a freshly created 16K file-per-table tablespace.
+        #[rustfmt::skip]
+        const FSP_HEADER: [u8; FSP_HEADER_SIZE as usize] = [
+            0x00, 0x00, 0x00, 0x05, // FSP_SPACE_ID
+            0x00, 0x00, 0x00, 0x00, // FSP_NOT_USED
+            0x00, 0x00, 0x00, 0x04, // FSP_SIZE
+            0x00, 0x00, 0x00, 0x40, // FSP_FREE_LIMIT
+            0x00, 0x00, 0x00, 0x15, // FSP_SPACE_FLAGS
+            0x00, 0x00, 0x00, 0x04, // FSP_FRAG_N_USED
+            0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, // FSP_FREE
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x9e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x9e, // FSP_FREE_FRAG
+            0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, // FSP_FULL_FRAG
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, // FSP_SEG_ID
+            0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, // FSP_SEG_INODES_FULL
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x26, 0x00, 0x00, 0x00, 0x02, 0x00, 0x26, // FSP_SEG_INODES_FREE
+        ];
+
+        let mut page = vec![0u8; 16384];
+        page[FSP_HEADER_OFFSET as usize..XDES_ARR_OFFSET as usize].copy_from_slice(&FSP_HEADER);
+
+        let header = fsp_header_t::from_page(&page);
+        assert_eq!(header.space_id, 5);
+        assert_eq!(header.space_pages, 4);
+        assert_eq!(header.free_limit, 64);
+        assert_eq!(header.flags, 0x15);
+        assert_eq!(header.free_frag_pages, 4);
+        assert!(header.free_extens.is_empty());
+        assert!(header.free_extens.first.is_empty());
+        assert_eq!(header.free_extents(), 0);
+        // the first extent is the only one in the free fragment list
+        let first_xdes = fil0fil::fil_addr_t::new(0, (XDES_ARR_OFFSET + XDES_FLST_NODE) as u16);
+        assert_eq!(header.free_frag.len, 1);
+        assert!(header.free_frag.first == first_xdes);
+        assert!(header.free_frag.last == first_xdes);
+        assert!(header.full_frag.is_empty());
+        assert_eq!(header.seg_id, 3);
+        assert!(header.seg_inodes_full.is_empty());
+        let inode_page = fil0fil::fil_addr_t::new(2, FSEG_INODE_PAGE_NODE as u16);
+        assert_eq!(header.seg_inodes_free.len, 1);
+        assert!(header.seg_inodes_free.first == inode_page);
+    }
+
+    #[test]
+    fn test_fseg_inode_page_entries() {
+        let page_size = 16384;
+        let shift = univ::page_size_shift(page_size as u32);
+        let mut page = vec![0u8; page_size];
+
+        // the first inode is segment 2 with two fragment pages and one full extent.
+        let inode = FSEG_ARR_OFFSET as usize;
+        crate::mach::mach_write_to_8(&mut page[inode + FSEG_ID as usize..], 2).unwrap();
+        crate::mach::mach_write_to_4(&mut page[inode + FSEG_FULL as usize..], 1).unwrap();
+        page[inode + FSEG_MAGIC_N as usize..inode + FSEG_MAGIC_N as usize + 4]
+            .copy_from_slice(&FSEG_MAGIC_N_BYTES);
+        for i in 0..FSEG_FRAG_ARR_N_SLOTS(shift) {
+            let slot = inode + (FSEG_FRAG_ARR + i * FSEG_FRAG_SLOT_SIZE) as usize;
+            crate::mach::mach_write_to_4(&mut page[slot..], fil0fil::FIL_NULL).unwrap();
+        }
+        crate::mach::mach_write_to_4(&mut page[inode + FSEG_FRAG_ARR as usize..], 3).unwrap();
+        crate::mach::mach_write_to_4(
+            &mut page[inode + (FSEG_FRAG_ARR + 2 * FSEG_FRAG_SLOT_SIZE) as usize..],
+            5,
+        )
+        .unwrap();
+
+        let inodes = fseg_inode_page_entries(&page);
+        assert_eq!(inodes.len(), 85);
+        assert!(inodes[0].is_used());
+        assert!(inodes[0].magic_ok);
+        assert_eq!(inodes[0].seg_id, 2);
+        assert_eq!(inodes[0].full.len, 1);
+        assert!(inodes[0].free.is_empty());
+        assert_eq!(inodes[0].frag_arr.len(), 32);
+        assert_eq!(inodes[0].frag_pages().collect::<Vec<_>>(), vec![3, 5]);
+        assert!(!inodes[1].is_used());
+        assert!(!inodes[1].magic_ok);
+    }
+
+    #[test]
+    fn test_xdes_page_entries() {
+        let page_size = 16384;
+        let shift = univ::page_size_shift(page_size as u32);
+        let mut page = vec![0u8; page_size];
+
+        // the second extent belongs to segment 7 with all pages but the first two free.
+        let entry = XDES_ARR_OFFSET as usize + XDES_SIZE(shift) as usize;
+        crate::mach::mach_write_to_8(&mut page[entry + XDES_ID as usize..], 7).unwrap();
+        crate::mach::mach_write_to_4(&mut page[entry + XDES_STATE as usize..], XDES_FSEG).unwrap();
+        for i in 2..fsp0types::FSP_EXTENT_SIZE(shift) {
+            let nth = i * XDES_BITS_PER_PAGE + XDES_FREE_BIT;
+            page[entry + (XDES_BITMAP + nth / 8) as usize] |= 1 << (nth % 8);
+        }
+
+        let entries = xdes_page_entries(&page);
+        assert_eq!(entries.len(), 256);
+        assert_eq!(entries[0].state, 0);
+        assert_eq!(entries[1].seg_id, 7);
+        assert_eq!(entries[1].n_free(), 62);
+        assert_eq!(
+            entries[1].to_string(),
+            format!(
+                "state=FSEG (4), seg_id=7, free=62/64, pages=##{}",
+                ".".repeat(62)
+            )
+        );
+    }
+}
@@ -64,6 +64,7 @@ The file segment header points to the inode describing the file segment. */
 /** Data type for file segment header */
 #[allow(non_camel_case_types)]
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct fseg_header_t {
     /// space id of the inode
     pub space: u32,
@@ -94,6 +95,18 @@ impl fseg_header_t {
             offset: mach::mach_read_from_2(&buf[FSEG_HDR_OFFSET as usize..]),
         }
     }
+
+    /// Returns the space id of the inode and its address within that tablespace. The
+    /// address can be resolved with `TablespaceReader::deref_addr()` of the space.
+    pub fn as_fil_addr(&self) -> (u32, fil0fil::fil_addr_t) {
+        (
+            self.space,
+            fil0fil::fil_addr_t {
+                page: self.page_no,
+                boffset: self.offset,
+            },
+        )
+    }
 }
 
 impl Debug for fseg_header_t {
@@ -337,7 +350,8 @@ pub fn FSP_FLAGS_FCRC32_GET_COMPRESSED_ALGO(flags: u32) -> u32 {
     (flags & FSP_FLAGS_FCRC32_MASK_COMPRESSED_ALGO) >> FSP_FLAGS_FCRC32_POS_COMPRESSED_ALGO
 }
 
-/** @return the value of the DATA_DIR field */
+/** @return the value of the DATA_DIR field of the in-memory fil_space_t::flags.
+The bit does not exist in the on-disk FSP_SPACE_FLAGS, see fil0fil::disk_flags_has_data_dir(). */
 #[allow(non_snake_case)]
 pub fn FSP_FLAGS_HAS_DATA_DIR(flags: u32) -> u32 {
     flags & 1u32 << FSP_FLAGS_MEM_DATA_DIR
@@ -1,6 +1,6 @@
 use std::{fmt::Debug, io::Read};
 
-use crate::{fil0fil, mach};
+use crate::{fil0fil, fsp0fsp, mach, page0page, tablespace::TablespaceReader, trx0undo};
 
 /// The physical size of a list base node in bytes.
 pub const FLST_BASE_NODE_SIZE: u32 = 4 + 2 * fil0fil::FIL_ADDR_SIZE;
@@ -8,8 +8,14 @@ pub const FLST_BASE_NODE_SIZE: u32 = 4 + 2 * fil0fil::FIL_ADDR_SIZE;
 /// The physical size of a list node in bytes.
 pub const FLST_NODE_SIZE: u32 = 2 * fil0fil::FIL_ADDR_SIZE;
 
+/// 6-byte address of the previous list element.
+pub const FLST_PREV: u32 = 0;
+/// 6-byte address of the next list element.
+pub const FLST_NEXT: u32 = fil0fil::FIL_ADDR_SIZE;
+
 #[allow(non_camel_case_types)]
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct flst_base_node_t {
     pub len: u32,
     pub first: fil0fil::fil_addr_t,
@@ -18,11 +24,39 @@ pub struct flst_base_node_t {
 
 #[allow(non_camel_case_types)]
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct flst_node_t {
     pub prev: fil0fil::fil_addr_t,
     pub next: fil0fil::fil_addr_t,
 }
 
+/// Returns the offset of the list node of a page of the type, for the page types that
+/// are linked into a file-based list: segment inode pages (FSEG_INODE_PAGE_NODE), undo
+/// log pages (TRX_UNDO_PAGE_NODE) and ibuf free list pages (PAGE_BTR_IBUF_FREE_LIST_NODE).
+pub fn page_list_node_offset(page_type: u16) -> Option<u32> {
+    match page_type {
+        fil0fil::FIL_PAGE_INODE => Some(fsp0fsp::FSEG_INODE_PAGE_NODE),
+        fil0fil::FIL_PAGE_UNDO_LOG => {
+            Some(trx0undo::TRX_UNDO_PAGE_HDR + trx0undo::TRX_UNDO_PAGE_NODE)
+        }
+        fil0fil::FIL_PAGE_IBUF_FREE_LIST => {
+            Some(page0page::PAGE_HEADER + page0page::PAGE_BTR_IBUF_FREE_LIST_NODE)
+        }
+        _ => None,
+    }
+}
+
+/// Reads the list node of a page of the type, see `page_list_node_offset()`. Returns None
+/// for page types without a list node or if the page is shorter than `page_size`.
+pub fn page_list_node(page: &[u8], page_type: u16, page_size: usize) -> Option<flst_node_t> {
+    let offset = page_list_node_offset(page_type)? as usize;
+    if page.len() < page_size || offset + FLST_NODE_SIZE as usize > page_size {
+        return None;
+    }
+
+    Some(flst_node_t::from_buf(&page[offset..]))
+}
+
 impl flst_base_node_t {
     /// Reads a list base node from the given buffer.
     /// The buffer must be at least `FLST_BASE_NODE_SIZE` bytes long.
@@ -39,6 +73,56 @@ impl flst_base_node_t {
     }
 }
 
+/// Iterator over the nodes of a file-based list, from the first to the last. Yields the
+/// address of each node with the node itself, and stops at a null next address or after
+/// `len` nodes of the base node.
+pub struct FlstWalker<'r, 'a> {
+    reader: &'r TablespaceReader<'a>,
+    next: fil0fil::fil_addr_t,
+    remaining: u32,
+}
+
+impl<'r, 'a> FlstWalker<'r, 'a> {
+    pub fn new(reader: &'r TablespaceReader<'a>, base: &flst_base_node_t) -> Self {
+        FlstWalker {
+            reader,
+            next: base.first,
+            remaining: base.len,
+        }
+    }
+}
+
+impl Iterator for FlstWalker<'_, '_> {
+    type Item = std::io::Result<(fil0fil::fil_addr_t, flst_node_t)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 || self.next.is_empty() {
+            return None;
+        }
+
+        let addr = self.next;
+        let node = match self.reader.deref_addr(&addr) {
+            Ok(buf) if buf.len() >= FLST_NODE_SIZE as usize => flst_node_t::from_buf(buf),
+            Ok(_) => {
+                self.remaining = 0;
+                return Some(Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("list node at {addr:?} crosses the page end"),
+                )));
+            }
+            Err(err) => {
+                self.remaining = 0;
+                return Some(Err(err));
+            }
+        };
+
+        self.remaining -= 1;
+        self.next = node.next;
+
+        Some(Ok((addr, node)))
+    }
+}
+
 impl Read for flst_base_node_t {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         assert!(buf.len() >= FLST_BASE_NODE_SIZE as usize);
@@ -105,3 +189,58 @@ impl Debug for flst_node_t {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_page_list_node() {
+        let page_size = 16384;
+        let mut page = vec![0u8; page_size];
+
+        let inode = fsp0fsp::FSEG_INODE_PAGE_NODE as usize;
+        fil0fil::fil_addr_t::new(2, 38)
+            .write_to(&mut page[inode..])
+            .unwrap();
+        fil0fil::fil_addr_t::null()
+            .write_to(&mut page[inode + FLST_NEXT as usize..])
+            .unwrap();
+
+        let node = page_list_node(&page, fil0fil::FIL_PAGE_INODE, page_size).unwrap();
+        assert_eq!(node.prev, fil0fil::fil_addr_t::new(2, 38));
+        assert!(node.next.is_empty());
+
+        let undo = (trx0undo::TRX_UNDO_PAGE_HDR + trx0undo::TRX_UNDO_PAGE_NODE) as usize;
+        page.fill(0);
+        fil0fil::fil_addr_t::null()
+            .write_to(&mut page[undo..])
+            .unwrap();
+        fil0fil::fil_addr_t::new(7, undo as u16)
+            .write_to(&mut page[undo + FLST_NEXT as usize..])
+            .unwrap();
+
+        let node = page_list_node(&page, fil0fil::FIL_PAGE_UNDO_LOG, page_size).unwrap();
+        assert!(node.prev.is_empty());
+        assert_eq!(node.next, fil0fil::fil_addr_t::new(7, undo as u16));
+
+        let ibuf = 74;
+        assert_eq!(
+            page_list_node_offset(fil0fil::FIL_PAGE_IBUF_FREE_LIST),
+            Some(ibuf as u32)
+        );
+        page.fill(0);
+        fil0fil::fil_addr_t::new(3, ibuf as u16)
+            .write_to(&mut page[ibuf..])
+            .unwrap();
+        fil0fil::fil_addr_t::null()
+            .write_to(&mut page[ibuf + FLST_NEXT as usize..])
+            .unwrap();
+        let node = page_list_node(&page, fil0fil::FIL_PAGE_IBUF_FREE_LIST, page_size).unwrap();
+        assert_eq!(node.prev, fil0fil::fil_addr_t::new(3, ibuf as u16));
+        assert!(node.next.is_empty());
+
+        assert!(page_list_node(&page, fil0fil::FIL_PAGE_INDEX, page_size).is_none());
+        assert!(page_list_node(&page[..100], fil0fil::FIL_PAGE_UNDO_LOG, page_size).is_none());
+    }
+}
@@ -0,0 +1,213 @@
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Source of encryption keys, the counterpart of the MariaDB key management plugins
+/// (file_key_management, hashicorp_key_management, ...).
+pub trait Keyring {
+    /// Returns the key `key_id` of the version `key_version`, or None if it is unknown.
+    /// See encryption_key_get().
+    fn get_key(&self, key_id: u32, key_version: u32) -> Option<Vec<u8>>;
+}
+
+/// A keyring held in memory, keyed by (key_id, key_version).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryKeyring {
+    keys: BTreeMap<(u32, u32), Vec<u8>>,
+}
+
+impl MemoryKeyring {
+    pub fn new() -> MemoryKeyring {
+        MemoryKeyring::default()
+    }
+
+    pub fn insert(&mut self, key_id: u32, key_version: u32, key: Vec<u8>) {
+        self.keys.insert((key_id, key_version), key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl Keyring for MemoryKeyring {
+    fn get_key(&self, key_id: u32, key_version: u32) -> Option<Vec<u8>> {
+        self.keys.get(&(key_id, key_version)).cloned()
+    }
+}
+
+/// A keyring in the plaintext format of the file_key_management plugin. Every line is
+/// either a `key_id;hex_key` or a `key_id;hex_iv;hex_key` entry, or a `#` comment.
+/// The plugin only knows key version 1.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FileKeyring {
+    keys: MemoryKeyring,
+    ivs: BTreeMap<u32, Vec<u8>>,
+}
+
+/// The only key version provided by the file_key_management plugin.
+pub const FILE_KEY_VERSION: u32 = 1;
+
+/// Prefix of a keyring file encrypted with `openssl enc -aes-256-cbc -md sha1`.
+pub const OPENSSL_SALTED_MAGIC: &[u8] = b"Salted__";
+
+impl FileKeyring {
+    pub fn open(path: &Path) -> Result<FileKeyring> {
+        let buf = std::fs::read(path)?;
+        if buf.starts_with(OPENSSL_SALTED_MAGIC) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                "encrypted keyring files (file_key_management_filekey) are not supported",
+            ));
+        }
+
+        let text = String::from_utf8(buf).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        FileKeyring::parse(&text)
+    }
+
+    /// Parses the keyring file text. Fails on the first malformed line, reporting its
+    /// line number, like the plugin refuses to load such a file.
+    /// See Parser::parse_line() of file_key_management.
+    pub fn parse(text: &str) -> Result<FileKeyring> {
+        let mut keyring = FileKeyring::default();
+
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid =
+                |what: &str| Error::new(ErrorKind::InvalidData, format!("line {}: {what}", n + 1));
+
+            let fields = line.split(';').map(str::trim).collect::<Vec<_>>();
+            let (key_id, iv, key) = match fields[..] {
+                [key_id, key] => (key_id, None, key),
+                [key_id, iv, key] => (key_id, Some(iv), key),
+                _ => return Err(invalid("expected key_id;key or key_id;iv;key")),
+            };
+
+            let key_id = match key_id.parse::<u32>() {
+                Ok(0) | Err(_) => return Err(invalid("invalid key id")),
+                Ok(key_id) => key_id,
+            };
+            if keyring.keys.get_key(key_id, FILE_KEY_VERSION).is_some() {
+                return Err(invalid("duplicate key id"));
+            }
+
+            let key = decode_hex(key).ok_or_else(|| invalid("invalid hex key"))?;
+            if !matches!(key.len(), 16 | 24 | 32) {
+                return Err(invalid("key must be 16, 24 or 32 bytes long"));
+            }
+
+            if let Some(iv) = iv {
+                let iv = decode_hex(iv).ok_or_else(|| invalid("invalid hex iv"))?;
+                keyring.ivs.insert(key_id, iv);
+            }
+
+            keyring.keys.insert(key_id, FILE_KEY_VERSION, key);
+        }
+
+        Ok(keyring)
+    }
+
+    /// Returns the IV of the key if the entry carries one.
+    pub fn iv(&self, key_id: u32) -> Option<&[u8]> {
+        self.ivs.get(&key_id).map(Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl Keyring for FileKeyring {
+    fn get_key(&self, key_id: u32, key_version: u32) -> Option<Vec<u8>> {
+        self.keys.get_key(key_id, key_version)
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.is_empty() || !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file_keyring() {
+        let text = "\
+# keys for the test instance
+1;a7addd9adea9978fda19f21e6be987880e68ac92632ca052e5bb42b1a506939a
+
+2;000102030405060708090a0b0c0d0e0f;00112233445566778899AABBCCDDEEFF
+   # indented comment
+100 ; 0123456789abcdef0123456789abcdef0123456789abcdef
+";
+        let keyring = FileKeyring::parse(text).unwrap();
+        assert_eq!(keyring.len(), 3);
+
+        let key1 = keyring.get_key(1, FILE_KEY_VERSION).unwrap();
+        assert_eq!(key1.len(), 32);
+        assert_eq!(&key1[..4], &[0xa7, 0xad, 0xdd, 0x9a]);
+        assert_eq!(keyring.iv(1), None);
+
+        assert_eq!(
+            keyring.get_key(2, FILE_KEY_VERSION),
+            Some(vec![
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+                0xee, 0xff
+            ])
+        );
+        assert_eq!(keyring.iv(2), Some(&(0..16).collect::<Vec<u8>>()[..]));
+        assert_eq!(keyring.get_key(100, FILE_KEY_VERSION).unwrap().len(), 24);
+
+        // only version 1 exists.
+        assert_eq!(keyring.get_key(1, 2), None);
+        assert_eq!(keyring.get_key(3, FILE_KEY_VERSION), None);
+
+        let key = "00112233445566778899aabbccddeeff";
+        for malformed in [
+            format!("1;{}", &key[1..]),
+            format!("1;{}", &key[2..]),
+            format!("x;{key}"),
+            format!("0;{key}"),
+            format!("1;zz;{key}"),
+            format!("1;{key}\n1;{key}"),
+            format!("1;00;{key};00"),
+            key.to_string(),
+        ] {
+            assert!(FileKeyring::parse(&malformed).is_err(), "{malformed}");
+        }
+
+        let err = FileKeyring::parse(&format!("# ok\n1;{key}\n2;{key}0")).unwrap_err();
+        assert!(err.to_string().starts_with("line 3:"), "{err}");
+    }
+
+    #[test]
+    fn test_file_keyring_encrypted() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), b"Salted__\x01\x02\x03\x04\x05\x06\x07\x08").unwrap();
+
+        let err = FileKeyring::open(file.path()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -1,17 +1,27 @@
+pub mod audit;
 pub mod buf0buf;
 pub mod config;
+pub mod describe;
+pub mod fil0crypt;
 pub mod fil0fil;
 pub mod fsp0fsp;
 pub mod fsp0types;
 pub mod fut0lst;
+pub mod keyring;
 pub mod log;
+pub mod log0crypt;
+pub mod lsn;
 pub mod mach;
 pub mod mtr;
 pub mod mtr0log;
 pub mod mtr0types;
 pub mod page0page;
+pub mod page0zip;
 pub mod page_buf;
 pub mod ring;
+pub mod row0import;
+#[cfg(feature = "serde")]
+pub mod serde_hex;
 pub mod tablespace;
 pub mod trx0rseg;
 pub mod trx0sys;
@@ -1,19 +1,24 @@
 use std::{
     cmp::min,
-    io::Write,
+    collections::HashMap,
+    io::{Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
 use anyhow::{Context, bail};
 use crc32c::crc32c;
-use mmap_rs::{Mmap, MmapFlags, MmapOptions};
+use mmap_rs::{Mmap, MmapFlags, MmapMut, MmapOptions};
 
 use crate::{
     Lsn,
     config::Config,
-    mach,
+    keyring::Keyring,
+    log0crypt::{self, LogCryptKey},
+    lsn, mach,
     mtr::{self, MtrChain},
-    ring::{MmapRingWriter, RingReader},
+    mtr0types::{self, MtrOperation},
+    page_buf::PageBuf,
+    ring::{MmapRingWriter, RingReader, RingWriter},
 };
 
 // According to Linux "man 2 read" and "man 2 write" this applies to
@@ -52,6 +57,19 @@ pub const FORMAT_10_8: u32 = 0x5068_7973;
 /// The MariaDB 10.8.0 format with innodb_encrypt_log=ON
 pub const FORMAT_ENC_10_8: u32 = FORMAT_10_8 | FORMAT_ENCRYPTED;
 
+/// Size of a log block in the formats before 10.8
+pub const LOG_BLOCK_SIZE: usize = 512;
+/// Offset of the CRC-32C checksum of a log block before 10.8
+pub const LOG_BLOCK_CHECKSUM: usize = 508;
+/// Size of the log block header before 10.8
+pub const LOG_BLOCK_HDR_SIZE: u64 = 12;
+/// Size of the log file header before 10.8
+pub const LOG_FILE_HDR_SIZE: u64 = 2048;
+/// Location of the first checkpoint block before 10.8
+pub const LOG_CHECKPOINT_1: usize = 512;
+/// Location of the second checkpoint block before 10.8
+pub const LOG_CHECKPOINT_2: usize = 1536;
+
 /// Location of the first checkpoint block
 pub const CHECKPOINT_1: usize = 4096;
 /// Location of the second checkpoint block
@@ -68,16 +86,233 @@ pub const FIRST_LSN: Lsn = START_OFFSET;
 pub const SIZE_OF_FILE_CHECKPOINT: u64 = 3/*type,page_id*/ + 8/*LSN*/ + 1 + 4;
 
 pub struct Redo {
-    mmap: Mmap,
+    mmap: RedoMmap,
     size: u64,
     // The header of the redo log file.
     hdr: RedoHeader,
     // Checkpoint coordinates, if any.
     checkpoint: RedoCheckpointCoordinate,
+    // Key of the records of an encrypted log, see `Redo::open_with_keyring()`.
+    crypt_key: Option<LogCryptKey>,
+}
+
+/// A newly created 10.8 redo log opened for writing. See `Redo::create()`.
+pub struct RedoWriter {
+    ring: MmapRingWriter,
+    size: u64,
+    hdr: RedoHeader,
+    // LSN of the FILE_CHECKPOINT record the checkpoint blocks point at.
+    checkpoint_lsn: Lsn,
+    // LSN the next chain is written at.
+    end_lsn: Lsn,
+}
+
+fn print_warnings((redo, warnings): (Redo, Vec<RedoWarning>)) -> Redo {
+    for warning in warnings {
+        eprintln!("{warning}");
+    }
+
+    redo
+}
+
+/// Reads the log file in blocks of this size in `RedoStreamReader`.
+const STREAM_BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Size of the window of the ring `RedoStreamReader` keeps in memory. A chain is at most
+/// MTR_SIZE_MAX bytes of records plus their headers.
+const STREAM_WINDOW_SIZE: u64 = 2 * mtr::MTR_SIZE_MAX as u64;
+
+/// Parses a redo log from a `Read + Seek` source instead of a memory map, see
+/// `Redo::open_reader()`. The ring is read on demand ahead of the parse position into a
+/// fixed size window, so `parse_next()` gives the same chains as `RedoReader` without
+/// holding the whole log in memory.
+pub struct RedoStreamReader<R> {
+    r: R,
+    size: u64,
+    hdr: RedoHeader,
+    checkpoint: RedoCheckpointCoordinate,
+    // Key of the records of an encrypted log, see `Redo::open_reader_with_keyring()`.
+    crypt_key: Option<LogCryptKey>,
+    // The window of the ring, the byte of an LSN is at `lsn % STREAM_WINDOW_SIZE`.
+    buf: Vec<u8>,
+    // The window is read up to this LSN.
+    loaded_lsn: Lsn,
+    // LSN of the next chain.
+    lsn: Lsn,
+}
+
+/// Read-only (Redo::open) or writable (Redo::open_mut) mapping of the log file.
+enum RedoMmap {
+    ReadOnly(Mmap),
+    ReadWrite(MmapMut),
+}
+
+impl RedoMmap {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            RedoMmap::ReadOnly(m) => m.as_slice(),
+            RedoMmap::ReadWrite(m) => m.as_slice(),
+        }
+    }
+}
+
+/// Where a page LSN falls relative to the redo log checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageLsnState {
+    /// At or before the checkpoint: the page changes are fully flushed.
+    Flushed,
+    /// Between the checkpoint and the log end: recovery must apply redo to the page.
+    NeedsRedo,
+    /// Beyond the log end: the page is from the future (corruption or a wrong log).
+    Future,
+}
+
+/// Page LSN decoded against a redo log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageLsnInfo {
+    pub page_lsn: Lsn,
+    /// The number of times the log wrapped around before reaching the LSN.
+    pub generation: u64,
+    /// The file offset the LSN maps to.
+    pub offset: u64,
+    pub state: PageLsnState,
+}
+
+/// How much of the ring is occupied by the log written since the checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedoFillStats {
+    pub capacity: u64,
+    pub checkpoint_lsn: Lsn,
+    pub end_lsn: Lsn,
+    /// Checkpoint age: bytes between the checkpoint and the log end.
+    pub used: u64,
+}
+
+impl RedoFillStats {
+    /// Returns the used portion of the ring in percent.
+    pub fn fill_pct(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+
+        self.used as f64 * 100.0 / self.capacity as f64
+    }
+
+    /// Returns a warning if the used portion of the ring exceeds `threshold_pct`, which means
+    /// the server was close to a checkpoint stall (or the log was overwritten).
+    pub fn fill_warning(&self, threshold_pct: u8) -> Option<String> {
+        if self.used as u128 * 100 <= self.capacity as u128 * threshold_pct as u128 {
+            return None;
+        }
+
+        Some(format!(
+            "redo log is {:.1}% full (checkpoint age {} of {} bytes, threshold {}%): the \
+             server was close to a checkpoint stall",
+            self.fill_pct(),
+            self.used,
+            self.capacity,
+            threshold_pct
+        ))
+    }
+}
+
+/// The header, the checkpoint and the chains of a log. See `Redo::dump()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RedoDump {
+    pub header: RedoHeader,
+    pub checkpoint: RedoCheckpointCoordinate,
+    pub chains: Vec<MtrChain>,
+}
+
+#[cfg(feature = "json")]
+impl RedoDump {
+    /// Serializes the dump as a JSON array of the header, the checkpoint and then the
+    /// chains in log order.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let mut items = Vec::with_capacity(2 + self.chains.len());
+        items.push(serde_json::to_value(&self.header)?);
+        items.push(serde_json::to_value(&self.checkpoint)?);
+        for chain in &self.chains {
+            items.push(serde_json::to_value(chain)?);
+        }
+
+        serde_json::to_string(&items)
+    }
+}
+
+/// Result of `Redo::verify()`: a scan over all chains from the checkpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RedoVerification {
+    /// Number of valid chains.
+    pub chains: usize,
+    pub records: usize,
+    /// Number of chains with an invalid checksum or a malformed record.
+    pub corrupted: usize,
+    /// LSN of the first chain.
+    pub first_lsn: Option<Lsn>,
+    /// End LSN of the valid chains up to the first corrupted one.
+    pub end_lsn: Option<Lsn>,
+    /// LSN of the first corrupted chain.
+    pub first_corruption_lsn: Option<Lsn>,
+    pub checkpoint_lsn: Option<Lsn>,
+    /// Checkpoint LSN named by the FILE_CHECKPOINT record found at the end LSN of the
+    /// checkpoint block.
+    pub file_checkpoint_lsn: Option<Lsn>,
+    /// The first checksum mismatch or malformed record.
+    pub error: Option<String>,
+}
+
+impl RedoVerification {
+    /// Returns true if the FILE_CHECKPOINT record at the end LSN of the checkpoint block
+    /// names the checkpoint LSN.
+    pub fn checkpoint_matches(&self) -> bool {
+        self.checkpoint_lsn.is_some() && self.file_checkpoint_lsn == self.checkpoint_lsn
+    }
+
+    /// Returns true if all chains were parsed up to the end of the log.
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none() && self.corrupted == 0
+    }
+}
+
+impl std::fmt::Display for RedoVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lsn = |lsn: Option<Lsn>| lsn.map_or("none".to_string(), |lsn| lsn.to_string());
+
+        write!(
+            f,
+            "chains={} records={} corrupted={} first_lsn={} end_lsn={} \
+             first_corruption_lsn={} checkpoint_lsn={} file_checkpoint_lsn={} \
+             checkpoint_matches={} ok={}",
+            self.chains,
+            self.records,
+            self.corrupted,
+            lsn(self.first_lsn),
+            lsn(self.end_lsn),
+            lsn(self.first_corruption_lsn),
+            lsn(self.checkpoint_lsn),
+            lsn(self.file_checkpoint_lsn),
+            self.checkpoint_matches(),
+            self.is_ok()
+        )?;
+
+        if let Some(error) = &self.error {
+            write!(f, " error={error:?}")?;
+        }
+
+        Ok(())
+    }
 }
 
+/// Parses the mini-transaction chains of a log. As an `Iterator` it yields the chains
+/// up to the end of the log, or up to and including the first parse error.
 pub struct RedoReader<'a> {
     reader: RingReader<'a>,
+    crypt: Option<RedoCrypt>,
+    crypt_key: Option<LogCryptKey>,
+    done: bool,
 }
 
 // Offsets of a log file header.
@@ -101,29 +336,99 @@ pub const LOG_HEADER_CRC: usize = 508;
 // Redo log encryption key ID.
 pub const LOG_DEFAULT_ENCRYPTION_KEY: u32 = 1;
 
+// Size of the AES block and of the encrypted random message the log key is derived from.
+pub const MY_AES_BLOCK_SIZE: usize = 16;
+
+// Offsets of the encryption info after LOG_HEADER_CREATOR_END.
+// See log_crypt_write_header().
+pub const LOG_CRYPT_KEY_ID: usize = 0;
+pub const LOG_CRYPT_KEY_VERSION: usize = 4;
+pub const LOG_CRYPT_MSG: usize = 8;
+pub const LOG_CRYPT_NONCE: usize = LOG_CRYPT_MSG + MY_AES_BLOCK_SIZE;
+pub const LOG_CRYPT_NONCE_SIZE: usize = 4;
+
+/// Encryption info of an innodb_encrypt_log=ON redo log, see crypt_info_t. The log key is
+/// the crypt message encrypted with the key `key_version` of `key_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RedoCrypt {
+    /// Always LOG_DEFAULT_ENCRYPTION_KEY.
+    pub key_id: u32,
+    pub key_version: u32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_hex::serialize")
+    )]
+    pub crypt_msg: [u8; MY_AES_BLOCK_SIZE],
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_hex::serialize")
+    )]
+    pub crypt_nonce: [u8; LOG_CRYPT_NONCE_SIZE],
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RedoHeader {
     pub version: u32,
     pub first_lsn: Lsn,
     pub creator: String,
     pub crc: u32,
+    /// Encryption info of an encrypted 10.8 log, stored after the creator.
+    pub crypt: Option<RedoCrypt>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RedoCheckpointCoordinate {
     pub checkpoints: [RedoHeaderCheckpoint; 2],
     pub checkpoint_lsn: Option<Lsn>,
     // Position of the checkpoint block entry in the log file.
     // can be CHECKPOINT_1 or CHECKPOINT_2.
     pub checkpoint_no: Option<usize>,
+    // Byte offset of the checkpoint LSN in the log file, in the formats before 10.8
+    // where it is stored in the checkpoint block.
+    pub checkpoint_offset: Option<u64>,
     pub end_lsn: Lsn,
     pub encrypted: bool,
+    /// Encryption info of the log, if `encrypted`.
+    pub crypt: Option<RedoCrypt>,
     pub version: u32,
     // Redo log is after a restore operation.
     pub start_after_restore: bool,
 }
 
+/// A recoverable problem found while parsing the redo log header. See
+/// `Redo::open_with_warnings()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedoWarning {
+    /// The checkpoint block at `pos` is ignored or could not be trusted.
+    InvalidCheckpoint { pos: usize, reason: String },
+    /// The ring is smaller than the largest mini-transaction (`mtr::MTR_SIZE_MAX`), so a
+    /// single mini-transaction may overwrite itself and the log can not be scanned.
+    SmallCapacity { capacity: Lsn },
+}
+
+impl std::fmt::Display for RedoWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RedoWarning::InvalidCheckpoint { pos, reason } => {
+                write!(f, "InnoDB: Invalid checkpoint at {pos}: {reason}")
+            }
+            RedoWarning::SmallCapacity { capacity } => {
+                write!(
+                    f,
+                    "redo log capacity {capacity} is smaller than the maximum mini-transaction \
+                     size {}",
+                    mtr::MTR_SIZE_MAX
+                )
+            }
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RedoHeaderCheckpoint {
     pub checkpoint_lsn: Lsn,
     pub end_lsn: Lsn,
@@ -132,7 +437,108 @@ pub struct RedoHeaderCheckpoint {
 
 impl Redo {
     pub fn open(log_file_path: &Path) -> anyhow::Result<Redo> {
-        let log_file = std::fs::File::open(log_file_path)
+        Self::open_with(log_file_path, false).map(print_warnings)
+    }
+
+    /// Opens the redo log for modification (i.e. `advance_checkpoint()`).
+    pub fn open_mut(log_file_path: &Path) -> anyhow::Result<Redo> {
+        Self::open_with(log_file_path, true).map(print_warnings)
+    }
+
+    /// Opens the redo log like `open()` and derives the key of the records of an encrypted
+    /// log from the keyring. Fails if the keyring does not have the key version the log
+    /// was encrypted with.
+    pub fn open_with_keyring(log_file_path: &Path, keyring: &dyn Keyring) -> anyhow::Result<Redo> {
+        let mut redo = Self::open(log_file_path)?;
+
+        if let Some(crypt) = &redo.checkpoint.crypt {
+            redo.crypt_key = Some(
+                log0crypt::init_crypt_key(crypt, keyring)
+                    .with_context(|| format!("decrypt {}", log_file_path.display()))?,
+            );
+        }
+
+        Ok(redo)
+    }
+
+    /// Opens the redo log like `open()`, but returns the header parsing warnings instead of
+    /// printing them to stderr.
+    pub fn open_with_warnings(log_file_path: &Path) -> anyhow::Result<(Redo, Vec<RedoWarning>)> {
+        Self::open_with(log_file_path, false)
+    }
+
+    /// Opens a single redo log file of `size` bytes from a reader, for when the file can't
+    /// be memory mapped (i.e. a network stream or an archive member). Only the header is
+    /// read here; the records are read while parsing.
+    pub fn open_reader<R: Read + Seek>(mut r: R, size: u64) -> anyhow::Result<RedoStreamReader<R>> {
+        if size < START_OFFSET + SIZE_OF_FILE_CHECKPOINT {
+            bail!(
+                "log file is too small: {size} bytes, expected at least {} bytes",
+                START_OFFSET + SIZE_OF_FILE_CHECKPOINT
+            );
+        }
+
+        let mut buf = vec![0u8; START_OFFSET as usize];
+        r.seek(std::io::SeekFrom::Start(0))
+            .context("seek to the log header")?;
+        r.read_exact(&mut buf).context("read log header")?;
+
+        let hdr = Redo::parse_header(&buf).context("parse header")?;
+        if size <= hdr.first_lsn {
+            bail!(
+                "log file is truncated: {size} bytes, the records start at {}",
+                hdr.first_lsn
+            );
+        }
+
+        let mut warnings = vec![];
+        let checkpoint = Redo::parse_header_checkpoint_with_warnings(&buf, &hdr, 0, &mut warnings)
+            .context("parse redo log checkpoint")?;
+        for warning in warnings {
+            eprintln!("{warning}");
+        }
+
+        if !is_latest(hdr.version) {
+            bail!(
+                "streaming is only supported for {FORMAT_10_8:#x} logs, found {:#x}",
+                hdr.version
+            );
+        }
+
+        let lsn = checkpoint.checkpoint_lsn.unwrap_or(hdr.first_lsn);
+
+        Ok(RedoStreamReader {
+            r,
+            size,
+            hdr,
+            checkpoint,
+            crypt_key: None,
+            buf: vec![0u8; STREAM_WINDOW_SIZE as usize],
+            loaded_lsn: lsn,
+            lsn,
+        })
+    }
+
+    /// Opens a streaming reader like `open_reader()` and derives the key of the records of
+    /// an encrypted log like `open_with_keyring()`.
+    pub fn open_reader_with_keyring<R: Read + Seek>(
+        r: R,
+        size: u64,
+        keyring: &dyn Keyring,
+    ) -> anyhow::Result<RedoStreamReader<R>> {
+        let mut reader = Redo::open_reader(r, size)?;
+        if let Some(crypt) = &reader.checkpoint.crypt {
+            reader.crypt_key = Some(log0crypt::init_crypt_key(crypt, keyring)?);
+        }
+
+        Ok(reader)
+    }
+
+    fn open_with(log_file_path: &Path, writable: bool) -> anyhow::Result<(Redo, Vec<RedoWarning>)> {
+        let log_file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(writable)
+            .open(log_file_path)
             .with_context(|| format!("open log file at {}", log_file_path.display()))?;
         let log_meta = log_file.metadata().context("get metadata for log a file")?;
         let log_size = log_meta.len();
@@ -146,13 +552,16 @@ impl Redo {
             ));
         }
 
-        let mmap = unsafe {
+        let options = unsafe {
             MmapOptions::new(log_size as usize)
                 .context("mmap option")?
                 .with_file(&log_file, 0u64)
                 .with_flags(MmapFlags::SHARED)
-                .map()
-                .context("mmap log file")?
+        };
+        let mmap = if writable {
+            RedoMmap::ReadWrite(options.map_mut().context("mmap log file")?)
+        } else {
+            RedoMmap::ReadOnly(options.map().context("mmap log file")?)
         };
 
         let multiple_log_files = Self::search_multiple_log_files(
@@ -173,15 +582,30 @@ impl Redo {
         }
 
         let hdr = Redo::parse_header(mmap.as_slice()).context("parse header")?;
-        let checkpoint = Redo::parse_header_checkpoint(mmap.as_slice(), &hdr, multiple_log_files)
-            .context("parse redo log checkpoint")?;
+        let mut warnings = vec![];
+        let checkpoint = Redo::parse_header_checkpoint_with_warnings(
+            mmap.as_slice(),
+            &hdr,
+            multiple_log_files,
+            &mut warnings,
+        )
+        .context("parse redo log checkpoint")?;
 
-        Ok(Redo {
+        let redo = Redo {
             mmap,
             size: log_size,
             hdr,
             checkpoint,
-        })
+            crypt_key: None,
+        };
+
+        if redo.is_latest() && redo.capacity() < mtr::MTR_SIZE_MAX as Lsn {
+            warnings.push(RedoWarning::SmallCapacity {
+                capacity: redo.capacity(),
+            });
+        }
+
+        Ok((redo, warnings))
     }
 
     pub fn buf(&self) -> &[u8] {
@@ -200,6 +624,12 @@ impl Redo {
         &self.checkpoint
     }
 
+    /// Returns the key of the records of an encrypted log opened with
+    /// `Redo::open_with_keyring()`.
+    pub fn crypt_key(&self) -> Option<&LogCryptKey> {
+        self.crypt_key.as_ref()
+    }
+
     fn search_multiple_log_files(dir: PathBuf, size: u64) -> anyhow::Result<usize> {
         let mut found = 0;
 
@@ -228,6 +658,23 @@ impl Redo {
         Ok(found)
     }
 
+    /// Parses the header and the checkpoint of a single redo log file from a byte slice
+    /// holding at least its first `START_OFFSET` bytes, without touching the filesystem.
+    pub fn inspect(buf: &[u8]) -> anyhow::Result<(RedoHeader, RedoCheckpointCoordinate)> {
+        if buf.len() < START_OFFSET as usize {
+            bail!(
+                "redo log buffer is too small: {} bytes, expected at least {START_OFFSET} bytes",
+                buf.len()
+            );
+        }
+
+        let hdr = Redo::parse_header(buf).context("parse header")?;
+        let checkpoint =
+            Redo::parse_header_checkpoint(buf, &hdr, 0).context("parse redo log checkpoint")?;
+
+        Ok((hdr, checkpoint))
+    }
+
     pub fn parse_header(buf: &[u8]) -> anyhow::Result<RedoHeader> {
         if buf.len() < 512 {
             return Err(anyhow::anyhow!(
@@ -250,11 +697,20 @@ impl Redo {
             }
         }
 
+        // An encrypted 10.8 log keeps its format version, see log_crypt_read_header().
+        let crypt = if is_latest(version) {
+            Redo::parse_crypt_header(&buf[LOG_HEADER_CREATOR_END..])
+                .context("parse log encryption info")?
+        } else {
+            None
+        };
+
         Ok(RedoHeader {
             version,
             first_lsn,
             creator,
             crc,
+            crypt,
         })
     }
 
@@ -262,6 +718,27 @@ impl Redo {
         buf: &[u8],
         hdr: &RedoHeader,
         multiple_log_files: usize,
+    ) -> anyhow::Result<RedoCheckpointCoordinate> {
+        let mut warnings = vec![];
+        let checkpoint = Redo::parse_header_checkpoint_with_warnings(
+            buf,
+            hdr,
+            multiple_log_files,
+            &mut warnings,
+        );
+        for warning in warnings {
+            eprintln!("{warning}");
+        }
+
+        checkpoint
+    }
+
+    /// Same as `parse_header_checkpoint()`, but collects the warnings instead of printing them.
+    pub fn parse_header_checkpoint_with_warnings(
+        buf: &[u8],
+        hdr: &RedoHeader,
+        multiple_log_files: usize,
+        warnings: &mut Vec<RedoWarning>,
     ) -> anyhow::Result<RedoCheckpointCoordinate> {
         let mut checkpoint = RedoCheckpointCoordinate {
             checkpoints: [
@@ -270,8 +747,10 @@ impl Redo {
             ],
             checkpoint_lsn: None,
             checkpoint_no: None,
+            checkpoint_offset: None,
             end_lsn: hdr.first_lsn,
             encrypted: false,
+            crypt: None,
             version: hdr.version,
             start_after_restore: false,
         };
@@ -293,14 +772,23 @@ impl Redo {
                 let whatever_it_is = mach::mach_read_from_4(&buf[LOG_HEADER_CREATOR_END..]);
                 if whatever_it_is == 0 {
                     // all good
-                } else if !Redo::parse_crypt_header(&buf[LOG_HEADER_CREATOR_END..])? {
+                } else if let Some(crypt) = Redo::parse_crypt_header(&buf[LOG_HEADER_CREATOR_END..])
+                    .with_context(|| {
+                        format!(
+                            "InnoDB: Reading log encryption info failed; the log was created \
+                             with {}",
+                            hdr.creator
+                        )
+                    })?
+                {
+                    checkpoint.version = FORMAT_ENC_10_8;
+                    checkpoint.encrypted = true;
+                    checkpoint.crypt = Some(crypt);
+                } else {
                     bail!(
                         "InnoDB: Reading log encryption info failed; the log was created with {}",
                         hdr.creator
                     );
-                } else {
-                    checkpoint.version = FORMAT_ENC_10_8;
-                    checkpoint.encrypted = true;
                 }
 
                 let step = CHECKPOINT_2 - CHECKPOINT_1;
@@ -315,17 +803,30 @@ impl Redo {
                     let reserved = &buf[pos + 16..pos + 60];
                     let checksum = mach::mach_read_from_4(&buf[pos + 60..]);
 
-                    if checkpoint_lsn < hdr.first_lsn
-                        || end_lsn < checkpoint_lsn
-                        || reserved != [0; 44]
-                        || checksum != crc32c(&buf[pos..pos + 60])
-                    {
-                        writeln!(
-                            std::io::stderr(),
-                            "InnoDB: Invalid checkpoint at {pos}: \
-                             checkpoint_lsn={checkpoint_lsn}, end_lsn={end_lsn}, \
-                             reserved={reserved:?}, checksum={checksum}"
-                        )?;
+                    let mut reasons = vec![];
+                    if checkpoint_lsn < hdr.first_lsn {
+                        reasons.push(format!(
+                            "checkpoint_lsn={checkpoint_lsn} is below first_lsn={}",
+                            hdr.first_lsn
+                        ));
+                    }
+                    if end_lsn < checkpoint_lsn {
+                        reasons.push(format!(
+                            "end_lsn={end_lsn} is below checkpoint_lsn={checkpoint_lsn}"
+                        ));
+                    }
+                    if reserved != [0; 44] {
+                        reasons.push("reserved bytes are not zero".to_string());
+                    }
+                    let crc = crc32c(&buf[pos..pos + 60]);
+                    if checksum != crc {
+                        reasons.push(format!("checksum={checksum} does not match {crc}"));
+                    }
+                    if !reasons.is_empty() {
+                        warnings.push(RedoWarning::InvalidCheckpoint {
+                            pos,
+                            reason: reasons.join(", "),
+                        });
                     }
 
                     if checkpoint_lsn >= checkpoint.checkpoint_lsn.unwrap_or(0) {
@@ -353,42 +854,65 @@ impl Redo {
                     bail!("InnoDB: Expecting only ib_logfile0, but multiple log files found");
                 }
 
-                let log_size = ((buf.len() - 2048) * multiple_log_files) as Lsn;
-                for pos in (512_usize..2048).step_by(1024) {
-                    let crc = mach::mach_read_from_4(&buf[pos + LOG_HEADER_CRC..]);
-                    let (ok, hdr_crc) = verify_crc_block(&buf[pos..pos + 512], crc);
+                // Only read-only inspection: the checkpoint blocks are parsed, but the
+                // log is not recovered (see recv_log_recover_10_5()) nor upgraded.
+                if checkpoint.version & FORMAT_ENCRYPTED != 0 {
+                    checkpoint.encrypted = true;
+                }
+
+                let log_size = buf.len() as Lsn * (1 + multiple_log_files) as Lsn;
+                let step = LOG_CHECKPOINT_2 - LOG_CHECKPOINT_1;
+                for pos in (LOG_CHECKPOINT_1..=LOG_CHECKPOINT_2).step_by(step) {
+                    let crc = mach::mach_read_from_4(&buf[pos + LOG_BLOCK_CHECKSUM..]);
+                    let (ok, hdr_crc) = verify_crc_block(&buf[pos..pos + LOG_BLOCK_SIZE], crc);
                     if !ok {
-                        writeln!(
-                            std::io::stderr(),
-                            "InnoDB: Invalid checkpoint checksum at {pos}: expected {crc}, got \
-                             {hdr_crc}"
-                        )?;
+                        warnings.push(RedoWarning::InvalidCheckpoint {
+                            pos,
+                            reason: format!("checksum mismatch: expected {crc}, got {hdr_crc}"),
+                        });
                         continue;
                     }
 
-                    // TODO: if (log_sys.is_encrypted() && !log_crypt_read_checkpoint_buf(b))
-                    if checkpoint.version & FORMAT_ENCRYPTED != 0 {
-                        checkpoint.encrypted = true;
-                        todo!("Handle encrypted log header parsing");
-                        //  sql_print_error("InnoDB: Reading checkpoint encryption info failed./       continue;
-                    }
-
+                    // The checkpoint number, LSN and offset are not encrypted; the crypt
+                    // info of encrypted logs (log_crypt_read_checkpoint_buf()) is skipped.
                     let checkpoint_no = mach::mach_read_from_8(&buf[pos..]) as usize;
                     let checkpoint_lsn: Lsn = mach::mach_read_from_8(&buf[pos + 8..]);
-                    let end_lsn: Lsn = mach::mach_read_from_8(&buf[pos + 16..]);
+                    // Byte offset of checkpoint_lsn in the log file (group).
+                    let lsn_offset = mach::mach_read_from_8(&buf[pos + 16..]);
 
                     writeln!(
                         std::io::stderr(),
                         "InnoDB: checkpoint {checkpoint_no} at LSN {checkpoint_lsn} found",
                     )?;
 
-                    if checkpoint_no >= checkpoint.checkpoint_no.unwrap_or(0)
-                        && end_lsn >= 0x80c
-                        && (end_lsn & !511) + 512 < log_size
+                    checkpoint.checkpoints[(pos - LOG_CHECKPOINT_1) / step] =
+                        RedoHeaderCheckpoint {
+                            checkpoint_lsn,
+                            end_lsn: checkpoint_lsn,
+                            checksum: crc,
+                        };
+
+                    if lsn_offset < LOG_FILE_HDR_SIZE + LOG_BLOCK_HDR_SIZE
+                        || (lsn_offset & !(LOG_BLOCK_SIZE as u64 - 1)) + LOG_BLOCK_SIZE as u64
+                            >= log_size
                     {
+                        warnings.push(RedoWarning::InvalidCheckpoint {
+                            pos,
+                            reason: format!(
+                                "checkpoint offset {lsn_offset} is out of the log of {log_size} \
+                                 bytes"
+                            ),
+                        });
+                        continue;
+                    }
+
+                    if checkpoint_no >= checkpoint.checkpoint_no.unwrap_or(0) {
                         checkpoint.checkpoint_lsn = Some(checkpoint_lsn);
                         checkpoint.checkpoint_no = Some(checkpoint_no);
-                        checkpoint.end_lsn = end_lsn; // log_offset
+                        checkpoint.checkpoint_offset = Some(lsn_offset);
+                        // The log end is only known after scanning the log blocks from the
+                        // checkpoint, which is not implemented for these formats.
+                        checkpoint.end_lsn = checkpoint_lsn;
                     }
                 }
 
@@ -398,10 +922,6 @@ impl Redo {
                         hdr.creator
                     );
                 }
-
-                // TODO: if (dberr_t err= recv_log_recover_10_5(lsn_offset)) {}
-                todo!("Handle log recovery for <=10.5 formats");
-                // TODO: upgrade
             }
             _ => {
                 bail!(
@@ -423,14 +943,107 @@ impl Redo {
 
     // Read the encryption information from a log header buffer.
     // See log_crypt_read_header().
-    pub fn parse_crypt_header(hdr: &[u8]) -> anyhow::Result<bool> {
-        let encryption_key = mach::mach_read_from_4(hdr);
+    pub fn parse_crypt_header(hdr: &[u8]) -> anyhow::Result<Option<RedoCrypt>> {
+        if hdr.len() < LOG_CRYPT_NONCE + LOG_CRYPT_NONCE_SIZE {
+            bail!("log encryption info is truncated: {} bytes", hdr.len());
+        }
+
+        let encryption_key = mach::mach_read_from_4(&hdr[LOG_CRYPT_KEY_ID..]);
         if encryption_key != LOG_DEFAULT_ENCRYPTION_KEY {
             // No encryption.
-            return Ok(false);
+            return Ok(None);
+        }
+
+        let key_version = mach::mach_read_from_4(&hdr[LOG_CRYPT_KEY_VERSION..]);
+        // ENCRYPTION_KEY_NOT_ENCRYPTED and ENCRYPTION_KEY_VERSION_INVALID
+        if key_version == 0 || key_version == u32::MAX {
+            bail!("invalid log encryption key version {key_version}");
+        }
+
+        let mut crypt_msg = [0u8; MY_AES_BLOCK_SIZE];
+        crypt_msg.copy_from_slice(&hdr[LOG_CRYPT_MSG..LOG_CRYPT_MSG + MY_AES_BLOCK_SIZE]);
+        if crypt_msg == [0; MY_AES_BLOCK_SIZE] {
+            bail!("log encryption message is empty");
+        }
+
+        let mut crypt_nonce = [0u8; LOG_CRYPT_NONCE_SIZE];
+        crypt_nonce.copy_from_slice(&hdr[LOG_CRYPT_NONCE..LOG_CRYPT_NONCE + LOG_CRYPT_NONCE_SIZE]);
+
+        Ok(Some(RedoCrypt {
+            key_id: encryption_key,
+            key_version,
+            crypt_msg,
+            crypt_nonce,
+        }))
+    }
+
+    /// Validates that the record area of a newly created log starts at or after
+    /// `FIRST_LSN`, the same invariant the reader enforces on the header block.
+    pub fn validate_first_lsn(first_lsn: Lsn) -> anyhow::Result<()> {
+        if first_lsn < FIRST_LSN {
+            bail!("first LSN {first_lsn} must not be below {FIRST_LSN}");
+        }
+
+        Ok(())
+    }
+
+    /// Validates a checkpoint at `lsn` of a log file of `size` bytes with the record area
+    /// starting at `first_lsn`: the ring must hold the FILE_CHECKPOINT record with its end
+    /// marker and `lsn` must not be below `first_lsn`. The LSNs keep growing as the log
+    /// wraps, so a checkpoint past the first pass over the ring is valid.
+    pub fn validate_checkpoint_lsn(first_lsn: Lsn, size: u64, lsn: Lsn) -> anyhow::Result<()> {
+        if size <= first_lsn {
+            bail!("log file size {size} must be greater than the first LSN {first_lsn}");
+        }
+
+        let capacity = size - first_lsn;
+        if capacity <= SIZE_OF_FILE_CHECKPOINT {
+            bail!(
+                "log capacity {capacity} can not hold the FILE_CHECKPOINT record of \
+                 {SIZE_OF_FILE_CHECKPOINT} bytes"
+            );
         }
 
-        todo!("Handle log encryption header parsing");
+        if lsn < first_lsn {
+            bail!("checkpoint LSN {lsn} is below the first LSN {first_lsn}");
+        }
+
+        if lsn.checked_add(SIZE_OF_FILE_CHECKPOINT).is_none() {
+            bail!("checkpoint LSN {lsn} leaves no room for the FILE_CHECKPOINT record");
+        }
+
+        Ok(())
+    }
+
+    /// Creates an unencrypted 10.8 redo log of `size` bytes with the record area starting
+    /// at `first_lsn`. Both checkpoint blocks point at a FILE_CHECKPOINT record at
+    /// `first_lsn`, like a log created by the server. An existing file is truncated.
+    pub fn create(path: &Path, size: u64, first_lsn: Lsn) -> anyhow::Result<RedoWriter> {
+        Redo::validate_first_lsn(first_lsn)?;
+        Redo::validate_checkpoint_lsn(first_lsn, size, first_lsn)?;
+
+        let hdr = RedoHeader {
+            version: FORMAT_10_8,
+            first_lsn,
+            creator: "mdbutil".to_string(),
+            crc: 0,
+            crypt: None,
+        };
+        let header = hdr.to_bytes()?;
+
+        let mut ring = Redo::writer(path, first_lsn as usize, size)?;
+        ring.writer().write_all(&header)?;
+
+        let mut log = RedoWriter {
+            ring,
+            size,
+            hdr: Redo::parse_header(&header)?,
+            checkpoint_lsn: first_lsn,
+            end_lsn: first_lsn,
+        };
+        log.write_checkpoint(first_lsn)?;
+
+        Ok(log)
     }
 
     pub fn writer(file: &Path, header: usize, size: u64) -> anyhow::Result<MmapRingWriter> {
@@ -469,11 +1082,10 @@ impl Redo {
         };
 
         RedoReader {
-            reader: RingReader::buf_at(
-                self.mmap.as_slice(),
-                self.hdr.first_lsn as usize,
-                lsn as usize,
-            ),
+            reader: RingReader::buf_at(self.mmap.as_slice(), self.hdr.first_lsn as usize, lsn),
+            crypt: self.checkpoint.crypt.clone(),
+            crypt_key: self.crypt_key,
+            done: false,
         }
     }
 
@@ -484,107 +1096,1148 @@ impl Redo {
 
     /// returns redo log capacity in bytes.
     pub fn capacity(&self) -> Lsn {
-        self.size - self.hdr.first_lsn
+        self.size.saturating_sub(self.hdr.first_lsn)
     }
 
-    /// Determine the sequence bit at a log sequence number.
-    /// The sequence bit is used to determine whether the log record
-    /// corresponds to the current generation (wrap) of the redo log.
-    pub fn get_sequence_bit(&self, lsn: Lsn) -> u8 {
-        mtr::get_sequence_bit(self.hdr.first_lsn, self.capacity(), lsn)
+    /// Returns the file offset of `lsn`.
+    pub fn lsn_to_offset(&self, lsn: Lsn) -> usize {
+        lsn::lsn_to_offset(self.hdr.first_lsn, self.capacity(), lsn) as usize
     }
-}
 
-fn is_latest(version: u32) -> bool {
-    version & (!FORMAT_ENCRYPTED) == FORMAT_10_8
-}
-
-/// verifies block checksum where last 4 bytes is crc32.
-fn verify_crc_block(block: &[u8], crc: u32) -> (bool, u32) {
-    if block.len() < 4 {
-        return (false, 0);
+    /// Returns the LSN at the file `offset` in the generation of the ring closest to
+    /// `near`, i.e. the checkpoint LSN.
+    pub fn offset_to_lsn(&self, offset: usize, near: Lsn) -> Lsn {
+        lsn::offset_to_lsn(self.hdr.first_lsn, self.capacity(), offset as u64, near)
     }
 
-    let new = crc32c(&block[0..block.len() - 4]);
+    /// Returns the checkpoint age against the log end LSN stored in the checkpoint block.
+    pub fn fill_stats(&self) -> RedoFillStats {
+        self.fill_stats_at(self.checkpoint.end_lsn)
+    }
 
-    (new == crc, new)
-}
+    /// Returns the checkpoint age against the given log end LSN, i.e. the end found by
+    /// scanning the log.
+    pub fn fill_stats_at(&self, end_lsn: Lsn) -> RedoFillStats {
+        let checkpoint_lsn = self.checkpoint.checkpoint_lsn.unwrap_or(self.hdr.first_lsn);
 
-impl<'a> RedoReader<'a> {
-    pub fn reader(&self) -> &RingReader<'a> {
-        &self.reader
+        RedoFillStats {
+            capacity: self.capacity(),
+            checkpoint_lsn,
+            end_lsn,
+            used: end_lsn.saturating_sub(checkpoint_lsn),
+        }
     }
 
-    pub fn parse_next(&mut self) -> anyhow::Result<MtrChain> {
-        MtrChain::parse_next(&mut self.reader).context("Mtr::parse_next")
-    }
-}
+    /// Moves the checkpoint forward to the chain at `new_lsn`, like the server does after
+    /// flushing the pages modified before it: appends a FILE_CHECKPOINT record naming
+    /// `new_lsn` and the end marker at the end of the log and points both checkpoint
+    /// blocks at it. `new_lsn` must be a chain boundary between the current checkpoint
+    /// and the end of the log. The redo log must be opened with `Redo::open_mut()`.
+    pub fn advance_checkpoint(&mut self, new_lsn: Lsn) -> anyhow::Result<()> {
+        if !is_latest(self.hdr.version) || self.checkpoint.encrypted {
+            bail!(
+                "advancing checkpoint is only supported for unencrypted {FORMAT_10_8:#x} logs, \
+                 found {:#x}",
+                self.hdr.version
+            );
+        }
 
-impl RedoHeader {
-    pub fn build_unencrypted_header_10_8(
-        first_lsn: Lsn,
-        creator: &str,
-    ) -> std::io::Result<[u8; 512]> {
-        let mut buf = [0u8; 512];
+        let checkpoint_lsn = self.checkpoint.checkpoint_lsn.unwrap_or(self.hdr.first_lsn);
+        if new_lsn < checkpoint_lsn {
+            bail!(
+                "new checkpoint LSN {new_lsn} is behind the current checkpoint LSN \
+                 {checkpoint_lsn}"
+            );
+        }
 
-        mach::mach_write_to_4(&mut buf[LOG_HEADER_FORMAT..], FORMAT_10_8)?;
-        mach::mach_write_to_8(&mut buf[LOG_HEADER_START_LSN..], first_lsn)?;
+        let mut reader = self.reader();
+        let mut boundary = new_lsn == checkpoint_lsn;
+        for chain in reader.chains() {
+            boundary |= chain?.lsn == new_lsn;
+        }
+        let end_lsn = reader.reader().pos();
 
-        let creator_len = min(LOG_HEADER_CREATOR_END - LOG_HEADER_CREATOR, creator.len());
-        buf[LOG_HEADER_CREATOR..LOG_HEADER_CREATOR + creator_len]
-            .copy_from_slice(&creator.as_bytes()[..creator_len]);
+        if new_lsn > end_lsn {
+            bail!("new checkpoint LSN {new_lsn} is past the end of the log {end_lsn}");
+        }
+        if !boundary && new_lsn != end_lsn {
+            bail!("new checkpoint LSN {new_lsn} is not at the start of a mini-transaction");
+        }
 
-        let crc = crc32c(&buf[..LOG_HEADER_CRC]);
-        mach::mach_write_to_4(&mut buf[LOG_HEADER_CRC..], crc)?;
+        let first_lsn = self.hdr.first_lsn;
+        let capacity = self.capacity();
 
-        Ok(buf)
-    }
+        // the record must not overwrite the chains recovery still needs until the header
+        // points at the new checkpoint.
+        if end_lsn + SIZE_OF_FILE_CHECKPOINT + 1 - checkpoint_lsn > capacity {
+            bail!(
+                "redo log is full: no space for a FILE_CHECKPOINT record at {end_lsn} with the \
+                 checkpoint at {checkpoint_lsn} and capacity {capacity}"
+            );
+        }
 
-    // Checkpoint block is 60 bytes long + 4 bytes for the checksum.
-    // - 8 byte: checkpoint_lsn
-    // - 8 byte: end_lsn
+        let mut file_checkpoint = vec![];
+        mtr::Mtr::build_file_checkpoint_at(
+            &mut file_checkpoint,
+            first_lsn,
+            capacity,
+            end_lsn,
+            new_lsn,
+        )?;
+        file_checkpoint.push(0x0); // end marker
+
+        let RedoMmap::ReadWrite(mmap) = &mut self.mmap else {
+            bail!("redo log is opened read-only");
+        };
+
+        let mut writer = RingWriter::buf_at(mmap.as_mut_slice(), first_lsn as usize, 0);
+        writer.seek(std::io::SeekFrom::Start(end_lsn))?;
+        writer.write_all(&file_checkpoint)?;
+        mmap.flush(first_lsn as usize..self.size as usize)
+            .context("flush redo log")?;
+
+        self.write_checkpoint(new_lsn, end_lsn)
+    }
+
+    /// Walks the chains from the checkpoint and returns the most recent file name of each
+    /// tablespace from its FILE_CREATE, FILE_MODIFY and FILE_RENAME (new name) records.
+    /// A FILE_DELETE drops the tablespace.
+    pub fn scan_file_ops(&self) -> anyhow::Result<HashMap<u32, String>> {
+        let mut files = HashMap::new();
+
+        for chain in self.reader() {
+            let chain = chain?;
+
+            for mtr in chain.mtr {
+                let name = match mtr.op {
+                    MtrOperation::FileCreate | MtrOperation::FileModify => mtr.file_name,
+                    MtrOperation::FileRename => mtr.new_file_name,
+                    MtrOperation::FileDelete => {
+                        files.remove(&mtr.space_id);
+                        None
+                    }
+                    _ => None,
+                };
+
+                if let Some(name) = name {
+                    files.insert(mtr.space_id, name);
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Scans the whole ring for stand-alone FILE_CHECKPOINT mini-transactions and returns
+    /// the pairs of the checkpoint LSN each one names and the LSN it was written at, the
+    /// end of the log at the time of the checkpoint, in ascending order. A record is
+    /// written within the capacity after the LSN it names, and only counts if it is
+    /// exactly the one `mtr::Mtr::build_file_checkpoint_at()` writes there, so its
+    /// sequence bit and checksum agree.
+    pub fn find_file_checkpoints(&self) -> Vec<(Lsn, Lsn)> {
+        let first_lsn = self.hdr.first_lsn;
+        let capacity = self.capacity();
+        let buf = self.buf();
+
+        let mut found = vec![];
+        let mut record = [0u8; SIZE_OF_FILE_CHECKPOINT as usize];
+        let mut expected = Vec::with_capacity(record.len());
+
+        for offset in first_lsn..self.size {
+            // FILE_CHECKPOINT + body len 10 bytes
+            if buf[offset as usize] != mtr0types::mfile_type_t::FILE_CHECKPOINT as u8 | 10 {
+                continue;
+            }
+
+            let reader = RingReader::buf_at(buf, first_lsn as usize, offset);
+            if reader.block(&mut record) != record.len() {
+                continue;
+            }
+
+            let checkpoint_lsn = mach::mach_read_from_8(&record[3..]);
+            if checkpoint_lsn < first_lsn {
+                continue;
+            }
+
+            let distance = (offset + capacity
+                - lsn::lsn_to_offset(first_lsn, capacity, checkpoint_lsn))
+                % capacity;
+            let Some(lsn) = checkpoint_lsn.checked_add(distance) else {
+                continue;
+            };
+
+            expected.clear();
+            if mtr::Mtr::build_file_checkpoint_at(
+                &mut expected,
+                first_lsn,
+                capacity,
+                lsn,
+                checkpoint_lsn,
+            )
+            .is_ok()
+                && expected == record
+            {
+                found.push((checkpoint_lsn, lsn));
+            }
+        }
+
+        found.sort();
+        found
+    }
+
+    /// Overwrites both checkpoint blocks with `checkpoint_lsn` and `end_lsn`, so that
+    /// recovery starts at `checkpoint_lsn`. The log body is not checked nor modified, i.e.
+    /// to repair a header that disagrees with the records, see `find_file_checkpoints()`.
+    /// The redo log must be opened with `Redo::open_mut()`.
+    pub fn write_checkpoint(&mut self, checkpoint_lsn: Lsn, end_lsn: Lsn) -> anyhow::Result<()> {
+        if !is_latest(self.hdr.version) || self.checkpoint.encrypted {
+            bail!(
+                "writing checkpoint is only supported for unencrypted {FORMAT_10_8:#x} logs, \
+                 found {:#x}",
+                self.hdr.version
+            );
+        }
+
+        // The LSNs keep growing as the log wraps, so only the lower bound is checked.
+        if checkpoint_lsn < self.hdr.first_lsn {
+            bail!(
+                "checkpoint LSN {checkpoint_lsn} is below the first LSN {}",
+                self.hdr.first_lsn
+            );
+        }
+        if end_lsn < checkpoint_lsn {
+            bail!("end LSN {end_lsn} is behind the checkpoint LSN {checkpoint_lsn}");
+        }
+
+        let checkpoint =
+            RedoHeader::build_unencrypted_header_10_8_checkpoint(checkpoint_lsn, end_lsn)?;
+
+        let RedoMmap::ReadWrite(mmap) = &mut self.mmap else {
+            bail!("redo log is opened read-only");
+        };
+
+        for pos in [CHECKPOINT_1, CHECKPOINT_2] {
+            mmap[pos..pos + checkpoint.len()].copy_from_slice(&checkpoint);
+        }
+        mmap.flush(CHECKPOINT_1..CHECKPOINT_2 + checkpoint.len())
+            .context("flush redo log")?;
+
+        let block = RedoHeaderCheckpoint {
+            checkpoint_lsn,
+            end_lsn,
+            checksum: mach::mach_read_from_4(&checkpoint[60..]),
+        };
+        self.checkpoint.checkpoints = [block.clone(), block];
+        self.checkpoint.checkpoint_lsn = Some(checkpoint_lsn);
+        // CHECKPOINT_2 wins a tie, like in parse_header_checkpoint().
+        self.checkpoint.checkpoint_no = Some(0);
+        self.checkpoint.end_lsn = end_lsn;
+
+        Ok(())
+    }
+
+    /// Returns the header and the checkpoint with all chains from the checkpoint, for
+    /// structured output. The records of logs before 10.8 are not parsed. Fails on the
+    /// first chain that can't be parsed.
+    pub fn dump(&self) -> anyhow::Result<RedoDump> {
+        let chains = if self.is_latest() {
+            self.reader().collect::<anyhow::Result<_>>()?
+        } else {
+            vec![]
+        };
+
+        Ok(RedoDump {
+            header: self.hdr.clone(),
+            checkpoint: self.checkpoint.clone(),
+            chains,
+        })
+    }
+
+    /// Parses all chains from the checkpoint, where recovery starts, up to the end of the
+    /// log and reports what was found. The scan goes on past a chain with an invalid
+    /// checksum or a malformed record, as its length is known from the end marker.
+    pub fn verify(&self) -> RedoVerification {
+        let mut verification = RedoVerification {
+            checkpoint_lsn: self.checkpoint.checkpoint_lsn,
+            ..Default::default()
+        };
+
+        let mut reader = self.reader();
+        loop {
+            let lsn = reader.reader().pos();
+            let Some(chain) = reader.next() else {
+                break;
+            };
+
+            let chain = match chain.and_then(|chain| {
+                chain.validate_lengths()?;
+                Ok(chain)
+            }) {
+                Ok(chain) => chain,
+                Err(err) => {
+                    verification.corrupted += 1;
+                    verification.first_corruption_lsn.get_or_insert(lsn);
+                    verification.error.get_or_insert(format!("{err:#}"));
+
+                    if reader.reader().pos() == lsn {
+                        // the chain can't be skipped, i.e. the log is encrypted.
+                        break;
+                    }
+
+                    // the iteration ends at a parse error, resume it after the chain.
+                    reader.done = false;
+                    continue;
+                }
+            };
+
+            verification.chains += 1;
+            verification.records += chain.mtr.len();
+            verification.first_lsn.get_or_insert(chain.lsn);
+            if verification.first_corruption_lsn.is_none() {
+                verification.end_lsn = Some(chain.lsn + chain.len as Lsn);
+            }
+
+            for mtr in &chain.mtr {
+                // the server writes the record at the end of the log, after the LSN it names.
+                if mtr.op == MtrOperation::FileCheckpoint && mtr.lsn == self.checkpoint.end_lsn {
+                    verification.file_checkpoint_lsn = mtr.file_checkpoint_lsn;
+                }
+            }
+        }
+
+        verification
+    }
+
+    /// Returns the records after the checkpoint that recovery would apply to the page
+    /// `page_no` of `space_id` with FIL_PAGE_LSN `page_lsn`: the page records of the
+    /// mini-transactions that start at or after the page LSN.
+    pub fn page_records(
+        &self,
+        space_id: u32,
+        page_no: u32,
+        page_lsn: Lsn,
+    ) -> anyhow::Result<Vec<mtr::Mtr>> {
+        let mut records = vec![];
+
+        for chain in self.reader() {
+            let chain = chain?;
+
+            if chain.lsn < page_lsn {
+                continue;
+            }
+
+            records.extend(chain.mtr.into_iter().filter(|mtr| {
+                !mtr.op.is_file_op() && mtr.space_id == space_id && mtr.page_no == page_no
+            }));
+        }
+
+        Ok(records)
+    }
+
+    /// Determine the sequence bit at a log sequence number.
+    /// The sequence bit is used to determine whether the log record
+    /// corresponds to the current generation (wrap) of the redo log.
+    pub fn get_sequence_bit(&self, lsn: Lsn) -> u8 {
+        lsn::get_sequence_bit(self.hdr.first_lsn, self.capacity(), lsn)
+    }
+
+    /// Classifies a page LSN against the checkpoint LSN and the log end LSN.
+    pub fn classify_lsn(&self, lsn: Lsn) -> PageLsnState {
+        let checkpoint_lsn = self.checkpoint.checkpoint_lsn.unwrap_or(self.hdr.first_lsn);
+
+        if lsn <= checkpoint_lsn {
+            PageLsnState::Flushed
+        } else if lsn <= self.checkpoint.end_lsn {
+            PageLsnState::NeedsRedo
+        } else {
+            PageLsnState::Future
+        }
+    }
+
+    /// Decodes FIL_PAGE_LSN of the page into the log generation and file offset,
+    /// and classifies it with `classify_lsn()`.
+    pub fn page_lsn_info(&self, page: &PageBuf) -> PageLsnInfo {
+        let first_lsn = self.hdr.first_lsn;
+        let capacity = self.capacity();
+
+        PageLsnInfo {
+            page_lsn: page.page_lsn,
+            generation: lsn::lsn_gen(first_lsn, capacity, page.page_lsn),
+            offset: lsn::lsn_to_offset(first_lsn, capacity, page.page_lsn),
+            state: self.classify_lsn(page.page_lsn),
+        }
+    }
+}
+
+pub fn is_latest(version: u32) -> bool {
+    version & (!FORMAT_ENCRYPTED) == FORMAT_10_8
+}
+
+/// verifies block checksum where last 4 bytes is crc32.
+fn verify_crc_block(block: &[u8], crc: u32) -> (bool, u32) {
+    if block.len() < 4 {
+        return (false, 0);
+    }
+
+    let new = crc32c(&block[0..block.len() - 4]);
+
+    (new == crc, new)
+}
+
+impl RedoWriter {
+    pub fn header(&self) -> &RedoHeader {
+        &self.hdr
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// returns redo log capacity in bytes.
+    pub fn capacity(&self) -> Lsn {
+        self.size - self.hdr.first_lsn
+    }
+
+    /// Returns the LSN the next chain is written at.
+    pub fn end_lsn(&self) -> Lsn {
+        self.end_lsn
+    }
+
+    /// Writes a FILE_CHECKPOINT record at `lsn` followed by the end marker and points both
+    /// checkpoint blocks at it. The following chains are written after the record.
+    pub fn write_checkpoint(&mut self, lsn: Lsn) -> anyhow::Result<()> {
+        let first_lsn = self.hdr.first_lsn;
+        let capacity = self.capacity();
+        Redo::validate_checkpoint_lsn(first_lsn, self.size, lsn)?;
+
+        let mut file_checkpoint = vec![];
+        mtr::Mtr::build_file_checkpoint(&mut file_checkpoint, first_lsn, capacity, lsn)?;
+        file_checkpoint.push(0x0); // end marker
+
+        let checkpoint = RedoHeader::build_unencrypted_header_10_8_checkpoint(lsn, lsn)?;
+
+        let mut writer = self.ring.writer();
+        for pos in [CHECKPOINT_1, CHECKPOINT_2] {
+            writer.seek(std::io::SeekFrom::Start(pos as u64))?;
+            writer.write_all(&checkpoint)?;
+        }
+
+        writer.seek(std::io::SeekFrom::Start(lsn))?;
+        writer.write_all(&file_checkpoint)?;
+
+        self.checkpoint_lsn = lsn;
+        self.end_lsn = lsn + SIZE_OF_FILE_CHECKPOINT;
+
+        Ok(())
+    }
+
+    /// Appends a mini-transaction of the serialized `records` at the end of the log: the
+    /// records, the termination marker, the checksum and the end marker after the chain.
+    /// Returns the LSN of the chain. Fails if the chain would wrap the ring over the
+    /// FILE_CHECKPOINT record recovery starts at.
+    pub fn write_chain(&mut self, records: &[u8]) -> anyhow::Result<Lsn> {
+        let lsn = self.end_lsn;
+
+        let mut chain = Vec::with_capacity(records.len() + 1 + 4 + 1);
+        mtr::MtrChain::build(
+            &mut chain,
+            self.hdr.first_lsn,
+            self.capacity(),
+            lsn,
+            records,
+        )?;
+        chain.push(0x0); // end marker
+
+        if lsn + chain.len() as Lsn > self.checkpoint_lsn + self.capacity() {
+            bail!(
+                "redo log is full: the chain of {} bytes at LSN {lsn} would overwrite the \
+                 checkpoint at LSN {}",
+                chain.len(),
+                self.checkpoint_lsn
+            );
+        }
+
+        let mut writer = self.ring.writer();
+        writer.seek(std::io::SeekFrom::Start(lsn))?;
+        writer.write_all(&chain)?;
+
+        self.end_lsn = lsn + chain.len() as Lsn - 1;
+
+        Ok(lsn)
+    }
+
+    pub fn flush(&self) -> anyhow::Result<()> {
+        self.ring
+            .mmap()
+            .flush(0..self.size as usize)
+            .context("flush redo log")
+    }
+}
+
+impl<R: Read + Seek> RedoStreamReader<R> {
+    pub fn header(&self) -> &RedoHeader {
+        &self.hdr
+    }
+
+    pub fn checkpoint(&self) -> &RedoCheckpointCoordinate {
+        &self.checkpoint
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// returns redo log capacity in bytes.
+    pub fn capacity(&self) -> Lsn {
+        self.size - self.hdr.first_lsn
+    }
+
+    /// Returns the LSN of the next chain.
+    pub fn lsn(&self) -> Lsn {
+        self.lsn
+    }
+
+    /// Reads the ring up to `end_lsn` into the window, replacing the bytes of the LSNs
+    /// a window size before.
+    fn load(&mut self, end_lsn: Lsn) -> anyhow::Result<()> {
+        let first_lsn = self.hdr.first_lsn;
+        let capacity = self.capacity();
+
+        while self.loaded_lsn < end_lsn {
+            let offset = lsn::lsn_to_offset(first_lsn, capacity, self.loaded_lsn);
+            let slot = self.loaded_lsn % STREAM_WINDOW_SIZE;
+            let len = min(
+                min(STREAM_BLOCK_SIZE, end_lsn - self.loaded_lsn),
+                min(self.size - offset, STREAM_WINDOW_SIZE - slot),
+            );
+
+            self.r
+                .seek(std::io::SeekFrom::Start(offset))
+                .context("seek redo log")?;
+            self.r
+                .read_exact(&mut self.buf[slot as usize..(slot + len) as usize])
+                .with_context(|| format!("read redo log at {offset}"))?;
+
+            self.loaded_lsn += len;
+        }
+
+        Ok(())
+    }
+
+    pub fn parse_next(&mut self) -> anyhow::Result<MtrChain> {
+        if let (Some(crypt), None) = (&self.checkpoint.crypt, &self.crypt_key) {
+            bail!(
+                "Mtr::parse_next: the redo log is encrypted (key version {}), open it with \
+                 Redo::open_reader_with_keyring()",
+                crypt.key_version
+            );
+        }
+
+        self.load(self.lsn.saturating_add(STREAM_WINDOW_SIZE))?;
+
+        let mut reader =
+            RingReader::window(&self.buf, self.hdr.first_lsn, self.capacity(), self.lsn);
+        let chain = MtrChain::parse_next_with(&mut reader, self.crypt_key.as_ref())
+            .context("Mtr::parse_next")?;
+        self.lsn = reader.pos();
+
+        Ok(chain)
+    }
+}
+
+impl<'a> RedoReader<'a> {
+    pub fn reader(&self) -> &RingReader<'a> {
+        &self.reader
+    }
+
+    /// Returns the encryption info of the log, if it is encrypted.
+    pub fn crypt(&self) -> Option<&RedoCrypt> {
+        self.crypt.as_ref()
+    }
+
+    pub fn parse_next(&mut self) -> anyhow::Result<MtrChain> {
+        if let (Some(crypt), None) = (&self.crypt, &self.crypt_key) {
+            // Decrypting the records needs the AES log key derived from the crypt message.
+            bail!(
+                "Mtr::parse_next: the redo log is encrypted (key version {}), open it with \
+                 Redo::open_with_keyring()",
+                crypt.key_version
+            );
+        }
+
+        MtrChain::parse_next_with(&mut self.reader, self.crypt_key.as_ref())
+            .context("Mtr::parse_next")
+    }
+
+    /// Iterates the chains from the current position. The iteration ends at the end of
+    /// the log; any other parse error is yielded once as Err and ends it as well.
+    pub fn chains(&mut self) -> impl Iterator<Item = anyhow::Result<MtrChain>> + '_ {
+        self.by_ref()
+    }
+}
+
+impl Iterator for RedoReader<'_> {
+    type Item = anyhow::Result<MtrChain>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.parse_next() {
+            Ok(chain) => Some(Ok(chain)),
+            Err(err) => {
+                self.done = true;
+
+                // test for EOM.
+                if let Some(err) = err.downcast_ref::<std::io::Error>()
+                    && err.kind() == std::io::ErrorKind::NotFound
+                {
+                    return None;
+                }
+
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl RedoHeader {
+    pub fn build_unencrypted_header_10_8(
+        first_lsn: Lsn,
+        creator: &str,
+    ) -> std::io::Result<[u8; 512]> {
+        RedoHeader {
+            version: FORMAT_10_8,
+            first_lsn,
+            creator: creator.to_string(),
+            crc: 0,
+            crypt: None,
+        }
+        .to_bytes()
+    }
+
+    /// Serializes the header block with a recomputed checksum, so that a parsed header can
+    /// be modified and written back. The creator is truncated to fit the field. The crypt
+    /// info of an encrypted 10.8 log is written after the creator; the encrypted formats
+    /// before 10.8 are not supported.
+    pub fn to_bytes(&self) -> std::io::Result<[u8; 512]> {
+        if self.version & FORMAT_ENCRYPTED != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "serializing encrypted log header is not supported",
+            ));
+        }
+
+        let mut buf = [0u8; 512];
+
+        mach::mach_write_to_4(&mut buf[LOG_HEADER_FORMAT..], self.version)?;
+        mach::mach_write_to_8(&mut buf[LOG_HEADER_START_LSN..], self.first_lsn)?;
+
+        let creator_len = min(
+            LOG_HEADER_CREATOR_END - LOG_HEADER_CREATOR,
+            self.creator.len(),
+        );
+        buf[LOG_HEADER_CREATOR..LOG_HEADER_CREATOR + creator_len]
+            .copy_from_slice(&self.creator.as_bytes()[..creator_len]);
+
+        if let Some(crypt) = &self.crypt {
+            let info = &mut buf[LOG_HEADER_CREATOR_END..];
+            mach::mach_write_to_4(&mut info[LOG_CRYPT_KEY_ID..], crypt.key_id)?;
+            mach::mach_write_to_4(&mut info[LOG_CRYPT_KEY_VERSION..], crypt.key_version)?;
+            info[LOG_CRYPT_MSG..LOG_CRYPT_MSG + MY_AES_BLOCK_SIZE]
+                .copy_from_slice(&crypt.crypt_msg);
+            info[LOG_CRYPT_NONCE..LOG_CRYPT_NONCE + LOG_CRYPT_NONCE_SIZE]
+                .copy_from_slice(&crypt.crypt_nonce);
+        }
+
+        let crc = crc32c(&buf[..LOG_HEADER_CRC]);
+        mach::mach_write_to_4(&mut buf[LOG_HEADER_CRC..], crc)?;
+
+        Ok(buf)
+    }
+
+    // Checkpoint block is 60 bytes long + 4 bytes for the checksum.
+    // - 8 byte: checkpoint_lsn
+    // - 8 byte: end_lsn
     // - 44 byte: reserved
     // - 4 byte: checksum
     pub fn build_unencrypted_header_10_8_checkpoint(
         checkpoint_lsn: Lsn,
         end_lsn: Lsn,
     ) -> std::io::Result<[u8; 64]> {
+        RedoHeaderCheckpoint {
+            checkpoint_lsn,
+            end_lsn,
+            checksum: 0,
+        }
+        .to_bytes()
+    }
+}
+
+impl RedoHeaderCheckpoint {
+    /// Serializes the unencrypted checkpoint block with a recomputed checksum.
+    /// See `RedoHeader::build_unencrypted_header_10_8_checkpoint()` for the layout.
+    pub fn to_bytes(&self) -> std::io::Result<[u8; 64]> {
         let mut buf = [0u8; 64];
 
-        mach::mach_write_to_8(&mut buf[0..], checkpoint_lsn)?;
-        mach::mach_write_to_8(&mut buf[8..], end_lsn)?;
+        mach::mach_write_to_8(&mut buf[0..], self.checkpoint_lsn)?;
+        mach::mach_write_to_8(&mut buf[8..], self.end_lsn)?;
+
+        let crc = crc32c(&buf[..60]);
+        mach::mach_write_to_4(&mut buf[60..], crc)?;
+
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{Seek, Write},
+        path::Path,
+    };
+
+    use super::*;
+    use crate::{LSN_MAX, keyring::MemoryKeyring, mtr::Mtr, mtr0types::MtrOperation};
+
+    #[test]
+    fn test_build_header_10_8() {
+        let mut buf = [0u8; FIRST_LSN as usize];
+        let hdr = RedoHeader::build_unencrypted_header_10_8(FIRST_LSN, "test_creator")
+            .expect("Failed to build header");
+        let cp = RedoHeader::build_unencrypted_header_10_8_checkpoint(FIRST_LSN, FIRST_LSN)
+            .expect("Failed to build checkpoint");
+        buf[0..hdr.len()].copy_from_slice(&hdr);
+        buf[CHECKPOINT_1..CHECKPOINT_1 + cp.len()].copy_from_slice(&cp);
+        buf[CHECKPOINT_2..CHECKPOINT_2 + cp.len()].copy_from_slice(&cp);
+
+        let header = Redo::parse_header(&buf).expect("Failed to parse header");
+        let _checkpoint =
+            Redo::parse_header_checkpoint(&buf, &header, 0).expect("Failed to parse checkpoint");
+    }
+
+    #[test]
+    fn test_header_round_trip() {
+        let mut buf = [0u8; START_OFFSET as usize];
+        let hdr = RedoHeader::build_unencrypted_header_10_8(FIRST_LSN, "test_creator").unwrap();
+        let cp = RedoHeader::build_unencrypted_header_10_8_checkpoint(20000, 20100).unwrap();
+        buf[0..hdr.len()].copy_from_slice(&hdr);
+        buf[CHECKPOINT_1..CHECKPOINT_1 + cp.len()].copy_from_slice(&cp);
+
+        let (header, checkpoint) = Redo::inspect(&buf).unwrap();
+        assert_eq!(header.to_bytes().unwrap(), hdr);
+        assert_eq!(checkpoint.checkpoints[0].to_bytes().unwrap(), cp);
+
+        let mut edited = header.clone();
+        edited.creator = "MariaDB 11.4.2".to_string();
+        buf[0..512].copy_from_slice(&edited.to_bytes().unwrap());
+
+        let mut edited_cp = checkpoint.checkpoints[0].clone();
+        edited_cp.end_lsn = 20200;
+        buf[CHECKPOINT_1..CHECKPOINT_1 + 64].copy_from_slice(&edited_cp.to_bytes().unwrap());
+
+        let (reparsed, checkpoint) = Redo::inspect(&buf).unwrap();
+        assert_ne!(reparsed.crc, header.crc);
+        assert_eq!(
+            reparsed,
+            RedoHeader {
+                crc: reparsed.crc,
+                ..edited
+            }
+        );
+        assert_eq!(
+            checkpoint.checkpoints[0],
+            RedoHeaderCheckpoint {
+                checksum: checkpoint.checkpoints[0].checksum,
+                ..edited_cp
+            }
+        );
+        assert_eq!(checkpoint.end_lsn, 20200);
+    }
+
+    #[test]
+    fn test_parse_crypt_header() {
+        // key id, key version, crypt message, nonce
+        let mut info = vec![0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02];
+        info.extend_from_slice(&[0x11; MY_AES_BLOCK_SIZE]);
+        info.extend_from_slice(&[0x01, 0x02, 0x03, 0x04]);
+
+        let crypt = Redo::parse_crypt_header(&info).unwrap().unwrap();
+        assert_eq!(crypt.key_id, LOG_DEFAULT_ENCRYPTION_KEY);
+        assert_eq!(crypt.key_version, 2);
+        assert_eq!(crypt.crypt_msg, [0x11; MY_AES_BLOCK_SIZE]);
+        assert_eq!(crypt.crypt_nonce, [0x01, 0x02, 0x03, 0x04]);
+
+        assert!(Redo::parse_crypt_header(&info[..info.len() - 1]).is_err());
+
+        info[LOG_CRYPT_KEY_ID + 3] = 0;
+        assert_eq!(Redo::parse_crypt_header(&info).unwrap(), None);
+    }
+
+    #[test]
+    fn test_encrypted_header() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        Redo::create(path, size, FIRST_LSN)
+            .unwrap()
+            .flush()
+            .unwrap();
+
+        let set_crypt = |key_version: u32, crypt_msg: [u8; MY_AES_BLOCK_SIZE]| {
+            let mut buf = std::fs::read(path).unwrap();
+            let info = &mut buf[LOG_HEADER_CREATOR_END..];
+            mach::mach_write_to_4(&mut info[LOG_CRYPT_KEY_ID..], LOG_DEFAULT_ENCRYPTION_KEY)
+                .unwrap();
+            mach::mach_write_to_4(&mut info[LOG_CRYPT_KEY_VERSION..], key_version).unwrap();
+            info[LOG_CRYPT_MSG..LOG_CRYPT_MSG + MY_AES_BLOCK_SIZE].copy_from_slice(&crypt_msg);
+            info[LOG_CRYPT_NONCE..LOG_CRYPT_NONCE + LOG_CRYPT_NONCE_SIZE]
+                .copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+            let crc = crc32c(&buf[..LOG_HEADER_CRC]);
+            mach::mach_write_to_4(&mut buf[LOG_HEADER_CRC..], crc).unwrap();
+            std::fs::write(path, &buf).unwrap();
+        };
+
+        set_crypt(3, [0x5a; MY_AES_BLOCK_SIZE]);
+
+        // the crypt info survives a round trip of the header block.
+        let buf = std::fs::read(path).unwrap();
+        let hdr = Redo::parse_header(&buf).unwrap();
+        assert_eq!(hdr.version, FORMAT_10_8);
+        assert_eq!(hdr.crypt.as_ref().map(|c| c.key_version), Some(3));
+        assert_eq!(hdr.to_bytes().unwrap(), buf[..512]);
+
+        let log = Redo::open(path).unwrap();
+        assert!(log.checkpoint().encrypted);
+        assert_eq!(log.checkpoint().version, FORMAT_ENC_10_8);
+        assert_eq!(
+            log.checkpoint().crypt,
+            Some(RedoCrypt {
+                key_id: LOG_DEFAULT_ENCRYPTION_KEY,
+                key_version: 3,
+                crypt_msg: [0x5a; MY_AES_BLOCK_SIZE],
+                crypt_nonce: [0xde, 0xad, 0xbe, 0xef],
+            })
+        );
+
+        let mut reader = log.reader();
+        assert_eq!(reader.crypt().map(|c| c.key_version), Some(3));
+        assert!(reader.parse_next().is_err());
+        drop(log);
+
+        // the log key is the crypt message encrypted with the key version 3.
+        let mut keyring = MemoryKeyring::new();
+        assert!(Redo::open_with_keyring(path, &keyring).is_err());
+        keyring.insert(LOG_DEFAULT_ENCRYPTION_KEY, 3, vec![0x01; 16]);
+        let log = Redo::open_with_keyring(path, &keyring).unwrap();
+        assert_eq!(
+            log.crypt_key(),
+            Some(&log0crypt::aes_ecb_encrypt(&[0x01; 16], &[0x5a; MY_AES_BLOCK_SIZE]).unwrap())
+        );
+        assert_eq!(Redo::open(path).unwrap().crypt_key(), None);
+        let key = *log.crypt_key().unwrap();
+        drop(log);
+
+        // an encrypted chain at the checkpoint is decrypted by both readers.
+        let mut records = vec![];
+        Mtr::build_write(&mut records, 5, 9, 0x60, &[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        let mut chain = vec![];
+        MtrChain::build_encrypted(
+            &mut chain,
+            FIRST_LSN,
+            size - FIRST_LSN,
+            FIRST_LSN,
+            &records,
+            &key,
+        )
+        .unwrap();
+        let mut buf = std::fs::read(path).unwrap();
+        buf[FIRST_LSN as usize..FIRST_LSN as usize + chain.len()].copy_from_slice(&chain);
+        std::fs::write(path, &buf).unwrap();
+
+        let check = |chains: Vec<MtrChain>| {
+            assert_eq!(chains.len(), 1);
+            assert_eq!(chains[0].len() as usize, chain.len());
+            assert_eq!(
+                (chains[0].mtr[0].space_id, chains[0].mtr[0].page_no),
+                (5, 9)
+            );
+            assert_eq!(chains[0].mtr[0].offset, Some(0x60));
+            assert_eq!(chains[0].mtr[0].data, Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        };
+
+        let log = Redo::open_with_keyring(path, &keyring).unwrap();
+        check(log.reader().collect::<anyhow::Result<_>>().unwrap());
+        assert!(Redo::open(path).unwrap().reader().parse_next().is_err());
+        drop(log);
+
+        let file = std::fs::File::open(path).unwrap();
+        let mut stream = Redo::open_reader_with_keyring(file, size, &keyring).unwrap();
+        check(vec![stream.parse_next().unwrap()]);
+        assert!(stream.parse_next().is_err());
+        let file = std::fs::File::open(path).unwrap();
+        assert!(Redo::open_reader(file, size).unwrap().parse_next().is_err());
+
+        set_crypt(0, [0x5a; MY_AES_BLOCK_SIZE]);
+        assert!(Redo::open(path).is_err());
+        set_crypt(3, [0; MY_AES_BLOCK_SIZE]);
+        assert!(Redo::open(path).is_err());
+    }
+
+    #[test]
+    fn test_inspect() {
+        let mut buf = [0u8; START_OFFSET as usize];
+        let hdr = RedoHeader::build_unencrypted_header_10_8(FIRST_LSN, "test_creator").unwrap();
+        let cp = RedoHeader::build_unencrypted_header_10_8_checkpoint(20000, 20100).unwrap();
+        buf[0..hdr.len()].copy_from_slice(&hdr);
+        buf[CHECKPOINT_1..CHECKPOINT_1 + cp.len()].copy_from_slice(&cp);
+        buf[CHECKPOINT_2..CHECKPOINT_2 + cp.len()].copy_from_slice(&cp);
+
+        let (header, checkpoint) = Redo::inspect(&buf).expect("Failed to inspect redo log");
+        assert_eq!(header.version, FORMAT_10_8);
+        assert_eq!(header.first_lsn, FIRST_LSN);
+        assert_eq!(header.creator, "test_creator");
+        assert_eq!(checkpoint.checkpoint_lsn, Some(20000));
+        assert_eq!(checkpoint.end_lsn, 20100);
+
+        assert!(Redo::inspect(&buf[..CHECKPOINT_2]).is_err());
+    }
+
+    #[test]
+    fn test_legacy_checkpoint() {
+        let mut buf = vec![0u8; START_OFFSET as usize];
+        mach::mach_write_to_4(&mut buf[LOG_HEADER_FORMAT..], FORMAT_10_4).unwrap();
+        mach::mach_write_to_8(&mut buf[LOG_HEADER_START_LSN..], 0x2000).unwrap();
+        buf[LOG_HEADER_CREATOR..LOG_HEADER_CREATOR + 15].copy_from_slice(b"MariaDB 10.4.31");
+        let crc = crc32c(&buf[..LOG_BLOCK_CHECKSUM]);
+        mach::mach_write_to_4(&mut buf[LOG_BLOCK_CHECKSUM..], crc).unwrap();
+
+        let set_block = |buf: &mut [u8], pos: usize, no: u64, lsn: Lsn, offset: u64| {
+            let block = &mut buf[pos..pos + LOG_BLOCK_SIZE];
+            mach::mach_write_to_8(&mut block[0..], no).unwrap();
+            mach::mach_write_to_8(&mut block[8..], lsn).unwrap();
+            mach::mach_write_to_8(&mut block[16..], offset).unwrap();
+            let crc = crc32c(&block[..LOG_BLOCK_CHECKSUM]);
+            mach::mach_write_to_4(&mut block[LOG_BLOCK_CHECKSUM..], crc).unwrap();
+        };
+        let parse = |buf: &[u8]| {
+            let hdr = Redo::parse_header(buf).unwrap();
+            let mut warnings = vec![];
+            let checkpoint =
+                Redo::parse_header_checkpoint_with_warnings(buf, &hdr, 0, &mut warnings);
+            (checkpoint, warnings)
+        };
+
+        set_block(&mut buf, LOG_CHECKPOINT_1, 7, 50000, 0x1a0c);
+        set_block(&mut buf, LOG_CHECKPOINT_2, 8, 60000, 0x2040);
+
+        let (checkpoint, warnings) = parse(&buf);
+        let checkpoint = checkpoint.unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(!checkpoint.encrypted);
+        assert_eq!(checkpoint.version, FORMAT_10_4);
+        assert_eq!(checkpoint.checkpoint_no, Some(8));
+        assert_eq!(checkpoint.checkpoint_lsn, Some(60000));
+        assert_eq!(checkpoint.checkpoint_offset, Some(0x2040));
+        assert_eq!(checkpoint.end_lsn, 60000);
+        assert_eq!(checkpoint.checkpoints[0].checkpoint_lsn, 50000);
+
+        // the newer checkpoint points beyond the log file.
+        set_block(&mut buf, LOG_CHECKPOINT_2, 8, 60000, START_OFFSET);
+        let (checkpoint, warnings) = parse(&buf);
+        assert_eq!(checkpoint.unwrap().checkpoint_no, Some(7));
+        assert!(matches!(
+            warnings[..],
+            [RedoWarning::InvalidCheckpoint {
+                pos: LOG_CHECKPOINT_2,
+                ..
+            }]
+        ));
+
+        // and the older one is corrupted.
+        buf[LOG_CHECKPOINT_1 + 8] ^= 1;
+        let (checkpoint, warnings) = parse(&buf);
+        assert!(checkpoint.is_err());
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_open_10_5() {
+        let size = 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut buf = vec![0u8; size];
+        mach::mach_write_to_4(&mut buf[LOG_HEADER_FORMAT..], FORMAT_10_5).unwrap();
+        mach::mach_write_to_8(&mut buf[LOG_HEADER_START_LSN..], 0x2000).unwrap();
+        buf[LOG_HEADER_CREATOR..LOG_HEADER_CREATOR + 15].copy_from_slice(b"MariaDB 10.5.27");
+        let crc = crc32c(&buf[..LOG_BLOCK_CHECKSUM]);
+        mach::mach_write_to_4(&mut buf[LOG_BLOCK_CHECKSUM..], crc).unwrap();
+
+        // only the first checkpoint block was ever written.
+        let block = &mut buf[LOG_CHECKPOINT_1..LOG_CHECKPOINT_1 + LOG_BLOCK_SIZE];
+        mach::mach_write_to_8(&mut block[0..], 1).unwrap();
+        mach::mach_write_to_8(&mut block[8..], 0x3456).unwrap();
+        mach::mach_write_to_8(&mut block[16..], 0x1c56).unwrap();
+        let crc = crc32c(&block[..LOG_BLOCK_CHECKSUM]);
+        mach::mach_write_to_4(&mut block[LOG_BLOCK_CHECKSUM..], crc).unwrap();
+        std::fs::write(path, &buf).unwrap();
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(!log.is_latest());
+        assert_eq!(log.header().version, FORMAT_10_5);
+        assert_eq!(log.header().creator, "MariaDB 10.5.27");
+        assert_eq!(log.checkpoint().checkpoint_no, Some(1));
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(0x3456));
+        assert_eq!(log.checkpoint().checkpoint_offset, Some(0x1c56));
+        assert_eq!(log.checkpoint().end_lsn, 0x3456);
+        assert!(matches!(
+            warnings[..],
+            [RedoWarning::InvalidCheckpoint {
+                pos: LOG_CHECKPOINT_2,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_open_with_warnings() {
+        let size = 10u64 * 1024 * 1024; // 10 MB
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        make_redo_log_file(path, size, FIRST_LSN).unwrap();
+
+        let (_, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        // corrupt the checksum of the second checkpoint block.
+        let mut buf = std::fs::read(path).unwrap();
+        buf[CHECKPOINT_2 + 60] ^= 0xff;
+        std::fs::write(path, &buf).unwrap();
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(FIRST_LSN));
+        assert_eq!(warnings.len(), 1);
+        let RedoWarning::InvalidCheckpoint { pos, reason } = &warnings[0] else {
+            panic!("unexpected warning: {}", warnings[0]);
+        };
+        assert_eq!(*pos, CHECKPOINT_2);
+        assert!(reason.starts_with("checksum="), "{reason}");
+    }
+
+    #[test]
+    fn test_small_capacity_warning() {
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let size = FIRST_LSN + mtr::MTR_SIZE_MAX as Lsn;
+        make_redo_log_file(path, size, FIRST_LSN).unwrap();
+        let (_, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        make_redo_log_file(path, size - 1, FIRST_LSN).unwrap();
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert_eq!(
+            warnings,
+            vec![RedoWarning::SmallCapacity {
+                capacity: log.capacity()
+            }]
+        );
+        assert_eq!(log.capacity(), mtr::MTR_SIZE_MAX as Lsn - 1);
+    }
+
+    #[test]
+    fn test_custom_first_lsn() {
+        assert!(Redo::validate_first_lsn(FIRST_LSN - 1).is_err());
+
+        let first_lsn = FIRST_LSN + 0x10000;
+        Redo::validate_first_lsn(first_lsn).unwrap();
 
-        let crc = crc32c(&buf[..60]);
-        mach::mach_write_to_4(&mut buf[60..], crc)?;
+        let size = first_lsn + 1024 * 1024;
+        let lsn = first_lsn + 100;
+        Redo::validate_checkpoint_lsn(first_lsn, size, lsn).unwrap();
 
-        Ok(buf)
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        make_redo_log_file_at(path, size, first_lsn, lsn).unwrap();
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(log.header().first_lsn, first_lsn);
+        assert_eq!(log.capacity(), size - first_lsn);
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(lsn));
+        assert_eq!(log.checkpoint().end_lsn, lsn);
+
+        let mut reader = log.reader();
+        let chain = reader.parse_next().unwrap();
+        assert_eq!(chain.lsn, lsn);
+        assert_eq!(chain.mtr[0].op, MtrOperation::FileCheckpoint);
+        assert_eq!(chain.mtr[0].file_checkpoint_lsn, Some(lsn));
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::{
-        io::{Seek, Write},
-        path::Path,
-    };
+    /// Appends a chain of the given records at `lsn` of a log in the first generation,
+    /// followed by the end marker. Returns the LSN after the chain.
+    fn append_chain(path: &Path, lsn: Lsn, records: &[u8]) -> Lsn {
+        let mut buf = std::fs::read(path).unwrap();
+        let pos = lsn as usize;
 
-    use super::*;
-    use crate::{mtr::Mtr, mtr0types::MtrOperation};
+        buf[pos..pos + records.len()].copy_from_slice(records);
+        buf[pos + records.len()] = 1; // sequence bit of the first generation
+        let crc = crc32c(records);
+        buf[pos + records.len() + 1..pos + records.len() + 5].copy_from_slice(&crc.to_be_bytes());
+        buf[pos + records.len() + 5] = 0; // end marker
+        std::fs::write(path, &buf).unwrap();
+
+        lsn + records.len() as Lsn + 5
+    }
 
     #[test]
-    fn test_build_header_10_8() {
-        let mut buf = [0u8; FIRST_LSN as usize];
-        let hdr = RedoHeader::build_unencrypted_header_10_8(FIRST_LSN, "test_creator")
-            .expect("Failed to build header");
-        let cp = RedoHeader::build_unencrypted_header_10_8_checkpoint(FIRST_LSN, FIRST_LSN)
-            .expect("Failed to build checkpoint");
-        buf[0..hdr.len()].copy_from_slice(&hdr);
-        buf[CHECKPOINT_1..CHECKPOINT_1 + cp.len()].copy_from_slice(&cp);
-        buf[CHECKPOINT_2..CHECKPOINT_2 + cp.len()].copy_from_slice(&cp);
+    fn test_page_records() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        make_redo_log_file(path, size, FIRST_LSN).unwrap();
+
+        let lsn_a = FIRST_LSN + SIZE_OF_FILE_CHECKPOINT;
+        // WRITE 5:3 at offset 0x26 and a same page MEMSET.
+        let lsn_b = append_chain(
+            path,
+            lsn_a,
+            &[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb, 0xc3, 0x10, 0x04, 0xcc],
+        );
+        // WRITE 5:4 at offset 0x26.
+        let lsn_c = append_chain(path, lsn_b, &[0x35, 0x05, 0x04, 0x26, 0x11, 0x22]);
+        // WRITE 5:3 at offset 0x30.
+        append_chain(path, lsn_c, &[0x35, 0x05, 0x03, 0x30, 0x33, 0x44]);
 
-        let header = Redo::parse_header(&buf).expect("Failed to parse header");
-        let _checkpoint =
-            Redo::parse_header_checkpoint(&buf, &header, 0).expect("Failed to parse checkpoint");
+        let log = Redo::open(path).unwrap();
+
+        let records = log.page_records(5, 3, 0).unwrap();
+        assert_eq!(
+            records
+                .iter()
+                .map(|mtr| (mtr.lsn, mtr.op, mtr.payload.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (lsn_a, MtrOperation::Write, vec![0x26, 0xaa, 0xbb]),
+                (lsn_a + 6, MtrOperation::Memset, vec![0x10, 0x04, 0xcc]),
+                (lsn_c, MtrOperation::Write, vec![0x30, 0x33, 0x44]),
+            ]
+        );
+
+        // the page is already flushed up to the end of the first chain.
+        let records = log.page_records(5, 3, lsn_b).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].lsn, lsn_c);
+
+        let records = log.page_records(5, 4, lsn_b).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].payload, vec![0x26, 0x11, 0x22]);
+
+        assert!(log.page_records(5, 5, 0).unwrap().is_empty());
+        // FILE_CHECKPOINT is not a record of page 0:0.
+        assert!(log.page_records(0, 0, 0).unwrap().is_empty());
     }
 
     #[test]
@@ -624,35 +2277,876 @@ mod test {
         parse_redo_log_file(path, lsn).expect("Failed to parse redo log file");
     }
 
-    fn make_redo_log_file(path: &Path, size: u64, lsn: Lsn) -> std::io::Result<()> {
-        let first_lsn = FIRST_LSN;
-        let capacity = size - first_lsn;
+    #[test]
+    fn test_validate_checkpoint_lsn() {
+        let size = 10u64 * 1024 * 1024; // 10 MB
 
-        let mut log =
-            Redo::writer(path, first_lsn as usize, size).map_err(std::io::Error::other)?;
-        let mut writer = log.writer();
+        Redo::validate_checkpoint_lsn(FIRST_LSN, size, FIRST_LSN).unwrap();
+        Redo::validate_checkpoint_lsn(FIRST_LSN, size, size - 1).unwrap();
+        // a checkpoint in a later generation of the ring.
+        Redo::validate_checkpoint_lsn(FIRST_LSN, size, size).unwrap();
+        Redo::validate_checkpoint_lsn(FIRST_LSN, size, 3 * size).unwrap();
 
-        let header = RedoHeader::build_unencrypted_header_10_8(first_lsn, "test_creator")?;
-        writer.seek(std::io::SeekFrom::Start(0))?;
-        writer.write_all(&header)?;
+        assert!(Redo::validate_checkpoint_lsn(FIRST_LSN, size, FIRST_LSN - 1).is_err());
+        assert!(Redo::validate_checkpoint_lsn(FIRST_LSN, FIRST_LSN, FIRST_LSN).is_err());
+        assert!(
+            Redo::validate_checkpoint_lsn(
+                FIRST_LSN,
+                FIRST_LSN + SIZE_OF_FILE_CHECKPOINT,
+                FIRST_LSN
+            )
+            .is_err()
+        );
+        assert!(Redo::validate_checkpoint_lsn(FIRST_LSN, size, LSN_MAX).is_err());
+    }
 
-        let checkpoint = RedoHeader::build_unencrypted_header_10_8_checkpoint(lsn, lsn)?;
-        writer.seek(std::io::SeekFrom::Start(CHECKPOINT_1 as u64))?;
-        writer.write_all(&checkpoint)?;
+    #[test]
+    fn test_advance_checkpoint() {
+        let size = 2 * 1024 * 1024;
+
+        // in the first pass over the ring and after wrapping it twice.
+        for lsn in [FIRST_LSN + 100, FIRST_LSN + 2 * (size - FIRST_LSN) + 100] {
+            let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+            let path = temp_file.path();
+
+            let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+            log.write_checkpoint(lsn).unwrap();
+            let lsn_a = log
+                .write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+                .unwrap();
+            let lsn_b = log
+                .write_chain(&[0x35, 0x05, 0x04, 0x26, 0x11, 0x22])
+                .unwrap();
+            let end_lsn = log.end_lsn();
+            log.flush().unwrap();
+            drop(log);
+
+            assert!(Redo::open(path).unwrap().advance_checkpoint(lsn_b).is_err());
+
+            let mut log = Redo::open_mut(path).unwrap();
+            assert!(log.advance_checkpoint(lsn - 1).is_err());
+            assert!(log.advance_checkpoint(lsn_a + 1).is_err());
+            assert!(log.advance_checkpoint(end_lsn + 1).is_err());
+
+            log.advance_checkpoint(lsn_b).unwrap();
+            assert_eq!(log.checkpoint().checkpoint_lsn, Some(lsn_b));
+            assert_eq!(log.checkpoint().end_lsn, end_lsn);
+            drop(log);
+
+            let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+            assert!(warnings.is_empty(), "{warnings:?}");
+            assert_eq!(log.checkpoint().checkpoint_lsn, Some(lsn_b));
+            assert_eq!(log.checkpoint().end_lsn, end_lsn);
+
+            // the chains after the new checkpoint are intact and followed by the record.
+            let chains = log.reader().collect::<anyhow::Result<Vec<_>>>().unwrap();
+            assert_eq!(chains.len(), 2);
+            assert_eq!(chains[0].lsn, lsn_b);
+            assert_eq!(chains[1].lsn, end_lsn);
+            assert_eq!(chains[1].mtr[0].file_checkpoint_lsn, Some(lsn_b));
+        }
+    }
 
-        writer.seek(std::io::SeekFrom::Start(CHECKPOINT_2 as u64))?;
-        writer.write_all(&checkpoint)?;
+    #[test]
+    fn test_write_chain_full() {
+        let size = START_OFFSET + 4096;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
 
-        let mut file_checkpoint = vec![];
-        Mtr::build_file_checkpoint(&mut file_checkpoint, first_lsn, capacity, lsn).unwrap();
-        file_checkpoint.push(0x0); // end marker
+        let records = [0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb];
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        let err = loop {
+            if let Err(err) = log.write_chain(&records) {
+                break err;
+            }
+        };
+        assert!(err.to_string().contains("redo log is full"), "{err}");
+        let end_lsn = log.end_lsn();
+        assert!(end_lsn <= FIRST_LSN + log.capacity());
+        assert!(end_lsn + records.len() as Lsn + 6 > FIRST_LSN + log.capacity());
+
+        // a later checkpoint frees the ring for the chains that wrap it.
+        log.write_checkpoint(end_lsn).unwrap();
+        let lsn = log.write_chain(&records).unwrap();
+        assert_eq!(lsn, end_lsn + SIZE_OF_FILE_CHECKPOINT);
+        log.flush().unwrap();
+        drop(log);
+
+        let log = Redo::open(path).unwrap();
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(end_lsn));
+        let chains = log.reader().collect::<anyhow::Result<Vec<_>>>().unwrap();
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[1].lsn, lsn);
+        assert_eq!(chains[1].mtr[0].data, Some(vec![0xaa, 0xbb]));
+    }
 
-        writer.seek(std::io::SeekFrom::Start(lsn))?;
-        writer.write_all(&file_checkpoint)?;
+    #[test]
+    fn test_advance_checkpoint_full() {
+        let size = START_OFFSET + 4096;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        let lsn_a = log
+            .write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        while log.end_lsn() + SIZE_OF_FILE_CHECKPOINT < FIRST_LSN + log.capacity() {
+            log.write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+                .unwrap();
+        }
+        log.flush().unwrap();
+        drop(log);
+
+        // the record would overwrite the FILE_CHECKPOINT at the current checkpoint.
+        let mut log = Redo::open_mut(path).unwrap();
+        let err = log.advance_checkpoint(lsn_a).unwrap_err();
+        assert!(err.to_string().contains("redo log is full"), "{err}");
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(FIRST_LSN));
+    }
+
+    #[test]
+    fn test_fill_warning() {
+        let stats = |used| RedoFillStats {
+            capacity: 1000,
+            checkpoint_lsn: FIRST_LSN,
+            end_lsn: FIRST_LSN + used,
+            used,
+        };
+
+        assert!(stats(800).fill_warning(75).is_some());
+        assert_eq!(stats(800).fill_pct(), 80.0);
+        assert!(stats(500).fill_warning(75).is_none());
+        assert!(stats(750).fill_warning(75).is_none());
+        assert!(stats(751).fill_warning(75).is_some());
+
+        let size = 1024u64 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        make_redo_log_file(temp_file.path(), size, FIRST_LSN + 100).unwrap();
+        let log = Redo::open(temp_file.path()).unwrap();
+
+        let end_lsn = FIRST_LSN + 100 + log.capacity() * 8 / 10;
+        assert!(log.fill_stats_at(end_lsn).fill_warning(75).is_some());
+        let end_lsn = FIRST_LSN + 100 + log.capacity() / 2;
+        assert!(log.fill_stats_at(end_lsn).fill_warning(75).is_none());
+        assert_eq!(log.fill_stats().used, 0);
+    }
+
+    #[test]
+    fn test_page_lsn_info() {
+        let size = 1024u64 * 1024;
+        let capacity = size - FIRST_LSN;
+        let checkpoint_lsn = FIRST_LSN + capacity + 100;
+        let end_lsn = checkpoint_lsn + 1000;
+
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        make_redo_log_file(path, size, checkpoint_lsn).expect("Failed to create redo log file");
+
+        // patch the checkpoint end LSN, so that there is a region to be recovered.
+        let cp =
+            RedoHeader::build_unencrypted_header_10_8_checkpoint(checkpoint_lsn, end_lsn).unwrap();
+        let mut buf = std::fs::read(path).unwrap();
+        buf[CHECKPOINT_1..CHECKPOINT_1 + cp.len()].copy_from_slice(&cp);
+        buf[CHECKPOINT_2..CHECKPOINT_2 + cp.len()].copy_from_slice(&cp);
+        std::fs::write(path, buf).unwrap();
+
+        let log = Redo::open(path).unwrap();
+        assert_eq!(log.checkpoint().end_lsn, end_lsn);
+
+        let mut page = vec![0u8; 16384];
+        for (lsn, state) in [
+            (FIRST_LSN + 5, PageLsnState::Flushed),
+            (checkpoint_lsn, PageLsnState::Flushed),
+            (checkpoint_lsn + 1, PageLsnState::NeedsRedo),
+            (end_lsn, PageLsnState::NeedsRedo),
+            (end_lsn + 1, PageLsnState::Future),
+        ] {
+            mach::mach_write_to_8(&mut page[crate::fil0fil::FIL_PAGE_LSN as usize..], lsn).unwrap();
+            let info = log.page_lsn_info(&PageBuf::new(0x15, &page));
+            assert_eq!(info.page_lsn, lsn);
+            assert_eq!(info.state, state, "lsn {lsn}");
+        }
+
+        mach::mach_write_to_8(
+            &mut page[crate::fil0fil::FIL_PAGE_LSN as usize..],
+            checkpoint_lsn,
+        )
+        .unwrap();
+        let info = log.page_lsn_info(&PageBuf::new(0x15, &page));
+        assert_eq!(info.generation, 1);
+        assert_eq!(info.offset, FIRST_LSN + 100);
+    }
+
+    fn make_redo_log_file(path: &Path, size: u64, lsn: Lsn) -> std::io::Result<()> {
+        make_redo_log_file_at(path, size, FIRST_LSN, lsn)
+    }
+
+    fn make_redo_log_file_at(
+        path: &Path,
+        size: u64,
+        first_lsn: Lsn,
+        lsn: Lsn,
+    ) -> std::io::Result<()> {
+        let mut log = Redo::create(path, size, first_lsn).map_err(std::io::Error::other)?;
+        log.write_checkpoint(lsn).map_err(std::io::Error::other)?;
 
         Ok(())
     }
 
+    #[test]
+    fn test_create() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        assert!(Redo::create(path, size, FIRST_LSN - 1).is_err());
+        assert!(Redo::create(path, FIRST_LSN, FIRST_LSN).is_err());
+
+        // the checkpoint LSN of write-redo: below the first LSN, or too big to hold the
+        // FILE_CHECKPOINT record in the ring or in the LSN range.
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        assert!(log.write_checkpoint(FIRST_LSN - 1).is_err());
+        assert!(
+            log.write_checkpoint(LSN_MAX - SIZE_OF_FILE_CHECKPOINT + 1)
+                .is_err()
+        );
+        assert!(Redo::create(path, FIRST_LSN + SIZE_OF_FILE_CHECKPOINT, FIRST_LSN).is_err());
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        assert_eq!(log.header().creator, "mdbutil");
+        assert_eq!(log.end_lsn(), FIRST_LSN + SIZE_OF_FILE_CHECKPOINT);
+        assert!(log.write_chain(&[]).is_err());
+        assert!(log.write_chain(&[0x01]).is_err());
+
+        // WRITE 5:3 at offset 0x26 and a same page MEMSET, then WRITE 5:4.
+        let lsn_a = log
+            .write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb, 0xc3, 0x10, 0x04, 0xcc])
+            .unwrap();
+        let lsn_b = log
+            .write_chain(&[0x35, 0x05, 0x04, 0x26, 0x11, 0x22])
+            .unwrap();
+        assert_eq!(lsn_a, FIRST_LSN + SIZE_OF_FILE_CHECKPOINT);
+        assert_eq!(lsn_b, lsn_a + 10 + 5);
+        assert_eq!(log.end_lsn(), lsn_b + 6 + 5);
+        log.flush().unwrap();
+        drop(log);
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(log.header().first_lsn, FIRST_LSN);
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(FIRST_LSN));
+
+        let mut reader = log.reader();
+        let chains = (0..3)
+            .map(|_| reader.parse_next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(chains[0].mtr[0].file_checkpoint_lsn, Some(FIRST_LSN));
+        assert_eq!(chains[1].lsn, lsn_a);
+        assert_eq!(chains[1].mtr.len(), 2);
+        assert_eq!(chains[2].lsn, lsn_b);
+        assert_eq!(chains[2].mtr[0].page_no, 4);
+        assert!(reader.parse_next().is_err());
+    }
+
+    #[test]
+    fn test_iterator() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        let lsn_a = log
+            .write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        let lsn_b = log
+            .write_chain(&[0x35, 0x05, 0x04, 0x26, 0x11, 0x22])
+            .unwrap();
+        log.flush().unwrap();
+        drop(log);
+
+        let log = Redo::open(path).unwrap();
+        let mut reader = log.reader();
+        let chains = reader.chains().collect::<anyhow::Result<Vec<_>>>().unwrap();
+        assert_eq!(
+            chains.iter().map(|chain| chain.lsn).collect::<Vec<_>>(),
+            vec![FIRST_LSN, lsn_a, lsn_b]
+        );
+        assert_eq!(reader.chains().count(), 0);
+        assert_eq!(reader.count(), 0);
+        drop(log);
+
+        // a corrupted chain is yielded as an error once and ends the iteration.
+        let mut buf = std::fs::read(path).unwrap();
+        buf[lsn_b as usize + 4] ^= 0xff;
+        std::fs::write(path, &buf).unwrap();
+
+        let log = Redo::open(path).unwrap();
+        let mut reader = log.reader();
+        let mut chains = reader.chains();
+        assert_eq!(chains.next().unwrap().unwrap().lsn, FIRST_LSN);
+        assert_eq!(chains.next().unwrap().unwrap().lsn, lsn_a);
+        let err = chains.next().unwrap().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+            Some(std::io::ErrorKind::InvalidData),
+            "{err:?}"
+        );
+        assert!(chains.next().is_none());
+    }
+
+    #[test]
+    fn test_verify() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        log.write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb, 0xc3, 0x10, 0x04, 0xcc])
+            .unwrap();
+        let lsn_b = log
+            .write_chain(&[0x35, 0x05, 0x04, 0x26, 0x11, 0x22])
+            .unwrap();
+        log.write_chain(&[0x34, 0x05, 0x05, 0x26, 0x33]).unwrap();
+        let end_lsn = log.end_lsn();
+        log.flush().unwrap();
+        drop(log);
+
+        let verification = Redo::open(path).unwrap().verify();
+        assert_eq!(
+            verification,
+            RedoVerification {
+                chains: 4,
+                records: 5,
+                corrupted: 0,
+                first_lsn: Some(FIRST_LSN),
+                end_lsn: Some(end_lsn),
+                first_corruption_lsn: None,
+                checkpoint_lsn: Some(FIRST_LSN),
+                file_checkpoint_lsn: Some(FIRST_LSN),
+                error: None,
+            }
+        );
+        assert!(verification.is_ok());
+        assert!(verification.checkpoint_matches());
+        assert!(
+            verification
+                .to_string()
+                .ends_with("checkpoint_matches=true ok=true")
+        );
+
+        let mut buf = std::fs::read(path).unwrap();
+        buf[lsn_b as usize + 4] ^= 0xff;
+        std::fs::write(path, &buf).unwrap();
+
+        // the chain after the corrupted one is still verified.
+        let verification = Redo::open(path).unwrap().verify();
+        assert_eq!(verification.chains, 3);
+        assert_eq!(verification.records, 4);
+        assert_eq!(verification.corrupted, 1);
+        assert_eq!(verification.end_lsn, Some(lsn_b));
+        assert_eq!(verification.first_corruption_lsn, Some(lsn_b));
+        assert!(!verification.is_ok());
+        assert!(
+            verification
+                .error
+                .as_deref()
+                .is_some_and(|err| err.contains("checksum is invalid")),
+            "{verification}"
+        );
+    }
+
+    #[test]
+    fn test_verify_file_checkpoint() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        let lsn = log
+            .write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        let end_lsn = log.end_lsn();
+        log.flush().unwrap();
+        drop(log);
+
+        // the record at the checkpoint LSN names it, but it is not at the end LSN.
+        let mut log = Redo::open_mut(path).unwrap();
+        log.write_checkpoint(FIRST_LSN, lsn).unwrap();
+        let verification = log.verify();
+        assert_eq!(verification.file_checkpoint_lsn, None);
+        assert!(!verification.checkpoint_matches());
+
+        // the record written at the end of the log names the new checkpoint.
+        log.advance_checkpoint(lsn).unwrap();
+        let verification = log.verify();
+        assert_eq!(verification.checkpoint_lsn, Some(lsn));
+        assert_eq!(verification.file_checkpoint_lsn, Some(lsn));
+        assert_eq!(
+            verification.end_lsn,
+            Some(end_lsn + SIZE_OF_FILE_CHECKPOINT)
+        );
+        assert!(verification.checkpoint_matches());
+        assert!(verification.is_ok());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_dump_yaml() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        log.write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        log.flush().unwrap();
+        drop(log);
+
+        let dump = Redo::open(path).unwrap().dump().unwrap();
+        assert_eq!(dump.chains.len(), 2);
+
+        let yaml = serde_yaml::to_string(&dump).unwrap();
+        assert!(yaml.contains("  creator: mdbutil\n"), "{yaml}");
+        assert!(
+            yaml.contains(&format!("  checkpoint_lsn: {FIRST_LSN}\n")),
+            "{yaml}"
+        );
+        assert!(yaml.contains("    op: FileCheckpoint\n"), "{yaml}");
+        assert!(yaml.contains("    op: Write\n"), "{yaml}");
+        assert!(yaml.contains("    payload: 26aabb\n"), "{yaml}");
+        assert!(yaml.contains("    data: aabb\n"), "{yaml}");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_dump_json() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        log.write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        log.flush().unwrap();
+        drop(log);
+
+        let dump = Redo::open(path).unwrap().dump().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&dump.to_json().unwrap()).unwrap();
+        let items = json.as_array().unwrap();
+        assert_eq!(items.len(), 2 + dump.chains.len());
+        assert_eq!(items[0]["creator"], "mdbutil");
+        assert_eq!(items[1]["checkpoint_lsn"], FIRST_LSN);
+        assert_eq!(items[2]["mtr"][0]["op"], "FileCheckpoint");
+        assert_eq!(items[3]["mtr"][0]["op"], "Write");
+        assert_eq!(items[3]["mtr"][0]["data"], "aabb");
+    }
+
+    #[test]
+    fn test_sequence_bit_agrees() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        Redo::create(path, size, FIRST_LSN)
+            .unwrap()
+            .flush()
+            .unwrap();
+
+        let log = Redo::open(path).unwrap();
+        let capacity = log.capacity();
+
+        for lsn in [
+            FIRST_LSN,
+            FIRST_LSN + capacity - 12,
+            FIRST_LSN + capacity - 1,
+            FIRST_LSN + capacity,
+            FIRST_LSN + 2 * capacity - 11,
+            FIRST_LSN + 2 * capacity,
+        ] {
+            let bit = log.get_sequence_bit(lsn);
+            assert_eq!(
+                bit,
+                lsn::get_sequence_bit(FIRST_LSN, capacity, lsn),
+                "{lsn}"
+            );
+
+            // the termination marker that build_file_checkpoint() writes after 11 bytes.
+            let mut buf = vec![];
+            mtr::Mtr::build_file_checkpoint(&mut buf, FIRST_LSN, capacity, lsn).unwrap();
+            assert_eq!(buf[11], log.get_sequence_bit(lsn + 11), "{lsn}");
+        }
+
+        assert_eq!(log.get_sequence_bit(FIRST_LSN + capacity - 1), 1);
+        assert_eq!(log.get_sequence_bit(FIRST_LSN + capacity), 0);
+    }
+
+    #[test]
+    fn test_lsn_to_offset() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        Redo::create(path, size, FIRST_LSN)
+            .unwrap()
+            .flush()
+            .unwrap();
+
+        let log = Redo::open(path).unwrap();
+        let capacity = log.capacity();
+        assert_eq!(capacity, size - FIRST_LSN);
+
+        // before the first wrap
+        let lsn = FIRST_LSN + 100;
+        assert_eq!(log.lsn_to_offset(lsn), lsn as usize);
+        assert_eq!(log.offset_to_lsn(lsn as usize, FIRST_LSN), lsn);
+
+        // after wrapping once
+        let lsn = FIRST_LSN + capacity + 100;
+        assert_eq!(log.lsn_to_offset(lsn), FIRST_LSN as usize + 100);
+        assert_eq!(log.offset_to_lsn(FIRST_LSN as usize + 100, lsn), lsn);
+        assert_eq!(
+            log.offset_to_lsn(FIRST_LSN as usize + 100, FIRST_LSN),
+            FIRST_LSN + 100
+        );
+        // the end of the previous generation is closer than the end of this one.
+        assert_eq!(
+            log.offset_to_lsn(size as usize - 1, lsn),
+            FIRST_LSN + capacity - 1
+        );
+    }
+
+    #[test]
+    fn test_repair_checkpoint() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        let lsn = log
+            .write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        let end_lsn = log.end_lsn();
+        log.flush().unwrap();
+        drop(log);
+
+        // the FILE_CHECKPOINT record is written at the end of the log, after the LSN it
+        // names, like the server does.
+        let mut log = Redo::open_mut(path).unwrap();
+        log.advance_checkpoint(lsn).unwrap();
+        drop(log);
+
+        // the header still points at the first FILE_CHECKPOINT record.
+        let mut buf = std::fs::read(path).unwrap();
+        let stale =
+            RedoHeader::build_unencrypted_header_10_8_checkpoint(FIRST_LSN, FIRST_LSN).unwrap();
+        for pos in [CHECKPOINT_1, CHECKPOINT_2] {
+            buf[pos..pos + stale.len()].copy_from_slice(&stale);
+        }
+        std::fs::write(path, &buf).unwrap();
+
+        let mut log = Redo::open_mut(path).unwrap();
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(FIRST_LSN));
+        assert_eq!(
+            log.find_file_checkpoints(),
+            vec![(FIRST_LSN, FIRST_LSN), (lsn, end_lsn)]
+        );
+        assert!(log.write_checkpoint(FIRST_LSN - 1, end_lsn).is_err());
+        log.write_checkpoint(lsn, end_lsn).unwrap();
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(lsn));
+        drop(log);
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(lsn));
+        assert_eq!(log.checkpoint().end_lsn, end_lsn);
+
+        let mut reader = log.reader();
+        let chain = reader.parse_next().unwrap();
+        assert_eq!(chain.lsn, lsn);
+        assert_eq!(chain.mtr[0].page_no, 3);
+        let chain = reader.parse_next().unwrap();
+        assert_eq!(chain.lsn, end_lsn);
+        assert_eq!(chain.mtr[0].file_checkpoint_lsn, Some(lsn));
+        assert!(reader.parse_next().is_err());
+
+        let mut log = Redo::open(path).unwrap();
+        assert!(log.write_checkpoint(lsn, end_lsn).is_err());
+    }
+
+    #[test]
+    fn test_write_checkpoint() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        log.write_chain(&[0x35, 0x05, 0x03, 0x26, 0xaa, 0xbb])
+            .unwrap();
+        let end_lsn = log.end_lsn();
+        log.write_checkpoint(end_lsn).unwrap();
+        log.flush().unwrap();
+        drop(log);
+
+        let mut log = Redo::open_mut(path).unwrap();
+        assert!(log.write_checkpoint(FIRST_LSN - 1, end_lsn).is_err());
+        assert!(log.write_checkpoint(end_lsn, FIRST_LSN).is_err());
+        log.write_checkpoint(FIRST_LSN, end_lsn).unwrap();
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(FIRST_LSN));
+        assert_eq!(log.checkpoint().end_lsn, end_lsn);
+        let in_memory = log.checkpoint().clone();
+        drop(log);
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(FIRST_LSN));
+        assert_eq!(log.checkpoint().end_lsn, end_lsn);
+        assert_eq!(log.checkpoint().checkpoints, in_memory.checkpoints);
+        assert_eq!(log.checkpoint().checkpoint_no, in_memory.checkpoint_no);
+
+        // recovery starts at the first chain again.
+        assert_eq!(log.reader().count(), 3);
+    }
+
+    #[test]
+    fn test_write_checkpoint_wrapped() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        // the checkpoint is in the third pass over the ring.
+        let lsn = FIRST_LSN + 2 * (size - FIRST_LSN) + 100;
+        assert!(lsn > size);
+        make_redo_log_file(path, size, lsn).unwrap();
+
+        let mut log = Redo::open_mut(path).unwrap();
+        assert!(log.write_checkpoint(FIRST_LSN - 1, lsn).is_err());
+        log.write_checkpoint(lsn, lsn + SIZE_OF_FILE_CHECKPOINT)
+            .unwrap();
+        drop(log);
+
+        let (log, warnings) = Redo::open_with_warnings(path).unwrap();
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(log.checkpoint().checkpoint_lsn, Some(lsn));
+        assert_eq!(log.checkpoint().end_lsn, lsn + SIZE_OF_FILE_CHECKPOINT);
+
+        let chain = log.reader().parse_next().unwrap();
+        assert_eq!(chain.lsn, lsn);
+        assert_eq!(chain.mtr[0].file_checkpoint_lsn, Some(lsn));
+    }
+
+    #[test]
+    fn test_open_reader() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        for i in 0..100u8 {
+            log.write_chain(&[0x35, 0x05, i, 0x26, i, 0xbb]).unwrap();
+        }
+        log.flush().unwrap();
+        drop(log);
+
+        let mmap_log = Redo::open(path).unwrap();
+        let mut reader = mmap_log.reader();
+        let expected = std::iter::from_fn(|| reader.parse_next().ok()).collect::<Vec<_>>();
+        assert_eq!(expected.len(), 101);
+
+        let file = std::fs::File::open(path).unwrap();
+        let mut stream = Redo::open_reader(file, size).unwrap();
+        assert_eq!(stream.header(), mmap_log.header());
+        assert_eq!(stream.checkpoint(), mmap_log.checkpoint());
+
+        let chains = std::iter::from_fn(|| stream.parse_next().ok()).collect::<Vec<_>>();
+        assert_eq!(chains, expected);
+        assert_eq!(stream.lsn(), reader.reader().pos());
+
+        // from an in-memory cursor, too small and too short inputs.
+        let bytes = std::fs::read(path).unwrap();
+        let mut stream = Redo::open_reader(std::io::Cursor::new(&bytes), size).unwrap();
+        assert_eq!(stream.parse_next().unwrap(), expected[0]);
+        assert!(Redo::open_reader(std::io::Cursor::new(&bytes), START_OFFSET).is_err());
+        let mut short =
+            Redo::open_reader(std::io::Cursor::new(&bytes[..size as usize / 2]), size).unwrap();
+        assert!(short.parse_next().is_err());
+
+        // a file that ends before the first LSN of its header.
+        let first_lsn = FIRST_LSN + 4096;
+        let mut truncated = vec![0u8; first_lsn as usize];
+        truncated[..512].copy_from_slice(
+            &RedoHeader::build_unencrypted_header_10_8(first_lsn, "test").unwrap(),
+        );
+        let Err(err) = Redo::open_reader(std::io::Cursor::new(&truncated), first_lsn) else {
+            panic!("opened a truncated log");
+        };
+        assert!(err.to_string().contains("truncated"), "{err:#}");
+    }
+
+    #[test]
+    fn test_open_reader_window() {
+        let size = 3 * STREAM_WINDOW_SIZE;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        // the chains span more than twice the window of the stream reader.
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        let data = (0..4000).map(|i| i as u8).collect::<Vec<_>>();
+        while log.end_lsn() < FIRST_LSN + 5 * STREAM_WINDOW_SIZE / 2 {
+            let mut records = vec![];
+            Mtr::build_write(&mut records, 5, 3, 0x26, &data).unwrap();
+            log.write_chain(&records).unwrap();
+        }
+        log.flush().unwrap();
+        drop(log);
+
+        let mmap_log = Redo::open(path).unwrap();
+        let expected = mmap_log
+            .reader()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+
+        let file = std::fs::File::open(path).unwrap();
+        let mut stream = Redo::open_reader(file, size).unwrap();
+        let chains = std::iter::from_fn(|| stream.parse_next().ok()).collect::<Vec<_>>();
+        assert_eq!(chains.len(), expected.len());
+        assert_eq!(chains, expected);
+        assert_eq!(stream.buf.len() as u64, STREAM_WINDOW_SIZE);
+
+        // the chains cross the end of a ring smaller than the window.
+        let size = STREAM_WINDOW_SIZE / 2;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        log.write_checkpoint(size - 10000).unwrap();
+        while log.end_lsn() < 2 * size - FIRST_LSN - 20000 {
+            let mut records = vec![];
+            Mtr::build_write(&mut records, 5, 3, 0x26, &data).unwrap();
+            log.write_chain(&records).unwrap();
+        }
+        log.flush().unwrap();
+        drop(log);
+
+        let expected = Redo::open(path)
+            .unwrap()
+            .reader()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        assert!(expected.last().unwrap().lsn > size);
+
+        let file = std::fs::File::open(path).unwrap();
+        let mut stream = Redo::open_reader(file, size).unwrap();
+        let chains = std::iter::from_fn(|| stream.parse_next().ok()).collect::<Vec<_>>();
+        assert_eq!(chains, expected);
+    }
+
+    #[test]
+    fn test_scan_file_ops() {
+        let size = 2 * 1024 * 1024;
+        let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+        let path = temp_file.path();
+
+        let file_op = |op: u8, space_id: u8, name: &[u8]| {
+            let mut record = vec![op | (2 + name.len()) as u8, space_id, 0x00];
+            record.extend_from_slice(name);
+            record
+        };
+
+        let mut log = Redo::create(path, size, FIRST_LSN).unwrap();
+        log.write_chain(&file_op(0xb0, 5, b"./db/a.ibd")).unwrap(); // FILE_MODIFY
+        log.write_chain(&[file_op(0xb0, 6, b"./db/b.ibd"), file_op(0xa0, 5, b"a\0c")].concat())
+            .unwrap(); // FILE_MODIFY, FILE_RENAME
+        log.write_chain(&file_op(0x90, 7, b"./db/d.ibd")).unwrap(); // FILE_DELETE
+        log.write_chain(&file_op(0x80, 8, b"./db/e.ibd")).unwrap(); // FILE_CREATE
+        log.write_chain(&file_op(0x90, 8, b"./db/e.ibd")).unwrap(); // FILE_DELETE
+        log.flush().unwrap();
+        drop(log);
+
+        let log = Redo::open(path).unwrap();
+        let files = log.scan_file_ops().unwrap();
+        assert_eq!(
+            files,
+            HashMap::from([(5, "c".to_string()), (6, "./db/b.ibd".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_parse_across_generations() {
+        let size = START_OFFSET + 4096;
+        let capacity = size - FIRST_LSN;
+        let chain_len = SIZE_OF_FILE_CHECKPOINT;
+
+        // wrap 0 -> 1, 1 -> 2 and a late 5 -> 6, where consecutive chains have
+        // different sequence bits.
+        for generation in [0, 1, 5] {
+            let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
+            let path = temp_file.path();
+
+            let wrap_lsn = FIRST_LSN + capacity * (generation + 1);
+            let start_lsn = wrap_lsn - 3 * chain_len + 5;
+            let lsns = (0..6)
+                .map(|i| start_lsn + i * chain_len)
+                .collect::<Vec<_>>();
+            let end_lsn = start_lsn + lsns.len() as u64 * chain_len;
+            {
+                let mut log = Redo::writer(path, FIRST_LSN as usize, size).unwrap();
+                let mut writer = log.writer();
+
+                let header = RedoHeader::build_unencrypted_header_10_8(FIRST_LSN, "test").unwrap();
+                writer.seek(std::io::SeekFrom::Start(0)).unwrap();
+                writer.write_all(&header).unwrap();
+
+                let checkpoint =
+                    RedoHeader::build_unencrypted_header_10_8_checkpoint(start_lsn, end_lsn)
+                        .unwrap();
+                for offset in [CHECKPOINT_1, CHECKPOINT_2] {
+                    writer
+                        .seek(std::io::SeekFrom::Start(offset as u64))
+                        .unwrap();
+                    writer.write_all(&checkpoint).unwrap();
+                }
+
+                // a stale chain of the previous generation right after the log end.
+                let mut stale = vec![];
+                Mtr::build_file_checkpoint(&mut stale, FIRST_LSN, capacity, end_lsn - capacity)
+                    .unwrap();
+                writer.seek(std::io::SeekFrom::Start(end_lsn)).unwrap();
+                writer.write_all(&stale).unwrap();
+
+                for &lsn in &lsns {
+                    let mut chain = vec![];
+                    Mtr::build_file_checkpoint(&mut chain, FIRST_LSN, capacity, lsn).unwrap();
+                    writer.seek(std::io::SeekFrom::Start(lsn)).unwrap();
+                    writer.write_all(&chain).unwrap();
+                }
+            }
+
+            let log = Redo::open(path).unwrap();
+            assert_ne!(
+                log.get_sequence_bit(lsns[0] + chain_len - 5),
+                log.get_sequence_bit(lsns[5] + chain_len - 5),
+                "the scan must cross a generation boundary"
+            );
+
+            let mut reader = log.reader();
+            let mut parsed = vec![];
+            let err = loop {
+                match reader.parse_next() {
+                    Ok(chain) => {
+                        chain.validate_lengths().unwrap();
+                        parsed.push(chain.mtr[0].file_checkpoint_lsn.unwrap());
+                    }
+                    Err(err) => break err,
+                }
+            };
+
+            assert_eq!(parsed, lsns, "generation {generation}");
+            assert_eq!(
+                err.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+                Some(std::io::ErrorKind::NotFound),
+                "the stale chain must not be parsed: {err:?}"
+            );
+        }
+    }
+
     fn parse_redo_log_file(path: &Path, lsn: Lsn) -> anyhow::Result<()> {
         let log = Redo::open(path)?;
 
@@ -665,20 +3159,8 @@ mod test {
         let mut reader = log.reader();
         let mut mtrs = 0usize;
 
-        loop {
-            let chain = match reader.parse_next() {
-                Ok(chain) => chain,
-                Err(err) => {
-                    // test for EOM.
-                    if let Some(err) = err.downcast_ref::<std::io::Error>()
-                        && err.kind() == std::io::ErrorKind::NotFound
-                    {
-                        break;
-                    }
-
-                    panic!("Failed to parse MTR: {err}");
-                }
-            };
+        for chain in reader.chains() {
+            let chain = chain.unwrap_or_else(|err| panic!("Failed to parse MTR: {err}"));
 
             mtrs += chain.mtr.len();
 
@@ -0,0 +1,166 @@
+use aes::{
+    Aes128, Aes192, Aes256,
+    cipher::{BlockEncrypt, KeyInit, KeyIvInit, StreamCipher, generic_array::GenericArray},
+};
+use anyhow::bail;
+
+use crate::{
+    keyring::Keyring,
+    log::{MY_AES_BLOCK_SIZE, RedoCrypt},
+    mtr0log::{mlog_decode_varint, mlog_decode_varint_length},
+};
+
+/// The AES key the records of an encrypted redo log are encrypted with, see crypt_info_t.
+pub type LogCryptKey = [u8; MY_AES_BLOCK_SIZE];
+
+/// Encrypts a single block with AES in ECB mode, see my_aes_crypt(MY_AES_ECB). The key
+/// must be 16, 24 or 32 bytes long.
+pub fn aes_ecb_encrypt(
+    key: &[u8],
+    block: &[u8; MY_AES_BLOCK_SIZE],
+) -> anyhow::Result<[u8; MY_AES_BLOCK_SIZE]> {
+    let mut out = GenericArray::clone_from_slice(block);
+
+    match key.len() {
+        16 => Aes128::new(GenericArray::from_slice(key)).encrypt_block(&mut out),
+        24 => Aes192::new(GenericArray::from_slice(key)).encrypt_block(&mut out),
+        32 => Aes256::new(GenericArray::from_slice(key)).encrypt_block(&mut out),
+        len => bail!("AES key must be 16, 24 or 32 bytes long, found {len}"),
+    }
+
+    Ok(out.into())
+}
+
+/// Encrypts or decrypts `buf` in place with AES-128 in CTR mode, where the whole `iv` is
+/// the big-endian block counter, see my_aes_crypt(MY_AES_CTR).
+pub fn aes_ctr(key: &LogCryptKey, iv: &[u8; MY_AES_BLOCK_SIZE], buf: &mut [u8]) {
+    ctr::Ctr128BE::<Aes128>::new(key.into(), iv.into()).apply_keystream(buf);
+}
+
+/// Encrypts or decrypts the serialized records of a chain in place, see
+/// log_encrypt_buf(). The record headers and lengths stay in plaintext. Every record body
+/// is encrypted separately with AES-CTR, where the IV is the 8 bytes stored after the
+/// termination marker of the chain followed by the page identifier, which is in
+/// plaintext for the records that carry it and kept for the same-page and file records.
+pub fn log_crypt_buf(key: &LogCryptKey, nonce: &[u8; 8], buf: &mut [u8]) -> std::io::Result<()> {
+    let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidData);
+
+    let mut iv = [0u8; MY_AES_BLOCK_SIZE];
+    iv[..8].copy_from_slice(nonce);
+
+    let mut l = 0usize;
+    while l < buf.len() {
+        let b = buf[l];
+        l += 1;
+
+        let mut rlen = (b & 0xf) as usize;
+        if rlen == 0 {
+            let lenlen = mlog_decode_varint_length(*buf.get(l).ok_or_else(invalid)?) as usize;
+            let addlen = mlog_decode_varint(buf.get(l..).ok_or_else(invalid)?)? as usize;
+            rlen = addlen + 15 - lenlen;
+            l += lenlen;
+        }
+
+        if b < 0x80 {
+            // Add the page identifier to the initialization vector.
+            for field in [8, 12] {
+                let idlen = mlog_decode_varint_length(*buf.get(l).ok_or_else(invalid)?) as usize;
+                if idlen > rlen {
+                    return Err(invalid());
+                }
+                let id = mlog_decode_varint(buf.get(l..).ok_or_else(invalid)?)?;
+                iv[field..field + 4].copy_from_slice(&id.to_be_bytes());
+                l += idlen;
+                rlen -= idlen;
+            }
+        }
+
+        let body = buf.get_mut(l..l + rlen).ok_or_else(invalid)?;
+        aes_ctr(key, &iv, body);
+        l += rlen;
+    }
+
+    Ok(())
+}
+
+/// Derives the key of the log records: the crypt message of the log header encrypted
+/// with the key version of LOG_DEFAULT_ENCRYPTION_KEY from the keyring.
+/// See init_crypt_key().
+pub fn init_crypt_key(crypt: &RedoCrypt, keyring: &dyn Keyring) -> anyhow::Result<LogCryptKey> {
+    let Some(key) = keyring.get_key(crypt.key_id, crypt.key_version) else {
+        bail!(
+            "obtaining redo log encryption key version {} of key id {} failed",
+            crypt.key_version,
+            crypt.key_id
+        );
+    };
+
+    aes_ecb_encrypt(&key, &crypt.crypt_msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{keyring::MemoryKeyring, log::LOG_DEFAULT_ENCRYPTION_KEY};
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_init_crypt_key() {
+        // FIPS-197 appendix C.1 and C.3.
+        let crypt = RedoCrypt {
+            key_id: LOG_DEFAULT_ENCRYPTION_KEY,
+            key_version: 2,
+            crypt_msg: hex("00112233445566778899aabbccddeeff").try_into().unwrap(),
+            crypt_nonce: [0; 4],
+        };
+
+        let mut keyring = MemoryKeyring::new();
+        assert!(init_crypt_key(&crypt, &keyring).is_err());
+
+        keyring.insert(1, 2, hex("000102030405060708090a0b0c0d0e0f"));
+        assert_eq!(
+            init_crypt_key(&crypt, &keyring).unwrap().to_vec(),
+            hex("69c4e0d86a7b0430d8cdb78070b4c55a")
+        );
+
+        keyring.insert(
+            1,
+            2,
+            hex("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"),
+        );
+        assert_eq!(
+            init_crypt_key(&crypt, &keyring).unwrap().to_vec(),
+            hex("8ea2b7ca516745bfeafc49904b496089")
+        );
+
+        keyring.insert(1, 2, vec![0; 20]);
+        assert!(init_crypt_key(&crypt, &keyring).is_err());
+    }
+
+    #[test]
+    fn test_aes_ctr_decrypt() {
+        // NIST SP 800-38A F.5.2 CTR-AES128.Decrypt.
+        let key = hex("2b7e151628aed2a6abf7158809cf4f3c").try_into().unwrap();
+        let iv = hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff").try_into().unwrap();
+
+        let mut buf =
+            hex("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5");
+        aes_ctr(&key, &iv, &mut buf);
+        assert_eq!(
+            buf,
+            hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35c")
+        );
+
+        aes_ctr(&key, &iv, &mut buf);
+        assert_eq!(
+            buf,
+            hex("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5")
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Log sequence number arithmetic over the redo log ring.
+//!
+//! The record area of the redo log file starts at `first_lsn` (the header size) and is
+//! `capacity` bytes long (file size - header). An LSN at or above `first_lsn` maps to the
+//! file offset `first_lsn + (lsn - first_lsn) % capacity`, and the number of times the
+//! ring wrapped before it is its generation.
+
+use crate::Lsn;
+
+/// Returns the file offset of `lsn`. LSNs below `first_lsn` address the header and map
+/// to themselves, as does any LSN of an empty ring.
+#[inline]
+pub fn lsn_to_offset(first_lsn: Lsn, capacity: u64, lsn: Lsn) -> u64 {
+    if lsn < first_lsn || capacity == 0 {
+        return lsn;
+    }
+
+    first_lsn + (lsn - first_lsn) % capacity
+}
+
+/// Returns the LSN that maps to the file `offset` closest to `near`, as an offset is
+/// reached once per generation of the ring. Offsets in the header and all offsets of an
+/// empty ring map to themselves.
+pub fn offset_to_lsn(first_lsn: Lsn, capacity: u64, offset: u64, near: Lsn) -> Lsn {
+    if offset < first_lsn || capacity == 0 {
+        return offset;
+    }
+
+    let base = first_lsn + lsn_gen(first_lsn, capacity, near).saturating_mul(capacity);
+    let lsn = base.saturating_add((offset - first_lsn) % capacity);
+
+    [
+        lsn.checked_sub(capacity),
+        Some(lsn),
+        lsn.checked_add(capacity),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|&lsn| lsn >= first_lsn)
+    .min_by_key(|&lsn| lsn.abs_diff(near))
+    .unwrap_or(lsn)
+}
+
+/// Returns how many times the ring wrapped before `lsn`. LSNs below `first_lsn` and
+/// all LSNs of an empty ring are in generation 0.
+#[inline]
+pub fn lsn_gen(first_lsn: Lsn, capacity: u64, lsn: Lsn) -> u64 {
+    lsn.saturating_sub(first_lsn)
+        .checked_div(capacity)
+        .unwrap_or(0)
+}
+
+/// Determine the sequence bit at a log sequence number.
+/// The sequence bit is used to determine whether the log record
+/// corresponds to the current generation (wrap) of the redo log.
+/// It is 1 in even generations and 0 in odd ones.
+#[inline]
+pub fn get_sequence_bit(first_lsn: Lsn, capacity: u64, lsn: Lsn) -> u8 {
+    if lsn_gen(first_lsn, capacity, lsn) & 1 == 0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LSN_MAX;
+
+    const FIRST: Lsn = 12288;
+    const CAP: u64 = 1000;
+
+    #[test]
+    fn test_offset_and_gen() {
+        // header
+        assert_eq!(lsn_to_offset(FIRST, CAP, 0), 0);
+        assert_eq!(lsn_to_offset(FIRST, CAP, FIRST - 1), FIRST - 1);
+        assert_eq!(lsn_gen(FIRST, CAP, FIRST - 1), 0);
+
+        // first generation
+        assert_eq!(lsn_to_offset(FIRST, CAP, FIRST), FIRST);
+        assert_eq!(lsn_to_offset(FIRST, CAP, FIRST + CAP - 1), FIRST + CAP - 1);
+        assert_eq!(lsn_gen(FIRST, CAP, FIRST), 0);
+        assert_eq!(lsn_gen(FIRST, CAP, FIRST + CAP - 1), 0);
+
+        // wrap boundary
+        assert_eq!(lsn_to_offset(FIRST, CAP, FIRST + CAP), FIRST);
+        assert_eq!(lsn_gen(FIRST, CAP, FIRST + CAP), 1);
+        assert_eq!(lsn_to_offset(FIRST, CAP, FIRST + 2 * CAP + 7), FIRST + 7);
+        assert_eq!(lsn_gen(FIRST, CAP, FIRST + 2 * CAP + 7), 2);
+
+        // no overflow at the top of the LSN range
+        let top = lsn_to_offset(FIRST, CAP, LSN_MAX);
+        assert!((FIRST..FIRST + CAP).contains(&top));
+        assert_eq!(lsn_gen(FIRST, CAP, LSN_MAX), (LSN_MAX - FIRST) / CAP);
+
+        // empty ring
+        assert_eq!(lsn_to_offset(FIRST, 0, FIRST + 5), FIRST + 5);
+        assert_eq!(lsn_gen(FIRST, 0, FIRST + 5), 0);
+    }
+
+    #[test]
+    fn test_offset_to_lsn() {
+        // header
+        assert_eq!(offset_to_lsn(FIRST, CAP, 100, FIRST + 5 * CAP), 100);
+
+        // first generation
+        assert_eq!(offset_to_lsn(FIRST, CAP, FIRST + 7, FIRST), FIRST + 7);
+        assert_eq!(offset_to_lsn(FIRST, CAP, FIRST + 7, 0), FIRST + 7);
+
+        // second generation, and the end of the first one just before the wrap
+        let near = FIRST + CAP + 10;
+        assert_eq!(
+            offset_to_lsn(FIRST, CAP, FIRST + 20, near),
+            FIRST + CAP + 20
+        );
+        assert_eq!(
+            offset_to_lsn(FIRST, CAP, FIRST + CAP - 5, near),
+            FIRST + CAP - 5
+        );
+        assert_eq!(
+            offset_to_lsn(FIRST, CAP, FIRST + 5, FIRST + CAP - 10),
+            FIRST + CAP + 5
+        );
+
+        for lsn in [
+            FIRST,
+            FIRST + 1,
+            FIRST + CAP - 1,
+            FIRST + CAP,
+            FIRST + 3 * CAP + 7,
+        ] {
+            let offset = lsn_to_offset(FIRST, CAP, lsn);
+            assert_eq!(offset_to_lsn(FIRST, CAP, offset, lsn), lsn);
+        }
+
+        // empty ring
+        assert_eq!(offset_to_lsn(FIRST, 0, FIRST + 5, LSN_MAX), FIRST + 5);
+    }
+
+    #[test]
+    fn test_sequence_bit() {
+        assert_eq!(get_sequence_bit(FIRST, CAP, 0), 1);
+        assert_eq!(get_sequence_bit(FIRST, CAP, FIRST), 1);
+        assert_eq!(get_sequence_bit(FIRST, CAP, FIRST + CAP - 1), 1);
+        assert_eq!(get_sequence_bit(FIRST, CAP, FIRST + CAP), 0);
+        assert_eq!(get_sequence_bit(FIRST, CAP, FIRST + 2 * CAP - 1), 0);
+        assert_eq!(get_sequence_bit(FIRST, CAP, FIRST + 2 * CAP), 1);
+        assert_eq!(get_sequence_bit(FIRST, 0, FIRST + 2 * CAP), 1);
+    }
+}
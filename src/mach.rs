@@ -1,32 +1,273 @@
 // Functions related to encoding.
-use std::io::{Result, Write};
+use std::io::{Read, Result, Write};
 
-use byteorder::{BigEndian, ByteOrder};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
 
 // MariaDB uses big-endian byte order for its Mach-O files.
 // The most significant byte is at the lowest address.
 type E = BigEndian;
 
+pub fn mach_read_from_1(buf: &[u8]) -> u8 {
+    buf[0]
+}
+
 pub fn mach_read_from_2(buf: &[u8]) -> u16 {
     E::read_u16(buf)
 }
 
+pub fn mach_read_from_3(buf: &[u8]) -> u32 {
+    E::read_u24(buf)
+}
+
 pub fn mach_read_from_4(buf: &[u8]) -> u32 {
     E::read_u32(buf)
 }
 
+pub fn mach_read_from_6(buf: &[u8]) -> u64 {
+    E::read_uint(buf, 6)
+}
+
+pub fn mach_read_from_7(buf: &[u8]) -> u64 {
+    E::read_uint(buf, 7)
+}
+
 pub fn mach_read_from_8(buf: &[u8]) -> u64 {
     E::read_u64(buf)
 }
 
+pub fn mach_write_to_1(mut buf: impl Write, value: u8) -> Result<()> {
+    buf.write_all(&[value])
+}
+
 pub fn mach_write_to_2(mut buf: impl Write, value: u16) -> Result<()> {
     buf.write_all(&value.to_be_bytes())
 }
 
+pub fn mach_write_to_3(mut buf: impl Write, value: u32) -> Result<()> {
+    debug_assert!(value < 1 << 24);
+    buf.write_all(&value.to_be_bytes()[1..])
+}
+
 pub fn mach_write_to_4(mut buf: impl Write, value: u32) -> Result<()> {
     buf.write_all(&value.to_be_bytes())
 }
 
+pub fn mach_write_to_6(mut buf: impl Write, value: u64) -> Result<()> {
+    debug_assert!(value < 1 << 48);
+    buf.write_all(&value.to_be_bytes()[2..])
+}
+
+pub fn mach_write_to_7(mut buf: impl Write, value: u64) -> Result<()> {
+    debug_assert!(value < 1 << 56);
+    buf.write_all(&value.to_be_bytes()[1..])
+}
+
 pub fn mach_write_to_8(mut buf: impl Write, value: u64) -> Result<()> {
     buf.write_all(&value.to_be_bytes())
 }
+
+/// Returns the size of an u32 in the compressed form of `mach_write_compressed()`.
+pub fn mach_get_compressed_size(n: u32) -> usize {
+    match n {
+        0..0x80 => 1,
+        0x80..0x4000 => 2,
+        0x4000..0x20_0000 => 3,
+        0x20_0000..0x1000_0000 => 4,
+        _ => 5,
+    }
+}
+
+/// Writes an u32 in a compressed form, where the high bits of the first byte tell the
+/// length:
+/// 0xxxxxxx                            for 0 to 0x7f
+/// 10xxxxxx xxxxxxxx                   for 0x80 to 0x3fff
+/// 110xxxxx xxxxxxxx xxxxxxxx          for 0x4000 to 0x1fffff
+/// 1110xxxx xxxxxxxx xxxxxxxx xxxxxxxx for 0x200000 to 0xfffffff
+/// 11110000 followed by 4 bytes        for 0x10000000 to 0xffffffff
+///
+/// Unlike `mlog_encode_varint()`, the values are not biased by the minimum of the length.
+pub fn mach_write_compressed(mut buf: impl Write, n: u32) -> Result<()> {
+    match mach_get_compressed_size(n) {
+        1 => buf.write_all(&[n as u8]),
+        2 => buf.write_all(&(n as u16 | 0x8000).to_be_bytes()),
+        3 => buf.write_all(&(n | 0xc0_0000).to_be_bytes()[1..]),
+        4 => buf.write_all(&(n | 0xe000_0000).to_be_bytes()),
+        _ => {
+            buf.write_u8(0xf0)?;
+            buf.write_all(&n.to_be_bytes())
+        }
+    }
+}
+
+/// Reads an u32 written by `mach_write_compressed()`. Also reads the extended forms of
+/// the values close to u32::MAX:
+/// 111110xx xxxxxxxx                   for 0xfffffc00 to 0xffffffff
+/// 1111110x xxxxxxxx xxxxxxxx          for 0xfffe0000 to 0xffffffff
+/// 11111110 followed by 3 bytes        for 0xff000000 to 0xffffffff
+///
+/// See mach_read_next_compressed().
+pub fn mach_read_compressed(mut buf: impl Read) -> Result<u32> {
+    let b0 = buf.read_u8()?;
+
+    let (len, mask, extended) = match b0 {
+        0..0x80 => return Ok(b0 as u32),
+        0x80..0xc0 => (2, 0x3fff, 0),
+        0xc0..0xe0 => (3, 0x1f_ffff, 0),
+        0xe0..0xf0 => (4, 0xfff_ffff, 0),
+        0xf0..0xf8 => return buf.read_u32::<E>(),
+        0xf8..0xfc => (2, 0x3ff, 0xffff_fc00),
+        0xfc..0xfe => (3, 0x1_ffff, 0xfffe_0000),
+        _ => (4, 0xff_ffff, 0xff00_0000),
+    };
+
+    let mut bytes = [0u8; 4];
+    bytes[4 - len] = b0;
+    buf.read_exact(&mut bytes[5 - len..])?;
+
+    Ok(u32::from_be_bytes(bytes) & mask | extended)
+}
+
+/// Writes an u64 in a compressed form: values that fit in 32 bits as in
+/// `mach_write_compressed()`, the others as 0xff followed by the compressed high and low
+/// 32 bits.
+pub fn mach_u64_write_much_compressed(mut buf: impl Write, n: u64) -> Result<()> {
+    if n >> 32 == 0 {
+        return mach_write_compressed(buf, n as u32);
+    }
+
+    buf.write_u8(0xff)?;
+    mach_write_compressed(&mut buf, (n >> 32) as u32)?;
+    mach_write_compressed(&mut buf, n as u32)
+}
+
+/// Reads an u64 written by `mach_u64_write_much_compressed()`.
+pub fn mach_u64_read_much_compressed(mut buf: impl Read) -> Result<u64> {
+    let b0 = buf.read_u8()?;
+    if b0 != 0xff {
+        return mach_read_compressed([b0].chain(buf)).map(u64::from);
+    }
+
+    let high = mach_read_compressed(&mut buf)? as u64;
+    let low = mach_read_compressed(&mut buf)? as u64;
+
+    Ok(high << 32 | low)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mach_round_trip() {
+        let mut buf = [0u8; 8];
+
+        mach_write_to_1(&mut buf[..], 0xab).unwrap();
+        assert_eq!(buf[..1], [0xab]);
+        assert_eq!(mach_read_from_1(&buf), 0xab);
+
+        mach_write_to_2(&mut buf[..], 0x0102).unwrap();
+        assert_eq!(buf[..2], [0x01, 0x02]);
+        assert_eq!(mach_read_from_2(&buf), 0x0102);
+
+        mach_write_to_3(&mut buf[..], 0x010203).unwrap();
+        assert_eq!(buf[..3], [0x01, 0x02, 0x03]);
+        assert_eq!(mach_read_from_3(&buf), 0x010203);
+
+        mach_write_to_4(&mut buf[..], 0x01020304).unwrap();
+        assert_eq!(buf[..4], [0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(mach_read_from_4(&buf), 0x01020304);
+
+        mach_write_to_6(&mut buf[..], 0x010203040506).unwrap();
+        assert_eq!(buf[..6], [0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        assert_eq!(mach_read_from_6(&buf), 0x010203040506);
+
+        mach_write_to_7(&mut buf[..], 0x01020304050607).unwrap();
+        assert_eq!(buf[..7], [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07]);
+        assert_eq!(mach_read_from_7(&buf), 0x01020304050607);
+
+        mach_write_to_8(&mut buf[..], 0x0102030405060708).unwrap();
+        assert_eq!(buf, [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        assert_eq!(mach_read_from_8(&buf), 0x0102030405060708);
+    }
+
+    #[test]
+    fn test_compressed() {
+        for (num, first, len) in [
+            (0x00, 0x00, 1),
+            (0x7f, 0x7f, 1),
+            (0x80, 0x80, 2),
+            (0x3fff, 0xbf, 2),
+            (0x4000, 0xc0, 3),
+            (0x1f_ffff, 0xdf, 3),
+            (0x20_0000, 0xe0, 4),
+            (0xfff_ffff, 0xef, 4),
+            (0x1000_0000, 0xf0, 5),
+            (u32::MAX, 0xf0, 5),
+        ] {
+            let mut buf = Vec::<u8>::new();
+            mach_write_compressed(&mut buf, num).unwrap();
+            assert_eq!(buf.len(), len, "num: {num:#x}");
+            assert_eq!(buf.len(), mach_get_compressed_size(num));
+            assert_eq!(buf[0], first, "num: {num:#x}");
+            assert_eq!(mach_read_compressed(buf.as_slice()).unwrap(), num);
+        }
+
+        assert!(mach_read_compressed([0xc0u8, 0].as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_read_compressed_extended() {
+        for (buf, num) in [
+            (&[0xf8u8, 0x00][..], 0xffff_fc00),
+            (&[0xf9, 0x23], 0xffff_fd23),
+            (&[0xfb, 0xff], 0xffff_ffff),
+            (&[0xfc, 0x00, 0x00], 0xfffe_0000),
+            (&[0xfd, 0x12, 0x34], 0xffff_1234),
+            (&[0xfe, 0x12, 0x34, 0x56], 0xff12_3456),
+            (&[0xfe, 0xff, 0xff, 0xff], 0xffff_ffff),
+        ] {
+            let mut r = buf;
+            assert_eq!(mach_read_compressed(&mut r).unwrap(), num, "buf: {buf:x?}");
+            assert!(r.is_empty(), "buf: {buf:x?}");
+        }
+
+        assert!(mach_read_compressed([0xf8u8].as_slice()).is_err());
+        assert!(mach_read_compressed([0xfcu8, 0].as_slice()).is_err());
+        assert!(mach_read_compressed([0xfeu8, 0, 0].as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_much_compressed() {
+        for (num, len) in [
+            (0x7f, 1),
+            (u32::MAX as u64, 5),
+            (1 << 32, 3),
+            (0x1234_5678_9abc, 1 + 2 + 5),
+            (u64::MAX, 11),
+        ] {
+            let mut buf = Vec::<u8>::new();
+            mach_u64_write_much_compressed(&mut buf, num).unwrap();
+            assert_eq!(buf.len(), len, "num: {num:#x}");
+            assert_eq!(
+                mach_u64_read_much_compressed(buf.as_slice()).unwrap(),
+                num,
+                "buf: {buf:#x?}"
+            );
+        }
+
+        // the reader consumes exactly the encoded bytes.
+        let mut buf = Vec::<u8>::new();
+        mach_u64_write_much_compressed(&mut buf, 1 << 40).unwrap();
+        mach_u64_write_much_compressed(&mut buf, 0x81).unwrap();
+        let mut rd = buf.as_slice();
+        assert_eq!(mach_u64_read_much_compressed(&mut rd).unwrap(), 1 << 40);
+        assert_eq!(mach_u64_read_much_compressed(&mut rd).unwrap(), 0x81);
+        assert!(rd.is_empty());
+    }
+
+    #[test]
+    fn test_mach_write_short_buffer() {
+        let mut buf = [0u8; 2];
+        assert!(mach_write_to_3(&mut buf[..], 1).is_err());
+    }
+}
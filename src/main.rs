@@ -1,30 +1,37 @@
-use std::{
-    io::{Seek, Write},
-    path::PathBuf,
-};
+use std::{io::Write, path::PathBuf};
 
+use anyhow::Context;
 use clap::Parser;
 use mdbutil::{
-    Lsn,
-    config::Config,
+    Lsn, audit,
+    buf0buf::PageChecksum,
+    config::{Config, TablespaceConfig},
+    describe,
     fil0fil::{
-        FIL_PAGE_TYPE_ALLOCATED, FIL_PAGE_TYPE_FSP_HDR, FIL_PAGE_TYPE_SYS, FIL_PAGE_TYPE_TRX_SYS,
+        FIL_PAGE_INDEX, FIL_PAGE_INODE, FIL_PAGE_RTREE, FIL_PAGE_TYPE_ALLOCATED,
+        FIL_PAGE_TYPE_FSP_HDR, FIL_PAGE_TYPE_SYS, FIL_PAGE_TYPE_TRX_SYS, FIL_PAGE_TYPE_XDES,
         FIL_PAGE_UNDO_LOG, tablespace_flags_to_string,
     },
-    fsp0fsp::fsp_header_t,
-    fsp0types::FSP_TRX_SYS_PAGE_NO,
-    log,
-    log::{CHECKPOINT_1, CHECKPOINT_2, Redo, RedoHeader},
-    mtr::Mtr,
+    fsp0fsp::{fseg_inode_page_entries, fsp_header_t, xdes_page_entries},
+    fsp0types::{FSP_EXTENT_SIZE, FSP_TRX_SYS_PAGE_NO},
+    keyring, log,
+    log::Redo,
+    mtr::{ExportedRecord, Mtr},
     mtr0types::MtrOperation,
     page_buf::{PageBuf, make_undo_log_page},
+    page0page::page_header_t,
     ring,
+    row0import::row_import_cfg_t,
     tablespace::{MmapTablespaceReader, MmapTablespaceWriter, TablespaceReader, TablespaceWriter},
     trx0rseg::trx_rseg_t,
-    trx0sys::{trx_sys_rseg_t, trx_sys_t},
-    trx0undo::trx_undo_page_t,
+    trx0sys::{self, trx_sys_rseg_t, trx_sys_t},
+    trx0undo::{self, trx_undo_page_t},
+    univ,
 };
 
+/// Maximum number of undo log headers printed per rollback segment history list.
+const HISTORY_DUMP_LIMIT: usize = 10000;
+
 #[derive(Parser)]
 enum Cli {
     ReadRedo(ReadRedoCommand),
@@ -32,12 +39,63 @@ enum Cli {
     ReadTablespace(ReadTablespaceCommand),
     ReadPage(ReadPageCommand),
     CleanUndo(CleanUndoCommand),
+    Audit(AuditCommand),
+    Describe(DescribeCommand),
+    ScanUndo(ScanUndoCommand),
+    ReadCfg(ReadCfgCommand),
+    Innochecksum(InnochecksumCommand),
+    ExplainPage(ExplainPageCommand),
+    RepairCheckpoint(RepairCheckpointCommand),
+    VerifyRedo(VerifyRedoCommand),
 }
 
 #[derive(clap::Args)]
 struct ReadRedoCommand {
     #[clap(flatten)]
     config: Config,
+
+    #[clap(
+        long = "strict",
+        help = "Fail if the decoded records of a MTR chain do not cover exactly its payload"
+    )]
+    strict: bool,
+
+    #[clap(
+        long = "payload",
+        help = "Print the bytes of each record: the offset and data of page writes, the raw \
+                payload of the other records"
+    )]
+    payload: bool,
+
+    #[clap(
+        long = "format",
+        help = "Output format: text, or the header, checkpoint and chains as a document",
+        default_value = "text"
+    )]
+    format: OutputFormat,
+
+    #[clap(
+        long = "warn-fill-pct",
+        help = "Warn if the log written since the checkpoint fills more than this percentage of \
+                the ring",
+        default_value = "75",
+        value_parser = clap::value_parser!(u8).range(0..=100)
+    )]
+    warn_fill_pct: u8,
+
+    #[clap(
+        long = "export-records",
+        help = "Write the decoded records to this file as length-prefixed frames of \
+                (space_id, page_no, op, payload)"
+    )]
+    export_records: Option<PathBuf>,
+
+    #[clap(
+        long = "keyring",
+        help = "Decrypt the records of an encrypted log with the keys of this \
+                file_key_management keyring file"
+    )]
+    keyring: Option<PathBuf>,
 }
 
 #[derive(clap::Args)]
@@ -53,10 +111,20 @@ struct WriteRedoCommand {
         help = "Redo log sequence number (LSN). Usually is MariaDB sequence number - 16."
     )]
     lsn: Lsn,
+
+    #[clap(
+        long = "first-lsn",
+        help = "LSN of the start of the record area, must not be below 12288",
+        default_value_t = log::FIRST_LSN
+    )]
+    first_lsn: Lsn,
 }
 
 #[derive(clap::Args)]
 struct ReadTablespaceCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
     #[clap(
         long = "file-path",
         help = "Path to the tablespace file (ibdata1, undoXXX, *.ibd)"
@@ -64,34 +132,78 @@ struct ReadTablespaceCommand {
     pub file_path: PathBuf,
 
     #[clap(
-        long = "page-size",
-        help = "Page size in bytes (default: 16384)",
-        default_value = "16384"
+        long = "verify-undo-slots",
+        help = "Report rollback segment undo slots not pointing to undo log pages",
+        default_value_t = false
+    )]
+    pub verify_undo_slots: bool,
+
+    #[clap(
+        long = "force-flags",
+        help = "Tablespace flags in hex to use instead of page 0 flags (skips page 0 validation)",
+        value_parser = parse_hex_u32
+    )]
+    pub force_flags: Option<u32>,
+
+    #[clap(
+        long = "sparse-pages",
+        help = "Report all-zero pages that their extent descriptor marks as used (likely \
+                punched holes)",
+        default_value_t = false
     )]
-    pub page_size: usize,
+    pub sparse_pages: bool,
 
     #[clap(
-        long = "undo-log-dir",
-        help = "Path to the undo logs directory (Undo Log)"
+        long = "dump-history",
+        help = "Print the undo log headers on the rollback segment history lists (committed \
+                transactions not purged yet)",
+        default_value_t = false
     )]
-    pub undo_log_dir: Option<PathBuf>,
+    pub dump_history: bool,
+
+    #[clap(
+        long = "histogram",
+        help = "Print the number of pages per page type",
+        default_value_t = false
+    )]
+    pub histogram: bool,
+
+    #[clap(
+        long = "check",
+        help = "Verify the checksums of all pages and report the corrupted ones",
+        default_value_t = false
+    )]
+    pub check: bool,
+
+    #[clap(
+        long = "doublewrite",
+        help = "List the pages which can be recovered from the doublewrite buffer (system \
+                tablespace only)",
+        default_value_t = false
+    )]
+    pub doublewrite: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    Text,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "json")]
+    Json,
 }
 
 #[derive(clap::Args)]
 struct ReadPageCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
     #[clap(
         long = "file-path",
         help = "Path to the tablespace file (ibdata1, undoXXX, *.ibd)"
     )]
     pub file_path: PathBuf,
 
-    #[clap(
-        long = "page-size",
-        help = "Page size in bytes (default: 16384)",
-        default_value = "16384"
-    )]
-    pub page_size: usize,
-
     #[clap(
         long = "page",
         help = "Page number to read (0-based)",
@@ -108,25 +220,48 @@ struct ReadPageCommand {
 
     #[clap(long = "raw", help = "Dump raw page data", default_value_t = false)]
     pub raw: bool,
+
+    #[clap(
+        long = "format",
+        help = "Output format of the decoded page",
+        default_value = "text"
+    )]
+    pub format: OutputFormat,
+
+    #[clap(
+        long = "allow-uninitialized",
+        help = "Allow reading pages of a tablespace with all-zero page 0",
+        default_value_t = false
+    )]
+    pub allow_uninitialized: bool,
+
+    #[clap(
+        long = "force-flags",
+        help = "Tablespace flags in hex to use instead of page 0 flags (skips page 0 validation)",
+        value_parser = parse_hex_u32
+    )]
+    pub force_flags: Option<u32>,
+
+    #[clap(
+        long = "follow-next",
+        help = "Print a summary of up to this many pages following FIL_PAGE_NEXT of the page"
+    )]
+    pub follow_next: Option<usize>,
 }
 
 /// Command to cleanup an undo log file by rewriting all free undo log pages with zeroes to
 /// ensure it has no user's data.
 #[derive(clap::Args)]
 struct CleanUndoCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
     #[clap(
         long = "file-path",
         help = "Path to the tablespace file (ibdata1, undoXXX, *.ibd)"
     )]
     pub file_path: PathBuf,
 
-    #[clap(
-        long = "page-size",
-        help = "Page size in bytes (default: 16384)",
-        default_value = "16384"
-    )]
-    pub page_size: usize,
-
     #[clap(
         long = "dry-run",
         help = "Do not modify the file",
@@ -135,6 +270,127 @@ struct CleanUndoCommand {
     pub dry_run: bool,
 }
 
+/// Command to report which components of an installation (redo log, tablespaces) are
+/// encrypted and whether they can be read by this tool.
+#[derive(clap::Args)]
+struct AuditCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+}
+
+#[derive(clap::Args)]
+struct ScanUndoCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
+    #[clap(
+        long = "file-path",
+        visible_alias = "file",
+        help = "Path to the undo tablespace file (undoXXX)"
+    )]
+    pub file_path: PathBuf,
+
+    #[clap(
+        long = "verify-page-list",
+        help = "Verify the prev/next linkage of the pages of each undo segment",
+        default_value_t = false
+    )]
+    pub verify_page_list: bool,
+}
+
+/// Command to print the header of a .cfg metadata file written by FLUSH TABLES ... FOR EXPORT.
+#[derive(clap::Args)]
+struct ReadCfgCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
+    #[clap(long = "file-path", help = "Path to the .cfg file")]
+    pub file_path: PathBuf,
+}
+
+#[derive(clap::Args)]
+struct InnochecksumCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
+    #[clap(
+        long = "file-path",
+        help = "Path to the tablespace file (ibdata1, undoXXX, *.ibd)"
+    )]
+    pub file_path: PathBuf,
+
+    #[clap(
+        long = "force-flags",
+        help = "Tablespace flags in hex to use instead of page 0 flags (skips page 0 validation)",
+        value_parser = parse_hex_u32
+    )]
+    pub force_flags: Option<u32>,
+}
+
+#[derive(clap::Args)]
+struct ExplainPageCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
+    #[clap(
+        long = "tablespace",
+        help = "Path to the tablespace file (ibdata1, undoXXX, *.ibd)"
+    )]
+    pub tablespace: PathBuf,
+
+    #[clap(long = "redo", help = "Path to the log file (Redo Log)")]
+    pub redo: PathBuf,
+
+    #[clap(long = "page", help = "Page number to explain (0-based)")]
+    pub page: u32,
+
+    #[clap(
+        long = "force-flags",
+        help = "Tablespace flags in hex to use instead of page 0 flags (skips page 0 validation)",
+        value_parser = parse_hex_u32
+    )]
+    pub force_flags: Option<u32>,
+}
+
+/// Command to point the redo log header checkpoint at the latest FILE_CHECKPOINT record
+/// found in the log body.
+#[derive(clap::Args)]
+struct RepairCheckpointCommand {
+    #[clap(flatten)]
+    config: Config,
+
+    #[clap(
+        long = "force",
+        help = "Rewrite the checkpoint blocks (without it, only report the mismatch)",
+        default_value_t = false
+    )]
+    pub force: bool,
+}
+
+/// Parses every MTR chain of the redo log and prints a one line summary. Exits with 1 if
+/// a chain has an invalid checksum or a malformed record.
+#[derive(clap::Args)]
+struct VerifyRedoCommand {
+    #[clap(flatten)]
+    config: Config,
+
+    #[clap(
+        long = "format",
+        help = "Output format of the summary",
+        default_value = "text"
+    )]
+    pub format: OutputFormat,
+}
+
+#[derive(clap::Args)]
+struct DescribeCommand {
+    #[clap(flatten)]
+    config: TablespaceConfig,
+
+    #[clap(long = "struct", help = "Structure to print the field offsets of")]
+    structure: describe::Struct,
+}
+
 fn main() {
     let cli = Cli::parse();
     match cli {
@@ -143,16 +399,89 @@ fn main() {
         Cli::ReadTablespace(cmd) => cmd.run().expect("Failed to read tablespace"),
         Cli::ReadPage(cmd) => cmd.run().expect("Failed to read page"),
         Cli::CleanUndo(cmd) => cmd.run().expect("Failed to clean undo log"),
+        Cli::Audit(cmd) => cmd.run().expect("Failed to audit data directory"),
+        Cli::Describe(cmd) => cmd.run(),
+        Cli::ScanUndo(cmd) => cmd.run().expect("Failed to scan undo tablespace"),
+        Cli::ReadCfg(cmd) => cmd.run().expect("Failed to read .cfg file"),
+        Cli::Innochecksum(cmd) => cmd.run().expect("Failed to verify page checksums"),
+        Cli::ExplainPage(cmd) => cmd.run().expect("Failed to explain page"),
+        Cli::RepairCheckpoint(cmd) => cmd.run().expect("Failed to repair checkpoint"),
+        Cli::VerifyRedo(cmd) => cmd.run().expect("Failed to verify redo log"),
     };
 }
 
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Prints what a record changes: the page range of a WRITE, MEMSET or MEMMOVE, or the
+/// raw payload of any other record.
+fn print_record_payload(mtr: &Mtr) {
+    match (mtr.offset, &mtr.data, mtr.memmove_src) {
+        (Some(offset), Some(data), _) => {
+            println!("     offset={offset} data={}", to_hex(data));
+        }
+        (Some(offset), None, Some(src)) => {
+            println!("     offset={offset} src={src:+}");
+        }
+        _ if !mtr.payload.is_empty() => {
+            println!("     payload={}", to_hex(&mtr.payload));
+        }
+        _ => {}
+    }
+}
+
+fn parse_hex_u32(s: &str) -> Result<u32, std::num::ParseIntError> {
+    u32::from_str_radix(s.trim_start_matches("0x"), 16)
+}
+
+fn open_reader(
+    mmap_reader: &MmapTablespaceReader,
+    force_flags: Option<u32>,
+) -> anyhow::Result<TablespaceReader<'_>> {
+    let Some(flags) = force_flags else {
+        return mmap_reader.reader();
+    };
+
+    eprintln!(
+        "WARNING: page 0 validation is SKIPPED, using forced tablespace flags {flags:#x} ({})",
+        tablespace_flags_to_string(flags)
+    );
+
+    mmap_reader.reader_with_flags_override(flags)
+}
+
 impl ReadRedoCommand {
     fn run(self) {
         let log_file_path = self
             .config
             .get_log_file_path()
             .expect("Redo log file path not specified");
-        let log = log::Redo::open(&log_file_path).expect("Failed to open redo log");
+        let log = match &self.keyring {
+            Some(keyring) => {
+                let keyring = keyring::FileKeyring::open(keyring).expect("Failed to open keyring");
+                log::Redo::open_with_keyring(&log_file_path, &keyring)
+            }
+            None => log::Redo::open(&log_file_path),
+        }
+        .expect("Failed to open redo log");
+
+        #[cfg(feature = "json")]
+        if self.format == OutputFormat::Json {
+            let dump = log.dump().expect("Failed to parse redo log");
+            println!("{}", dump.to_json().expect("Failed to serialize redo log"));
+            return;
+        }
+
+        #[cfg(feature = "yaml")]
+        if self.format == OutputFormat::Yaml {
+            let dump = log.dump().expect("Failed to parse redo log");
+            print!(
+                "{}",
+                serde_yaml::to_string(&dump).expect("Failed to serialize redo log")
+            );
+            return;
+        }
 
         println!("Header block: {}", log.header().first_lsn);
         println!("Size: {}, Capacity: {}", log.size(), log.capacity());
@@ -160,26 +489,39 @@ impl ReadRedoCommand {
         println!("{:#?}", log.header());
         println!("{:#?}", log.checkpoint());
 
+        if !log.is_latest() {
+            eprintln!(
+                "WARNING: the redo log is in the {:#x} format, its records are not parsed.",
+                log.header().version
+            );
+            return;
+        }
+
+        let mut export = self.export_records.as_ref().map(|path| {
+            std::io::BufWriter::new(
+                std::fs::File::create(path).expect("Failed to create records export file"),
+            )
+        });
+
         let mut file_checkpoint_chain = None;
         let mut file_checkpoint_lsn = None;
         let mut reader = log.reader();
         let mut chains = 0usize;
-        loop {
-            let chain = match reader.parse_next() {
+        for chain in reader.chains() {
+            let chain = match chain {
                 Ok(chain) => chain,
                 Err(err) => {
-                    // test for EOM.
-                    if let Some(err) = err.downcast_ref::<std::io::Error>()
-                        && err.kind() == std::io::ErrorKind::NotFound
-                    {
-                        break;
-                    }
-
                     eprintln!("ERROR: {err}: {:?}", err.source());
                     break;
                 }
             };
 
+            if self.strict {
+                chain
+                    .validate_lengths()
+                    .expect("MTR chain records do not match its length");
+            }
+
             chains += 1;
             println!(
                 "{}: MTR Chain count={}, len={}, lsn={}",
@@ -198,17 +540,29 @@ impl ReadRedoCommand {
                     file_checkpoint_lsn = mtr.file_checkpoint_lsn;
                 }
 
+                if let Some(export) = export.as_mut() {
+                    ExportedRecord::from(mtr)
+                        .write_to(export)
+                        .expect("Failed to export record");
+                }
+
                 i += 1;
                 println!(
                     "  {i}: [{start}..{end}) {mtr}",
-                    start = reader.reader().pos_to_offset(mtr.lsn as usize),
-                    end = reader
-                        .reader()
-                        .pos_to_offset(mtr.lsn as usize + mtr.len as usize),
+                    start = log.lsn_to_offset(mtr.lsn),
+                    end = log.lsn_to_offset(mtr.lsn + mtr.len as Lsn),
                 );
+
+                if self.payload {
+                    print_record_payload(mtr);
+                }
             }
         }
 
+        if let Some(mut export) = export {
+            export.flush().expect("Failed to flush records export file");
+        }
+
         println!("Checkpoint LSN/1: {:?}", log.checkpoint().checkpoints[0]);
         println!("Checkpoint LSN/2: {:?}", log.checkpoint().checkpoints[1]);
 
@@ -226,6 +580,16 @@ impl ReadRedoCommand {
         if log.checkpoint().checkpoint_lsn != Some(log.checkpoint().end_lsn) {
             eprintln!("WARNING: checkpoint LSN is not at the end of the log.");
         }
+
+        let fill = log.fill_stats_at(reader.reader().pos());
+        println!(
+            "Checkpoint age: {} bytes, {:.1}% of capacity",
+            fill.used,
+            fill.fill_pct()
+        );
+        if let Some(warning) = fill.fill_warning(self.warn_fill_pct) {
+            eprintln!("WARNING: {warning}.");
+        }
     }
 }
 
@@ -233,43 +597,24 @@ impl WriteRedoCommand {
     fn run(&self) -> anyhow::Result<()> {
         let path = self.config.get_log_file_path()?;
 
-        let first_lsn = log::FIRST_LSN;
+        let first_lsn = self.first_lsn;
         let size = self.size;
-        let capacity = size - first_lsn;
-
-        let mut log = Redo::writer(path.as_path(), first_lsn as usize, size)
-            .map_err(std::io::Error::other)?;
-        let mut writer = log.writer();
-
-        let header = RedoHeader::build_unencrypted_header_10_8(first_lsn, "test_creator")?;
-        writer.seek(std::io::SeekFrom::Start(0))?;
-        writer.write_all(&header)?;
-
-        let checkpoint = RedoHeader::build_unencrypted_header_10_8_checkpoint(self.lsn, self.lsn)?;
-        writer.seek(std::io::SeekFrom::Start(CHECKPOINT_1 as u64))?;
-        writer.write_all(&checkpoint)?;
+        Redo::validate_first_lsn(first_lsn)?;
+        Redo::validate_checkpoint_lsn(first_lsn, size, self.lsn)?;
 
-        writer.seek(std::io::SeekFrom::Start(CHECKPOINT_2 as u64))?;
-        writer.write_all(&checkpoint)?;
-
-        let mut file_checkpoint = vec![];
-        Mtr::build_file_checkpoint(&mut file_checkpoint, first_lsn, capacity, self.lsn).unwrap();
-        file_checkpoint.push(0x0); // end marker
-
-        writer.seek(std::io::SeekFrom::Start(self.lsn))?;
-        writer.write_all(&file_checkpoint)?;
-
-        log.mmap().flush(0..size as usize)?;
-
-        drop(log);
+        let mut log = Redo::create(&path, size, first_lsn)?;
+        log.write_checkpoint(self.lsn)?;
+        log.flush()?;
 
         println!(
-            "Writing file checkpoint: {file_checkpoint:x?} at pos: {target_offset} \
-             ({target_offset:#x})",
+            "Writing file checkpoint at lsn: {lsn} pos: {target_offset} ({target_offset:#x})",
+            lsn = self.lsn,
             target_offset =
-                ring::pos_to_offset(first_lsn as usize, capacity as usize, self.lsn as usize)
+                ring::pos_to_offset(first_lsn as usize, log.capacity() as usize, self.lsn)
         );
 
+        drop(log);
+
         let target_log = Redo::open(&path).expect("Failed to open target redo log");
 
         println!("Target header block: {}", target_log.header().first_lsn);
@@ -284,17 +629,10 @@ impl WriteRedoCommand {
 
         let mut file_checkpoint_lsn = None;
         let mut reader = target_log.reader();
-        loop {
-            let chain = match reader.parse_next() {
+        for chain in reader.chains() {
+            let chain = match chain {
                 Ok(chain) => chain,
                 Err(err) => {
-                    // test for EOM.
-                    if let Some(err) = err.downcast_ref::<std::io::Error>()
-                        && err.kind() == std::io::ErrorKind::NotFound
-                    {
-                        break;
-                    }
-
                     eprintln!("\nERROR: {err:?}");
                     break;
                 }
@@ -309,10 +647,8 @@ impl WriteRedoCommand {
 
                 println!(
                     "  [{start}..{end}) {mtr}",
-                    start = reader.reader().pos_to_offset(mtr.lsn as usize),
-                    end = reader
-                        .reader()
-                        .pos_to_offset(mtr.lsn as usize + mtr.len as usize),
+                    start = target_log.lsn_to_offset(mtr.lsn),
+                    end = target_log.lsn_to_offset(mtr.lsn + mtr.len as Lsn),
                 );
             }
         }
@@ -337,13 +673,13 @@ impl WriteRedoCommand {
 impl ReadTablespaceCommand {
     fn run(&self) -> anyhow::Result<()> {
         let file_path = &self.file_path;
-        let page_size = self.page_size;
+        let page_size = self.config.page_size;
 
         let mmap_reader: MmapTablespaceReader =
             mdbutil::tablespace::MmapTablespaceReader::open(file_path, page_size)?;
         let num_pages = mmap_reader.mmap().len() / page_size;
 
-        let reader: TablespaceReader<'_> = mmap_reader.reader()?;
+        let reader: TablespaceReader<'_> = open_reader(&mmap_reader, self.force_flags)?;
 
         println!(
             "Opened tablespace file: {} with size: {} bytes, page size: {} bytes, num pages: {}, \
@@ -363,6 +699,49 @@ impl ReadTablespaceCommand {
         if page.page_type == FIL_PAGE_TYPE_FSP_HDR {
             let fsp_header = fsp_header_t::from_page(&page);
             println!("FSP header: {fsp_header:#?}");
+            let free_pages = fsp_header.free_extents() as usize
+                * FSP_EXTENT_SIZE(univ::page_size_shift(page_size as u32)) as usize;
+            println!(
+                "{} free extents, approximately {} bytes of free space",
+                fsp_header.free_extents(),
+                free_pages * page_size
+            );
+        }
+
+        if self.sparse_pages {
+            let sparse = reader.sparse_pages()?;
+            for page_no in &sparse {
+                println!("page {page_no}: all zero, but used according to its extent descriptor");
+            }
+            println!("{} likely sparse pages", sparse.len());
+        }
+
+        if self.check {
+            let corrupted = reader.verify_all();
+            for (page_no, err) in &corrupted {
+                println!("page {page_no}: {err}");
+            }
+            println!("{} corrupted pages out of {num_pages}", corrupted.len());
+        }
+
+        if self.histogram {
+            for (page_type, count) in reader.page_type_histogram()? {
+                println!(
+                    "{:<24} {:>10} {:>6.2}%",
+                    format!("{page_type:?}"),
+                    count,
+                    count as f64 * 100.0 / num_pages.max(1) as f64
+                );
+            }
+            println!("{} all-zero pages", reader.zero_page_count());
+        }
+
+        if self.doublewrite {
+            anyhow::ensure!(
+                reader.space_id() == 0,
+                "--doublewrite is only valid for the system tablespace"
+            );
+            self.list_doublewrite(&reader)?;
         }
 
         if page.space_id == 0 {
@@ -372,6 +751,54 @@ impl ReadTablespaceCommand {
         Ok(())
     }
 
+    /// Lists the valid page copies in the doublewrite buffer. A copy of a system
+    /// tablespace page is recoverable if the page itself is corrupted; the copies of other
+    /// tablespaces can not be verified here and are listed as unverified.
+    pub fn list_doublewrite(&self, reader: &TablespaceReader<'_>) -> anyhow::Result<()> {
+        let trx_sys_header = trx_sys_t::from_page(&reader.page(FSP_TRX_SYS_PAGE_NO)?);
+        let dw = &trx_sys_header.doublewrite;
+        let mut recoverable = 0;
+        let mut unverified = 0;
+
+        let copies = trx0sys::read_doublewrite(reader, dw)?;
+        let dw_pages = dw
+            .blocks(reader.page_size())
+            .into_iter()
+            .flatten()
+            .flatten();
+
+        for (dw_page_no, copy) in dw_pages.zip(copies) {
+            let (space_id, page_no) = match dw.check_copy(reader, &copy) {
+                Ok(Some(id)) => id,
+                Ok(None) => continue,
+                Err(err) => {
+                    println!("doublewrite page {dw_page_no}: {err}");
+                    continue;
+                }
+            };
+
+            if space_id != reader.space_id() {
+                println!(
+                    "doublewrite page {dw_page_no}: unverified copy of space {space_id} page \
+                     {page_no}"
+                );
+                unverified += 1;
+            } else if let Err(err) = reader.page(page_no).and_then(|page| page.corrupted(None)) {
+                println!(
+                    "doublewrite page {dw_page_no}: recoverable copy of page {page_no} ({err})"
+                );
+                recoverable += 1;
+            }
+        }
+
+        println!(
+            "{recoverable} recoverable pages, {unverified} unverified pages of other \
+             tablespaces in the doublewrite buffer"
+        );
+
+        Ok(())
+    }
+
     pub fn read_trx_sys_page(&self, reader: &TablespaceReader<'_>) -> anyhow::Result<()> {
         assert_eq!(reader.space_id(), 0);
 
@@ -383,6 +810,13 @@ impl ReadTablespaceCommand {
         let trx_sys_header = trx_sys_t::from_page(&page);
         println!("{trx_sys_header:#?}");
 
+        match trx_sys_header.doublewrite.blocks(reader.page_size()) {
+            Some([block1, block2]) => println!(
+                "doublewrite buffer: initialized, block1 pages {block1:?}, block2 pages {block2:?}"
+            ),
+            None => println!("doublewrite buffer: not initialized"),
+        }
+
         let undo_log_dir = self.undo_log_dir()?;
 
         for trx_sys_rseg_t { space_id, page_no } in trx_sys_header.rsegs {
@@ -397,7 +831,7 @@ impl ReadTablespaceCommand {
             let new_path = undo_log_dir.join(format!("undo{:03}", space_id));
 
             let mmap_reader: MmapTablespaceReader =
-                mdbutil::tablespace::MmapTablespaceReader::open(&new_path, self.page_size)?;
+                mdbutil::tablespace::MmapTablespaceReader::open(&new_path, self.config.page_size)?;
             let reader = mmap_reader.reader()?;
 
             let page: PageBuf<'_> = reader.page(page_no)?;
@@ -419,8 +853,10 @@ impl ReadTablespaceCommand {
         let rseg = trx_rseg_t::from_page(page);
 
         if rseg.history_size == 0 && rseg.undo_slots.is_empty() && rseg.mysql_log.is_none() {
-            if rseg.max_trx_id != 0 {
-                println!("trx_rseg_t {{ max_trx_id: {} }}", rseg.max_trx_id);
+            if let Some(max_trx_id) = rseg.max_trx_id
+                && max_trx_id != 0
+            {
+                println!("trx_rseg_t {{ max_trx_id: {max_trx_id} }}");
                 return Ok(());
             }
 
@@ -429,18 +865,32 @@ impl ReadTablespaceCommand {
 
         println!("{rseg:#?}");
 
+        if self.dump_history {
+            self.dump_history(reader, page.page_no, &rseg)?;
+        }
+
+        if self.verify_undo_slots {
+            let errors = rseg.verify_undo_slots(reader);
+            for err in &errors {
+                eprintln!("ERROR: RSEG page {}: {err}", page.page_no);
+            }
+            println!(
+                "RSEG page {}: {} undo slots, {} invalid",
+                page.page_no,
+                rseg.undo_slots.len(),
+                errors.len()
+            );
+        }
+
         for (slot, page_no) in &rseg.undo_slots {
             if *page_no == 0 || *page_no == 0xFFFFFFFF {
                 continue;
             }
 
-            let page: PageBuf<'_> = match reader.page(*page_no) {
+            let page: PageBuf<'_> = match trx_rseg_t::undo_slot_page(reader, *slot, *page_no) {
                 Ok(page) => page,
                 Err(err) => {
-                    eprintln!(
-                        "ERROR: Failed to read undo log page {} referenced from slot {}: {err}",
-                        page_no, slot
-                    );
+                    eprintln!("ERROR: {err}");
                     continue;
                 }
             };
@@ -451,9 +901,44 @@ impl ReadTablespaceCommand {
         Ok(())
     }
 
+    pub fn dump_history(
+        &self,
+        reader: &TablespaceReader<'_>,
+        rseg_page_no: u32,
+        rseg: &trx_rseg_t,
+    ) -> anyhow::Result<()> {
+        let entries = rseg.history(reader, HISTORY_DUMP_LIMIT)?;
+
+        for entry in &entries {
+            println!(
+                "RSEG page {rseg_page_no}: history {}:{}: trx_id: {}, trx_no: {}, table: {}, \
+                 needs_purge: {}",
+                entry.page_no,
+                entry.offset,
+                entry.hdr.trx_id,
+                entry.hdr.trx_no,
+                entry
+                    .hdr
+                    .dict_table_id
+                    .map_or("-".to_string(), |id| id.to_string()),
+                entry.hdr.needs_purge,
+            );
+        }
+
+        if entries.len() < rseg.history.len as usize {
+            println!(
+                "RSEG page {rseg_page_no}: history list has {} entries, printed the first {}",
+                rseg.history.len,
+                entries.len()
+            );
+        }
+
+        Ok(())
+    }
+
     pub fn undo_log_dir(&self) -> anyhow::Result<PathBuf> {
-        if let Some(path) = &self.undo_log_dir {
-            return Ok(path.clone());
+        if let Ok(path) = self.config.get_undo_log_dir() {
+            return Ok(path);
         }
 
         if let Some(path) = self.file_path.parent() {
@@ -476,6 +961,19 @@ impl ReadTablespaceCommand {
         let undo_page = trx_undo_page_t::from_page(page);
         println!("{undo_page:#?}");
 
+        if trx0undo::is_undo_seg_hdr_page(page.page_no, page) {
+            let seg_hdr = trx0undo::trx_undo_seg_hdr_t::from_page(page);
+            println!("{seg_hdr:#?}");
+
+            for (offset, log_hdr) in trx0undo::undo_log_headers(page) {
+                println!("undo log header at offset {offset}: {log_hdr:#?}");
+            }
+        }
+
+        for rec in undo_page.records(page.buf()) {
+            println!("{rec}");
+        }
+
         Ok(())
     }
 }
@@ -483,13 +981,21 @@ impl ReadTablespaceCommand {
 impl ReadPageCommand {
     fn run(&self) -> anyhow::Result<()> {
         let file_path = &self.file_path;
-        let page_size = self.page_size;
+        let page_size = self.config.page_size;
 
         let mmap_reader: MmapTablespaceReader =
             mdbutil::tablespace::MmapTablespaceReader::open(file_path, page_size)?;
         let num_pages = mmap_reader.mmap().len() / page_size;
 
-        let reader: TablespaceReader<'_> = mmap_reader.reader()?;
+        let reader: TablespaceReader<'_> = if self.allow_uninitialized && self.force_flags.is_none()
+        {
+            mmap_reader.reader_allow_uninitialized()?
+        } else {
+            open_reader(&mmap_reader, self.force_flags)?
+        };
+        if reader.is_uninitialized() {
+            eprintln!("WARNING: page 0 consists of zero bytes, the tablespace is uninitialized");
+        }
         let page: PageBuf<'_> = reader.page(self.page)?;
 
         if self.hex {
@@ -524,6 +1030,18 @@ impl ReadPageCommand {
             return Ok(());
         }
 
+        #[cfg(feature = "yaml")]
+        if self.format == OutputFormat::Yaml {
+            print!("{}", serde_yaml::to_string(&page.dump())?);
+            return Ok(());
+        }
+
+        #[cfg(feature = "json")]
+        if self.format == OutputFormat::Json {
+            println!("{}", serde_json::to_string(&page.dump())?);
+            return Ok(());
+        }
+
         println!(
             "Opened tablespace file: {} with size: {} bytes, page size: {} bytes, num pages: {}, \
              flags: {}",
@@ -542,6 +1060,7 @@ impl ReadPageCommand {
             FIL_PAGE_TYPE_FSP_HDR => {
                 let fsp_header = fsp_header_t::from_page(&page);
                 println!("FSP header: {fsp_header:#?}");
+                print_xdes_entries(&page);
             }
             FIL_PAGE_TYPE_TRX_SYS => {
                 let trx_sys_header = trx_sys_t::from_page(&page);
@@ -555,8 +1074,24 @@ impl ReadPageCommand {
                 let undo_page = trx_undo_page_t::from_page(&page);
                 println!("{undo_page:#?}");
             }
-            _ => {
-                return Ok(());
+            FIL_PAGE_TYPE_XDES => print_xdes_entries(&page),
+            FIL_PAGE_INODE => {
+                for (i, inode) in fseg_inode_page_entries(&page).iter().enumerate() {
+                    if inode.is_used() {
+                        println!("inode {i}: {inode:#?}");
+                    }
+                }
+            }
+            FIL_PAGE_INDEX | FIL_PAGE_RTREE => {
+                let page_header = page_header_t::from_page(&page);
+                println!("{page_header:#?}");
+            }
+            _ => {}
+        }
+
+        if let Some(max_next) = self.follow_next {
+            for next in reader.follow_next(self.page, max_next)?.iter().skip(1) {
+                println!("{}", next.summary());
             }
         }
 
@@ -564,10 +1099,20 @@ impl ReadPageCommand {
     }
 }
 
+/// Prints the used extent descriptors of a descriptor page (FIL_PAGE_TYPE_XDES or page 0).
+fn print_xdes_entries(page: &PageBuf) {
+    for (i, entry) in xdes_page_entries(page).iter().enumerate() {
+        if entry.state != 0 {
+            let first_page = page.page_no as usize + i * entry.bitmap.len();
+            println!("extent at page {first_page}: {entry}");
+        }
+    }
+}
+
 impl CleanUndoCommand {
     fn run(&self) -> anyhow::Result<()> {
         let file_path = &self.file_path;
-        let page_size = self.page_size;
+        let page_size = self.config.page_size;
 
         let mut mmap_writer: MmapTablespaceWriter =
             MmapTablespaceWriter::open(file_path, page_size)?;
@@ -728,6 +1273,7 @@ impl CleanUndoCommand {
         print!("Cleaning undo log pages: ");
         let space_id = reader.space_id();
         let flags = reader.flags();
+        let mut cleaned = Vec::new();
         let mut writer: TablespaceWriter<'_> = mmap_writer.writer()?;
         for (page_no, _) in pages.iter().enumerate().filter(|(_page_id, p)| **p == 1) {
             let page_buf = &mut writer.page_buf(page_no as u32)?;
@@ -739,10 +1285,198 @@ impl CleanUndoCommand {
             page_test.corrupted(Some(page_lsn))?;
 
             print!("{} ", page_no);
+            cleaned.push(page_no as u32);
         }
         println!();
 
-        mmap_writer.flush_all()?;
+        for page_no in cleaned {
+            mmap_writer.mark_dirty(page_no);
+        }
+        mmap_writer.flush_dirty()?;
+
+        Ok(())
+    }
+}
+
+impl AuditCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let report = audit::audit_data_dir(&self.config.get_data_dir()?)?;
+        println!("{report}");
+
+        Ok(())
+    }
+}
+
+impl ScanUndoCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let mmap_reader = MmapTablespaceReader::open(&self.file_path, self.config.page_size)?;
+        let reader = mmap_reader.reader()?;
+        println!("{reader}");
+
+        let segments = trx0undo::scan_undo_segments(&reader);
+        for segment in &segments {
+            println!("{segment}");
+
+            if self.verify_page_list {
+                for brk in trx0undo::verify_undo_page_list(&reader, segment.page_no)? {
+                    println!("  {brk}");
+                }
+            }
+        }
+        println!("Found {} undo segments", segments.len());
+
+        Ok(())
+    }
+}
+
+impl ReadCfgCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let buf = std::fs::read(&self.file_path)
+            .with_context(|| format!("read {}", self.file_path.display()))?;
+        let cfg = row_import_cfg_t::from_buf(&buf).context("parse .cfg header")?;
+
+        println!("{cfg:#?}");
+
+        if cfg.page_size as usize != self.config.page_size {
+            eprintln!(
+                "WARNING: the tablespace was exported with page size {}, but the page size is {}",
+                cfg.page_size, self.config.page_size
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl InnochecksumCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let mmap_reader = MmapTablespaceReader::open(&self.file_path, self.config.page_size)?;
+        let reader = open_reader(&mmap_reader, self.force_flags)?;
+        let num_pages = reader.len() / reader.page_size();
+
+        let mut corrupted = 0;
+        for page_no in 0..num_pages as u32 {
+            let checksum = PageChecksum::from_page(&reader.page(page_no)?);
+            println!("{checksum}");
+
+            if !checksum.valid {
+                eprintln!("Fail: page::{page_no} invalid");
+                corrupted += 1;
+            }
+        }
+
+        println!("{num_pages} pages, {corrupted} corrupted");
+
+        if corrupted > 0 {
+            std::process::exit(1);
+        }
+
+        Ok(())
+    }
+}
+
+impl ExplainPageCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let mmap_reader = MmapTablespaceReader::open(&self.tablespace, self.config.page_size)?;
+        let reader = open_reader(&mmap_reader, self.force_flags)?;
+        let page = reader.page(self.page)?;
+
+        println!("{page}");
+        println!("{:#?}", page.decode());
+
+        let log = Redo::open(&self.redo)?;
+        let records = log.page_records(page.space_id, page.page_no, page.page_lsn)?;
+
+        println!(
+            "{} redo records to apply after page LSN {} (checkpoint LSN {:?}):",
+            records.len(),
+            page.page_lsn,
+            log.checkpoint().checkpoint_lsn
+        );
+        for mtr in &records {
+            println!("  {mtr} payload={:02x?}", mtr.payload);
+        }
+
+        Ok(())
+    }
+}
+
+impl DescribeCommand {
+    fn run(&self) {
+        println!(
+            "{:?} (page size {}):",
+            self.structure, self.config.page_size
+        );
+        println!("{:>6} {:>6} {:>5}  field", "start", "end", "size");
+        for field in describe::describe(self.structure, self.config.page_size) {
+            println!("{field}");
+        }
+    }
+}
+
+impl RepairCheckpointCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let log_file_path = self.config.get_log_file_path()?;
+        let mut log = if self.force {
+            Redo::open_mut(&log_file_path)?
+        } else {
+            Redo::open(&log_file_path)?
+        };
+
+        let header = (log.checkpoint().checkpoint_lsn, log.checkpoint().end_lsn);
+        let found = log.find_file_checkpoints();
+        println!(
+            "Header checkpoint LSN: {:?} end LSN: {}",
+            header.0, header.1
+        );
+        println!("FILE_CHECKPOINT records (checkpoint LSN, record LSN): {found:?}");
+
+        let Some(&(latest, end_lsn)) = found.last() else {
+            anyhow::bail!(
+                "no FILE_CHECKPOINT record found in {}",
+                log_file_path.display()
+            );
+        };
+
+        if header == (Some(latest), end_lsn) {
+            println!("Checkpoint is consistent with the latest FILE_CHECKPOINT record");
+            return Ok(());
+        }
+
+        if !self.force {
+            println!(
+                "Would point the checkpoint at LSN {latest} with the end LSN {end_lsn}, rerun \
+                 with --force to write it"
+            );
+            return Ok(());
+        }
+
+        log.write_checkpoint(latest, end_lsn)?;
+        println!("Checkpoint now points at LSN {latest} with the end LSN {end_lsn}");
+
+        Ok(())
+    }
+}
+
+impl VerifyRedoCommand {
+    fn run(&self) -> anyhow::Result<()> {
+        let log_file_path = self.config.get_log_file_path()?;
+        let log = Redo::open(&log_file_path)?;
+        let verification = log.verify();
+
+        match self.format {
+            OutputFormat::Text => {
+                println!("log_file={} {verification}", log_file_path.display());
+            }
+            #[cfg(feature = "yaml")]
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&verification)?),
+            #[cfg(feature = "json")]
+            OutputFormat::Json => println!("{}", serde_json::to_string(&verification)?),
+        }
+
+        if !verification.is_ok() {
+            std::process::exit(1);
+        }
 
         Ok(())
     }
@@ -1,17 +1,22 @@
 use std::{
     cmp::min,
     fmt::Display,
-    io::{Error, ErrorKind, Result, Write},
+    io::{Error, ErrorKind, Read, Result, Write},
 };
 
 use crate::{
-    Lsn,
-    mach::{mach_write_to_4, mach_write_to_8},
-    mtr0log::{mlog_decode_varint, mlog_decode_varint_length},
+    Lsn, fil0fil,
+    log0crypt::{self, LogCryptKey},
+    lsn::get_sequence_bit,
+    mach::{mach_read_from_4, mach_write_to_4, mach_write_to_8},
+    mtr0log::{
+        mlog_decode_memmove_src, mlog_decode_varint, mlog_decode_varint_length, mlog_encode_varint,
+    },
     mtr0types::{
         MtrOperation,
         mfile_type_t::FILE_CHECKPOINT,
-        mrec_type_t::{INIT_PAGE, MEMSET, RESERVED},
+        mrec_ext_t, mrec_opt,
+        mrec_type_t::{INIT_PAGE, MEMSET, RESERVED, WRITE},
     },
     ring::RingReader,
 };
@@ -22,6 +27,9 @@ use crate::{
 ///    !(((lsn - header_size) / capacity & 1))
 pub const MTR_END_MARKER: u8 = 1u8;
 
+/// Size of the IV stored after the termination marker of a chain of an encrypted log.
+pub const MTR_CRYPT_IV_SIZE: usize = 8;
+
 /// Maximum guaranteed size of a mini-transaction.
 pub const MTR_SIZE_MAX: u32 = 1u32 << 20;
 
@@ -36,18 +44,22 @@ pub const UNIV_PAGE_SIZE_MAX: u32 = 1u32 << UNIV_PAGE_SIZE_SHIFT_MAX;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MtrChain {
     pub lsn: Lsn,
-    /// total mtr length including 1st byte, termination marker and checksum.
+    /// total mtr length including 1st byte, termination marker, IV and checksum.
     pub len: u32,
     // termination marker
     pub marker: u8,
+    /// IV after the termination marker of a chain of an encrypted log.
+    pub iv: Option<[u8; MTR_CRYPT_IV_SIZE]>,
     pub checksum: u32,
     pub mtr: Vec<Mtr>,
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Mtr {
     // coordinates
     pub lsn: Lsn,
@@ -58,38 +70,76 @@ pub struct Mtr {
     pub page_no: u32,
 
     pub op: MtrOperation,
+    /// Subtype of an EXTENDED record.
+    pub ext_subtype: Option<mrec_ext_t>,
+    /// Subtype of an OPTION record. Unknown subtypes are None, as recovery ignores them.
+    pub opt_subtype: Option<mrec_opt>,
 
     // FILE_CHECKPOINT LSN, if any.
     pub file_checkpoint_lsn: Option<Lsn>,
+
+    /// Record bytes following the page identifier (or the length for same-page records).
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_hex::serialize")
+    )]
+    pub payload: Vec<u8>,
+
+    /// Absolute byte offset within the page of a WRITE, MEMSET or MEMMOVE record.
+    pub offset: Option<u32>,
+    /// Bytes written by a WRITE record, or the fill pattern of a MEMSET record.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_hex::serialize_option")
+    )]
+    pub data: Option<Vec<u8>>,
+    /// Source offset of a MEMMOVE record, relative to `offset`.
+    pub memmove_src: Option<i32>,
+
+    /// File name of a FILE_CREATE, FILE_DELETE, FILE_MODIFY or FILE_RENAME record.
+    pub file_name: Option<String>,
+    /// New file name of a FILE_RENAME record.
+    pub new_file_name: Option<String>,
 }
 
 #[allow(clippy::len_without_is_empty)]
 impl MtrChain {
     pub fn parse_next(r: &mut RingReader) -> Result<Self> {
+        Self::parse_next_with(r, None)
+    }
+
+    /// Parses the next chain like `parse_next()`. With the `key` of an encrypted log, the
+    /// chain carries an IV after the termination marker, which the checksum covers, and
+    /// the records are decrypted before they are parsed.
+    /// See recv_sys_t::parse() and log_decrypt_buf().
+    pub fn parse_next_with(r: &mut RingReader, key: Option<&LogCryptKey>) -> Result<Self> {
         peek_not_end_marker(r)?;
 
         let mtr_start = r.clone();
-        let lsn = mtr_start.pos() as Lsn;
+        let lsn = mtr_start.pos();
         let _ = Self::find_end_marker(r)?;
 
-        let termination_marker_offset = r.pos() - mtr_start.pos();
+        let termination_marker_offset = (r.pos() - mtr_start.pos()) as usize;
         // following is equivalent to r.peek_1()?.
         let termination_byte = (&mtr_start + termination_marker_offset).peek_1()?;
         let termination_lsn = lsn + termination_marker_offset as u64;
 
-        if termination_byte
-            != get_sequence_bit(r.header() as u64, r.capacity() as u64, termination_lsn)
-        {
+        if termination_byte != r.sequence_bit(termination_lsn) {
             return Err(Error::from(ErrorKind::NotFound));
         }
 
-        // |MTR|MTR|...|^TERMINATION_MARKER|CHECKSUM|.
-        let real_crc = mtr_start.crc32c(termination_marker_offset)?;
+        // |MTR|MTR|...|^TERMINATION_MARKER|IV (encrypted only)|CHECKSUM|.
+        let mut real_crc = mtr_start.crc32c(termination_marker_offset)?;
         r.advance(1); // past termination marker.
 
-        // TODO: encryption, crc iv 8
+        let mut iv = [0u8; MTR_CRYPT_IV_SIZE];
+        if key.is_some() {
+            r.read_exact(&mut iv)?;
+            real_crc = crc32c::crc32c_append(real_crc, &iv);
+        }
 
         let expected_crc = r.read_4()?; // read block crc.
+        let iv_len = if key.is_some() { iv.len() } else { 0 };
 
         if real_crc != expected_crc {
             return Err(Error::new(
@@ -99,7 +149,7 @@ impl MtrChain {
                      {expected_crc:#x}, real {real_crc:#x}",
                     pos = mtr_start.pos(),
                     pos_hex = mtr_start.pos(),
-                    len = termination_marker_offset + 1 + 4,
+                    len = termination_marker_offset + 1 + iv_len + 4,
                 ),
             ));
         }
@@ -117,18 +167,39 @@ impl MtrChain {
         // Parse MTR chain.
         let mut chain = MtrChain {
             lsn,
-            len: termination_marker_offset as u32 + 1 + 4,
+            len: (termination_marker_offset + 1 + iv_len + 4) as u32,
             marker: termination_byte,
+            iv: key.map(|_| iv),
             checksum: real_crc,
             mtr: Vec::new(),
         };
 
-        let mut l = mtr_start.clone();
+        // The decrypted records and the termination marker, laid out so that a reader at
+        // the chain LSN sees them at the same positions as in the log.
+        let mut decrypted = vec![];
+        let mut l = match key {
+            Some(key) => {
+                let mut buf = vec![0u8; termination_marker_offset + 1];
+                mtr_start.block(&mut buf);
+                log0crypt::log_crypt_buf(key, &iv, &mut buf[..termination_marker_offset])?;
+
+                decrypted.resize(buf.len(), 0);
+                let start = (lsn % buf.len() as u64) as usize;
+                decrypted[start..].copy_from_slice(&buf[..buf.len() - start]);
+                decrypted[..start].copy_from_slice(&buf[buf.len() - start..]);
+
+                RingReader::buf_at(&decrypted, 0, lsn)
+            }
+            None => mtr_start.clone(),
+        };
         let mut rlen: u32;
         // let mut last_offset = 0u32;
         let mut got_page_op = false;
         let mut space_id = 0u32;
         let mut page_no = 0u32;
+        // Current byte offset within the page that WRITE, MEMSET and MEMMOVE records are
+        // relative to.
+        let mut last_offset = 0u32;
 
         loop {
             // println!(
@@ -140,6 +211,11 @@ impl MtrChain {
             let recs = l.clone();
             l.advance(1);
 
+            if recs.pos() >= termination_lsn {
+                // malformed records made us skip the termination marker.
+                break;
+            }
+
             let b = recs.peek_1()?;
 
             if b & 0x70 != RESERVED as u8 {
@@ -155,12 +231,16 @@ impl MtrChain {
 
             // move past varint length.
             rlen = (b & 0xf) as u32;
+            let mut lenlen = 0u32;
             if rlen == 0 {
-                let lenlen = mlog_decode_varint_length(l.peek_1()?);
+                lenlen = mlog_decode_varint_length(l.peek_1()?) as u32;
                 let addlen = mlog_decode_varint(&mut l)?;
-                rlen = addlen + 15 - lenlen as u32;
+                rlen = addlen
+                    .checked_add(15 - lenlen)
+                    .ok_or_else(|| Error::from(ErrorKind::InvalidData))?;
             }
-            let mtr_len = 1 + rlen; // 1 byte header + payload size (rlen).
+            // 1 byte header + varint length + payload size (rlen).
+            let mtr_len = 1 + lenlen + rlen;
 
             // println!(
             //     "mtr lsn start = {start}, lsn end = {end}, len: {rlen}, b = {b:#x}, pos = 0x{pos:x}",
@@ -203,6 +283,7 @@ impl MtrChain {
                 rlen -= page_no_len as u32;
 
                 got_page_op = b & 0x80 == 0;
+                last_offset = 0;
             } else {
                 // TODO: verify the same page op precond.
                 // This record is for the same page as the previous one.
@@ -218,6 +299,11 @@ impl MtrChain {
                 //             lsn, b, l - recs + rlen, space_id, page_no));
             }
 
+            // |HEADER|LENGTH|SPACE_ID|PAGE_NO|^PAYLOAD|.
+            let payload_start = l.clone();
+            let payload_len =
+                (recs.pos() + mtr_len as u64).saturating_sub(payload_start.pos()) as usize;
+
             let mut mtr_op = 0;
             let mut file_checkpoint_lsn = None;
 
@@ -226,9 +312,22 @@ impl MtrChain {
                 mtr_op = b & 0x70;
 
                 if mtr_op == MEMSET as u8 {
+                    // only validates the offset here, decode_page_write() resolves it
+                    // against the page cursor from the payload.
                     let olen = mlog_decode_varint_length(l.peek_1()?);
                     let _offset = mlog_decode_varint(&mut l)?;
 
+                    if rlen < olen as u32 {
+                        Self::eprintln_malformed(
+                            &mtr_start,
+                            &recs,
+                            &l,
+                            b,
+                            mtr_len,
+                            termination_lsn as Lsn,
+                        );
+                        break;
+                    }
                     rlen -= olen as u32;
                 }
             } else if rlen > 0 {
@@ -239,7 +338,7 @@ impl MtrChain {
                     // l[rlen] is l + rlen == termination marker:
                     // this condition means we do not expect anything else in the chain,
                     // or file checkpoint is the last record in the chain.
-                    if space_id != 0 || page_no != 0 || l[rlen] > 1 {
+                    if space_id != 0 || page_no != 0 || (&l + rlen).peek_1()? > 1 {
                         Self::eprintln_malformed(
                             &mtr_start,
                             &recs,
@@ -299,7 +398,7 @@ impl MtrChain {
                         mtr_op
                     );
 
-                    if l.pos() >= mtr_start.pos() + chain.len() as usize {
+                    if l.pos() >= mtr_start.pos() + chain.len() as u64 {
                         eprintln!(
                             "InnoDB: We are behind the end of the MTR chain at LSN {} >= {}+{}. \
                              Stopping here.",
@@ -315,13 +414,59 @@ impl MtrChain {
                 }
             };
 
+            let mut payload = vec![0u8; payload_len];
+            payload_start.block(&mut payload);
+
+            let write = match op {
+                MtrOperation::InitPage | MtrOperation::Extended => {
+                    last_offset = fil0fil::FIL_PAGE_TYPE;
+                    None
+                }
+                MtrOperation::Write | MtrOperation::Memset | MtrOperation::Memmove => {
+                    decode_page_write(op, &payload, last_offset)
+                }
+                _ => None,
+            };
+
+            if op == MtrOperation::Memmove && !write.as_ref().is_some_and(memmove_src_in_page) {
+                // the source range is outside of the page: the record is corrupted.
+                Self::eprintln_malformed(&mtr_start, &recs, &l, b, mtr_len, termination_lsn as Lsn);
+                l.advance(rlen as usize);
+
+                continue;
+            }
+
+            if let Some(write) = &write {
+                last_offset = write.end;
+            }
+
+            let (file_name, new_file_name) = decode_file_names(op, &payload);
+            let ext_subtype = match op {
+                MtrOperation::Extended => {
+                    payload.first().and_then(|&b| mrec_ext_t::try_from(b).ok())
+                }
+                _ => None,
+            };
+            let opt_subtype = match op {
+                MtrOperation::Option => payload.first().and_then(|&b| mrec_opt::try_from(b).ok()),
+                _ => None,
+            };
+
             chain.mtr.push(Mtr {
-                lsn: recs.pos() as Lsn,
+                lsn: recs.pos(),
                 len: mtr_len,
                 space_id,
                 page_no,
                 op,
+                ext_subtype,
+                opt_subtype,
                 file_checkpoint_lsn,
+                payload,
+                offset: write.as_ref().map(|write| write.offset),
+                memmove_src: write.as_ref().and_then(|write| write.memmove_src),
+                data: write.and_then(|write| write.data),
+                file_name,
+                new_file_name,
             });
 
             l.advance(rlen as usize);
@@ -330,6 +475,44 @@ impl MtrChain {
         Ok(chain)
     }
 
+    /// Verifies that the decoded records account for exactly the chain payload bytes:
+    /// the records are contiguous, start at the chain LSN and end at the termination
+    /// marker. A mismatch means a parse bug or a corrupted (skipped) record.
+    pub fn validate_lengths(&self) -> Result<()> {
+        // |MTR|MTR|...|TERMINATION_MARKER|IV (encrypted only)|CHECKSUM|.
+        let iv_len = self.iv.map_or(0, |iv| iv.len()) as u32;
+        let payload_end = self.lsn + self.len.saturating_sub(1 + iv_len + 4) as Lsn;
+        let mut next_lsn = self.lsn;
+
+        for (i, mtr) in self.mtr.iter().enumerate() {
+            if mtr.lsn != next_lsn {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "mtr chain at lsn={}: record {i} starts at lsn={}, expected {next_lsn}",
+                        self.lsn, mtr.lsn
+                    ),
+                ));
+            }
+
+            next_lsn += mtr.len as Lsn;
+        }
+
+        if next_lsn != payload_end {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "mtr chain at lsn={}: records cover {} bytes, but the payload is {} bytes",
+                    self.lsn,
+                    next_lsn - self.lsn,
+                    payload_end - self.lsn
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Looks through the MTR chain end finds the end marker.
     /// Where the chain is |MTR|MTR|...|^TERMINATION_MARKER|CHECKSUM|.
     /// Header byte, termination marker and checksum are not included
@@ -350,13 +533,13 @@ impl MtrChain {
             let mut rlen = (r.read_1()? & 0xf) as u32;
             if rlen == 0 {
                 let addlen = mlog_decode_varint(r.clone())?;
-                if payload_len >= MTR_SIZE_MAX {
+                if addlen >= MTR_SIZE_MAX {
                     return Err(Error::from(ErrorKind::NotFound));
                 }
                 rlen = addlen + 15;
             }
 
-            payload_len += rlen;
+            payload_len = payload_len.saturating_add(rlen);
 
             if !r.advance(rlen as usize) {
                 // if ring buffer pos overflow is not supported we don't want it.
@@ -383,7 +566,7 @@ impl MtrChain {
             header
         );
 
-        let size = min(mtr_len, chain_end_lsn as u32 - mtr.pos() as u32) as usize;
+        let size = min(mtr_len as u64, chain_end_lsn.saturating_sub(mtr.pos())) as usize;
         let mut buf = vec![0u8; size];
         mtr.block(buf.as_mut_slice());
         eprintln!("InnoDB: malformed mtr: {buf:x?}");
@@ -392,22 +575,123 @@ impl MtrChain {
     pub fn len(&self) -> u32 {
         self.len
     }
+
+    /// Serializes the chain back into its unencrypted redo log representation: the
+    /// records, the termination marker of the generation of the chain end and the checksum.
+    /// Fails on the records `Mtr::to_bytes()` does not support. Same-page records are
+    /// written with the full page identifier, so the chain may grow.
+    pub fn to_bytes(&self, header: u64, capacity: u64) -> Result<Vec<u8>> {
+        let mut records = Vec::with_capacity(self.len as usize);
+        for mtr in &self.mtr {
+            records.write_all(&mtr.to_bytes()?)?;
+        }
+
+        let mut buf = Vec::with_capacity(records.len() + 1 + 4);
+        Self::build(&mut buf, header, capacity, self.lsn, &records)?;
+
+        Ok(buf)
+    }
+
+    /// Builds a chain at `lsn` of the serialized `records` (i.e. from `Mtr::build_write()`):
+    /// the records, the termination marker of the generation of the chain end and the
+    /// checksum of the records.
+    pub fn build(
+        mut buf: impl Write,
+        header: u64,
+        capacity: u64,
+        lsn: Lsn,
+        records: &[u8],
+    ) -> Result<()> {
+        if records.first().is_none_or(|&b| b <= MTR_END_MARKER) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "MTR chain must start with a record",
+            ));
+        }
+
+        let marker_lsn = lsn
+            .checked_add(records.len() as Lsn)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "MTR chain LSN overflow"))?;
+
+        buf.write_all(records)?;
+        buf.write_all(&[get_sequence_bit(header, capacity, marker_lsn)])?;
+        mach_write_to_4(&mut buf, crc32c::crc32c(records))?;
+
+        Ok(())
+    }
+
+    /// Builds a chain like `build()` for a log encrypted with `key`: the records are
+    /// encrypted with the chain LSN as the IV, which follows the termination marker and
+    /// is covered by the checksum. See log_t::append_prepare() and log_encrypt_buf().
+    pub fn build_encrypted(
+        mut buf: impl Write,
+        header: u64,
+        capacity: u64,
+        lsn: Lsn,
+        records: &[u8],
+        key: &LogCryptKey,
+    ) -> Result<()> {
+        if records.first().is_none_or(|&b| b <= MTR_END_MARKER) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "MTR chain must start with a record",
+            ));
+        }
+
+        let marker_lsn = lsn
+            .checked_add(records.len() as Lsn)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "MTR chain LSN overflow"))?;
+
+        let iv = lsn.to_be_bytes();
+        let mut encrypted = records.to_vec();
+        log0crypt::log_crypt_buf(key, &iv, &mut encrypted)?;
+
+        buf.write_all(&encrypted)?;
+        buf.write_all(&[get_sequence_bit(header, capacity, marker_lsn)])?;
+        buf.write_all(&iv)?;
+        mach_write_to_4(
+            &mut buf,
+            crc32c::crc32c_append(crc32c::crc32c(&encrypted), &iv),
+        )?;
+
+        Ok(())
+    }
 }
 
 impl Mtr {
     pub fn build_file_checkpoint(
+        buf: impl Write,
+        header: u64,
+        capacity: u64,
+        lsn: Lsn,
+    ) -> Result<()> {
+        Mtr::build_file_checkpoint_at(buf, header, capacity, lsn, lsn)
+    }
+
+    /// Builds a FILE_CHECKPOINT record naming `checkpoint_lsn` to be written at `lsn`, the
+    /// end of the log at the time of the checkpoint, like the server does. The termination
+    /// marker is the one of the generation at `lsn`.
+    pub fn build_file_checkpoint_at(
         mut buf: impl Write,
         header: u64,
         capacity: u64,
         lsn: Lsn,
+        checkpoint_lsn: Lsn,
     ) -> Result<()> {
-        if lsn < header {
+        if lsn < header || checkpoint_lsn < header {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
                 "LSN must be greater than or equal to the header size",
             ));
         }
 
+        if checkpoint_lsn > lsn {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "checkpoint LSN must not be after the record LSN",
+            ));
+        }
+
         // 16 bytes is the record + 0x00 is the last termination marker.
         if lsn >= u64::MAX - 16 {
             return Err(Error::new(
@@ -422,7 +706,7 @@ impl Mtr {
 
         cursor.write_all(&[0xfa])?; // FILE_CHECKPOINT + body len 10 bytes
         cursor.write_all(&[0x00, 0x00])?; // tablespace id + page no
-        mach_write_to_8(&mut cursor, lsn)?; // checkpoint LSN
+        mach_write_to_8(&mut cursor, checkpoint_lsn)?; // checkpoint LSN
 
         let termination_marker = get_sequence_bit(header, capacity, lsn + 1 + 2 + 8);
         cursor.write_all(&[termination_marker])?;
@@ -434,6 +718,169 @@ impl Mtr {
 
         Ok(())
     }
+
+    /// Builds a WRITE record of `data` at the byte `offset` of the page. The record
+    /// carries the page identifier, so the offset is absolute within the page.
+    pub fn build_write(
+        buf: impl Write,
+        space_id: u32,
+        page_no: u32,
+        offset: u32,
+        data: &[u8],
+    ) -> Result<()> {
+        if data.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "WRITE record must write at least one byte",
+            ));
+        }
+        check_page_range(offset, data.len())?;
+
+        let mut body = vec![];
+        mlog_encode_varint(&mut body, space_id)?;
+        mlog_encode_varint(&mut body, page_no)?;
+        mlog_encode_varint(&mut body, offset)?;
+        body.write_all(data)?;
+
+        write_record(buf, WRITE as u8, &body)
+    }
+
+    /// Builds a MEMSET record filling `len` bytes at the byte `offset` of the page by
+    /// repeating `fill`, which must be shorter than `len`.
+    pub fn build_memset(
+        buf: impl Write,
+        space_id: u32,
+        page_no: u32,
+        offset: u32,
+        len: u32,
+        fill: &[u8],
+    ) -> Result<()> {
+        if fill.is_empty() || fill.len() >= len as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "MEMSET record fill pattern of {} bytes must be non-empty and shorter than \
+                     {len} bytes",
+                    fill.len()
+                ),
+            ));
+        }
+        check_page_range(offset, len as usize)?;
+
+        let mut body = vec![];
+        mlog_encode_varint(&mut body, space_id)?;
+        mlog_encode_varint(&mut body, page_no)?;
+        mlog_encode_varint(&mut body, offset)?;
+        mlog_encode_varint(&mut body, len - 1)?;
+        body.write_all(fill)?;
+
+        write_record(buf, MEMSET as u8, &body)
+    }
+
+    /// Serializes the record back into its redo log representation, with the page
+    /// identifier written out in full. Only FILE_CHECKPOINT, WRITE and MEMSET records
+    /// are supported.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+
+        match (self.op, self.file_checkpoint_lsn, self.offset, &self.data) {
+            (MtrOperation::FileCheckpoint, Some(lsn), ..)
+                if self.space_id == 0 && self.page_no == 0 =>
+            {
+                buf.write_all(&[FILE_CHECKPOINT as u8 | 10])?; // FILE_CHECKPOINT + body len 10 bytes
+                mlog_encode_varint(&mut buf, self.space_id)?;
+                mlog_encode_varint(&mut buf, self.page_no)?;
+                mach_write_to_8(&mut buf, lsn)?; // checkpoint LSN
+            }
+            (MtrOperation::Write, _, Some(offset), Some(data)) => {
+                Mtr::build_write(&mut buf, self.space_id, self.page_no, offset, data)?;
+            }
+            (MtrOperation::Memset, _, Some(offset), Some(fill)) => {
+                // the length follows the relative offset in the payload.
+                let mut r = self.payload.as_slice();
+                mlog_decode_varint(&mut r)?;
+                let len = mlog_decode_varint(&mut r)?
+                    .checked_add(1)
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "MEMSET length overflow"))?;
+
+                Mtr::build_memset(&mut buf, self.space_id, self.page_no, offset, len, fill)?;
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::Unsupported,
+                    format!("can't serialize {:?} record at lsn={}", self.op, self.lsn),
+                ));
+            }
+        }
+
+        Ok(buf)
+    }
+}
+
+/// A redo record framed independently of the ring buffer layout:
+/// |LEN:4|SPACE_ID:4|PAGE_NO:4|OP:1|PAYLOAD|, where LEN counts the bytes after itself.
+/// See `read-redo --export-records`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedRecord {
+    pub space_id: u32,
+    pub page_no: u32,
+    pub op: MtrOperation,
+    pub payload: Vec<u8>,
+}
+
+impl ExportedRecord {
+    /// Size of the fixed part of the frame following the length.
+    pub const HEADER_SIZE: usize = 4 + 4 + 1;
+
+    pub fn write_to(&self, mut w: impl Write) -> Result<()> {
+        let len = u32::try_from(Self::HEADER_SIZE + self.payload.len())
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "record payload is too large"))?;
+
+        mach_write_to_4(&mut w, len)?;
+        mach_write_to_4(&mut w, self.space_id)?;
+        mach_write_to_4(&mut w, self.page_no)?;
+        w.write_all(&[self.op as u8])?;
+        w.write_all(&self.payload)
+    }
+
+    /// Reads the next framed record. Returns None at the end of the stream.
+    pub fn read_from(mut r: impl Read) -> Result<Option<ExportedRecord>> {
+        let mut len = [0u8; 4];
+        match r.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+
+        let len = mach_read_from_4(&len) as usize;
+        if len < Self::HEADER_SIZE {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("record frame is too short: {len} bytes"),
+            ));
+        }
+
+        let mut frame = vec![0u8; len];
+        r.read_exact(&mut frame)?;
+
+        Ok(Some(ExportedRecord {
+            space_id: mach_read_from_4(&frame[0..]),
+            page_no: mach_read_from_4(&frame[4..]),
+            op: MtrOperation::try_from(frame[8])?,
+            payload: frame.split_off(Self::HEADER_SIZE),
+        }))
+    }
+}
+
+impl From<&Mtr> for ExportedRecord {
+    fn from(mtr: &Mtr) -> Self {
+        ExportedRecord {
+            space_id: mtr.space_id,
+            page_no: mtr.page_no,
+            op: mtr.op,
+            payload: mtr.payload.clone(),
+        }
+    }
 }
 
 impl Display for MtrChain {
@@ -450,21 +897,135 @@ impl Display for Mtr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Mtr {{ space_id: {}, page_no: {}, op: {:?} }} at ({}+{})",
-            self.space_id, self.page_no, self.op, self.lsn, self.len
-        )
+            "Mtr {{ space_id: {}, page_no: {}, op: {:?}",
+            self.space_id, self.page_no, self.op
+        )?;
+
+        if let Some(ext_subtype) = self.ext_subtype {
+            write!(f, "({ext_subtype:?})")?;
+        }
+
+        if let Some(opt_subtype) = self.opt_subtype {
+            write!(f, "({opt_subtype:?})")?;
+        }
+
+        write!(f, " }} at ({}+{})", self.lsn, self.len)
+    }
+}
+
+/// Checks that `len` bytes at `offset` fit into the largest page.
+fn check_page_range(offset: u32, len: usize) -> Result<()> {
+    if offset as u64 + len as u64 > UNIV_PAGE_SIZE_MAX as u64 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{len} bytes at offset {offset} do not fit into a page"),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Writes a record of type `op` with `body` following the header byte. Bodies longer
+/// than 15 bytes are preceded by the additional length bytes, see `mlog_decode_varint()`.
+fn write_record(mut buf: impl Write, op: u8, body: &[u8]) -> Result<()> {
+    if body.len() <= 15 {
+        buf.write_all(&[op | body.len() as u8])?;
+        return buf.write_all(body);
+    }
+
+    // the additional length counts itself: total = addlen + 15 bytes after the header.
+    let mut len = vec![];
+    for lenlen in 1..=3 {
+        let addlen = u32::try_from(body.len() + lenlen - 15)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "record is too long"))?;
+        len.clear();
+        mlog_encode_varint(&mut len, addlen)?;
+        if len.len() == lenlen {
+            break;
+        }
+    }
+
+    if len.len() > 3 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("record of {} bytes is too long", body.len()),
+        ));
+    }
+
+    buf.write_all(&[op])?;
+    buf.write_all(&len)?;
+    buf.write_all(body)
+}
+
+/// A WRITE, MEMSET or MEMMOVE record decoded by `decode_page_write()`.
+struct PageWrite {
+    /// Absolute byte offset of the modified range.
+    offset: u32,
+    /// The literal bytes (none for MEMMOVE).
+    data: Option<Vec<u8>>,
+    /// MEMMOVE source offset relative to `offset`.
+    memmove_src: Option<i32>,
+    /// End of the modified range, the current byte offset for the next record.
+    end: u32,
+}
+
+/// Decodes a WRITE, MEMSET or MEMMOVE record from the record bytes following the page
+/// identifier. The encoded offset is relative to `last_offset`, the end of the previous
+/// such record for the page.
+fn decode_page_write(op: MtrOperation, payload: &[u8], last_offset: u32) -> Option<PageWrite> {
+    let mut r = payload;
+    let offset = last_offset.checked_add(mlog_decode_varint(&mut r).ok()?)?;
+
+    let (data, memmove_src, data_len) = match op {
+        MtrOperation::Write => (Some(r.to_vec()), None, r.len() as u32),
+        MtrOperation::Memset => {
+            let data_len = mlog_decode_varint(&mut r).ok()?.checked_add(1)?;
+            (Some(r.to_vec()), None, data_len)
+        }
+        MtrOperation::Memmove => {
+            let data_len = mlog_decode_varint(&mut r).ok()?.checked_add(1)?;
+            let src = mlog_decode_memmove_src(mlog_decode_varint(&mut r).ok()?);
+            (None, Some(src), data_len)
+        }
+        _ => return None,
+    };
+
+    if data.as_ref().is_some_and(|data| data.is_empty()) {
+        return None;
     }
+
+    Some(PageWrite {
+        offset,
+        data,
+        memmove_src,
+        end: offset.checked_add(data_len)?,
+    })
+}
+
+/// Returns whether the source range of a MEMMOVE record is within the page.
+fn memmove_src_in_page(write: &PageWrite) -> bool {
+    let Some(src) = write.memmove_src else {
+        return true;
+    };
+    let src_start = write.offset as i64 + src as i64;
+
+    src_start >= 0 && src_start + (write.end - write.offset) as i64 <= UNIV_PAGE_SIZE_MAX as i64
 }
 
-/// Determine the sequence bit at a log sequence number.
-/// The sequence bit is used to determine whether the log record
-/// corresponds to the current generation (wrap) of the redo log.
-/// Capacity is the capacity of the ring buffer in bytes (file size - header).
-pub fn get_sequence_bit(header_size: u64, capacity: u64, lsn: Lsn) -> u8 {
-    if (((lsn - header_size) / capacity) & 1) == 0 {
-        1
-    } else {
-        0
+/// Decodes the file name following the page identifier of a FILE_CREATE, FILE_DELETE,
+/// FILE_MODIFY or FILE_RENAME record, and the new name after the NUL of FILE_RENAME.
+fn decode_file_names(op: MtrOperation, payload: &[u8]) -> (Option<String>, Option<String>) {
+    let name = |bytes: &[u8]| Some(String::from_utf8_lossy(bytes).into_owned());
+
+    match op {
+        MtrOperation::FileCreate | MtrOperation::FileDelete | MtrOperation::FileModify => {
+            (name(payload), None)
+        }
+        MtrOperation::FileRename => match payload.iter().position(|&b| b == 0) {
+            Some(nul) => (name(&payload[..nul]), name(&payload[nul + 1..])),
+            None => (name(payload), None),
+        },
+        _ => (None, None),
     }
 }
 
@@ -481,10 +1042,16 @@ pub fn peek_not_end_marker(r: &RingReader) -> Result<()> {
 
 #[cfg(test)]
 mod test {
-    use std::io::{Error, ErrorKind};
+    use std::io::{Error, ErrorKind, Write};
 
-    use super::{Mtr, MtrChain};
-    use crate::{mtr0types::MtrOperation, ring::RingReader};
+    use super::{ExportedRecord, MTR_CRYPT_IV_SIZE, Mtr, MtrChain, UNIV_PAGE_SIZE_MAX};
+    use crate::{
+        fil0fil,
+        log0crypt::LogCryptKey,
+        mtr0log::{mlog_encode_memmove_src, mlog_encode_varint},
+        mtr0types::{MtrOperation, mrec_ext_t, mrec_opt},
+        ring::{RingReader, RingWriter},
+    };
 
     #[test]
     fn test_mtr_short_len() {
@@ -507,7 +1074,7 @@ mod test {
         let lsn = 0x000000000000de3d;
         let hdr_size = 0;
         let fake_capacity = 0xffff;
-        let marker = super::get_sequence_bit(hdr_size, fake_capacity, lsn);
+        let marker = crate::lsn::get_sequence_bit(hdr_size, fake_capacity, lsn);
         Mtr::build_file_checkpoint(&mut buf, hdr_size, fake_capacity, lsn).unwrap();
 
         let r0 = RingReader::new(buf.as_slice());
@@ -529,16 +1096,104 @@ mod test {
         );
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_chain_json() {
+        let mut buf = Vec::new();
+        let lsn = 0xde3d;
+        Mtr::build_file_checkpoint(&mut buf, 0, 0xffff, lsn).unwrap();
+
+        let chain = MtrChain::parse_next(&mut RingReader::new(buf.as_slice())).unwrap();
+        let json = serde_json::to_string(&chain).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["lsn"], 0, "{json}");
+        assert_eq!(value["len"], 16, "{json}");
+        assert_eq!(value["marker"], 1, "{json}");
+        assert_eq!(value["checksum"], chain.checksum, "{json}");
+        assert_eq!(value["mtr"][0]["op"], "FileCheckpoint", "{json}");
+        assert_eq!(value["mtr"][0]["file_checkpoint_lsn"], lsn, "{json}");
+        assert_eq!(value["mtr"][0]["payload"], "000000000000de3d", "{json}");
+    }
+
+    #[test]
+    fn test_file_checkpoint_to_bytes() {
+        let (hdr_size, capacity) = (0x40, 0x100);
+
+        // the record fits the generation, crosses the wrap and starts the next generation.
+        for lsn in [0x80, 0x13a, 0x140, 0x150] {
+            let mut buf = Vec::new();
+            Mtr::build_file_checkpoint(&mut buf, hdr_size, capacity, lsn).unwrap();
+
+            let mut storage = vec![0u8; (hdr_size + capacity) as usize];
+            let mut w0 = RingWriter::buf_at(&mut storage, hdr_size as usize, lsn);
+            w0.write_all(&buf).unwrap();
+
+            let mut r0 = RingReader::buf_at(&storage, hdr_size as usize, lsn);
+            let chain = MtrChain::parse_next(&mut r0).unwrap();
+
+            assert_eq!(chain.mtr[0].to_bytes().unwrap(), buf[..1 + 10]);
+            assert_eq!(
+                chain.to_bytes(hdr_size, capacity).unwrap(),
+                buf,
+                "lsn={lsn}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_page_write_to_bytes() {
+        let (hdr_size, capacity, lsn) = (0x40, 0x200, 0x80);
+        let parse = |records: &[u8]| {
+            let mut buf = Vec::new();
+            MtrChain::build(&mut buf, hdr_size, capacity, lsn, records).unwrap();
+
+            let mut storage = vec![0u8; (hdr_size + capacity) as usize];
+            let mut w0 = RingWriter::buf_at(&mut storage, hdr_size as usize, lsn);
+            w0.write_all(&buf).unwrap();
+
+            let mut r0 = RingReader::buf_at(&storage, hdr_size as usize, lsn);
+            (MtrChain::parse_next(&mut r0).unwrap(), buf)
+        };
+
+        let mut records = vec![vec![], vec![], vec![]];
+        Mtr::build_write(&mut records[0], 5, 3, 0x26, &[0xaa, 0xbb]).unwrap();
+        Mtr::build_memset(&mut records[1], 5, 3, 0x38, 6, &[0xcc]).unwrap();
+        Mtr::build_write(&mut records[2], 300, 70000, 16000, &[0xdd; 20]).unwrap();
+
+        let (chain, buf) = parse(&records.concat());
+        for (mtr, record) in chain.mtr.iter().zip(&records) {
+            assert_eq!(&mtr.to_bytes().unwrap(), record, "{mtr}");
+        }
+        assert_eq!(chain.to_bytes(hdr_size, capacity).unwrap(), buf);
+
+        // a same-page WRITE is written with the page identifier and its absolute offset.
+        let same_page = [
+            0x34, 0x05, 0x03, 0x26, 0xaa, // WRITE 5:3 at 0x26
+            0xb2, 0x02, 0xbb, // same page WRITE at 0x27 + 2
+        ];
+        let (chain, _) = parse(&same_page);
+        let (reparsed, _) = parse(&chain.mtr[1].to_bytes().unwrap());
+        assert_eq!(reparsed.mtr[0].space_id, 5);
+        assert_eq!(reparsed.mtr[0].page_no, 3);
+        assert_eq!(reparsed.mtr[0].offset, Some(0x29));
+        assert_eq!(reparsed.mtr[0].data, Some(vec![0xbb]));
+
+        // INIT_PAGE can't be serialized.
+        let (chain, _) = parse(&[0x12, 0x05, 0x03]);
+        let err = chain.to_bytes(hdr_size, capacity).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
     #[test]
     fn test_build_file_checkpoint_marker_0() {
         let mut buf = Vec::new();
         let lsn = 0x0000000000000030;
         let hdr_size = 0;
         let fake_capacity = 0x10;
-        let marker = super::get_sequence_bit(hdr_size, fake_capacity, lsn);
+        let marker = crate::lsn::get_sequence_bit(hdr_size, fake_capacity, lsn);
         Mtr::build_file_checkpoint(&mut buf, hdr_size, fake_capacity, lsn).unwrap();
 
-        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn as usize);
+        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn);
         let chain = MtrChain::parse_next(&mut r0.clone()).unwrap();
 
         assert_eq!(chain.len, 16, "len");
@@ -564,10 +1219,10 @@ mod test {
         let lsn = 0x0000000000000030;
         let hdr_size = 0;
         let fake_capacity = 0x10;
-        let marker = super::get_sequence_bit(hdr_size, fake_capacity, lsn);
+        let marker = crate::lsn::get_sequence_bit(hdr_size, fake_capacity, lsn);
         Mtr::build_file_checkpoint(&mut buf, hdr_size, fake_capacity, lsn).unwrap();
 
-        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn as usize);
+        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn);
         let chain = MtrChain::parse_next(&mut r0.clone()).unwrap();
 
         assert_eq!(chain.len, 16, "len");
@@ -600,7 +1255,7 @@ mod test {
         buf[..offset].copy_from_slice(&buf0[..offset]);
         buf[offset..].copy_from_slice(&buf0[offset..]);
 
-        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn as usize);
+        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn);
         assert!(MtrChain::parse_next(&mut r0.clone()).is_err());
     }
 
@@ -618,15 +1273,15 @@ mod test {
         buf[..offset].copy_from_slice(&buf0[..offset]);
         buf[offset..].copy_from_slice(&buf0[offset..]);
 
-        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn as usize);
+        let r0 = RingReader::buf_at(buf.as_slice(), hdr_size as usize, lsn);
         assert!(MtrChain::parse_next(&mut r0.clone()).is_err());
     }
 
     #[test]
     fn test_parse_mtr_chain() {
         let buf = vec![
-            // MTR Chain count=2, len=123, lsn=163
-            //   1: Mtr { space_id: 3, page_no: 45, op: Extended }
+            // MTR Chain count=2, len=123, lsn=181
+            //   1: Mtr { space_id: 3, page_no: 45, op: Extended(UNDO_APPEND) }
             //   2: Mtr { space_id: 3, page_no: 45, op: Option }
             0x20, 0x5e, 0x3, 0x2d, 0x3, 0xd, 0x3, 0xf, 0x20, 0x0, 0x0, 0x0, 0x0, 0x17, 0xc6, 0x0,
             0x0, 0x0, 0x2d, 0x1, 0x78, 0x4, 0x74, 0x65, 0x73, 0x74, 0x1, 0x61, 0x7, 0x50, 0x52,
@@ -636,7 +1291,7 @@ mod test {
             0x0, 0x4, 0x74, 0x65, 0x73, 0x74, 0x1, 0x1, 0x61, 0x2, 0x7, 0x50, 0x52, 0x49, 0x4d,
             0x41, 0x52, 0x59, 0x3, 0xc, 0x6e, 0x5f, 0x64, 0x69, 0x66, 0x66, 0x5f, 0x70, 0x66, 0x78,
             0x30, 0x31, 0x77, 0x3, 0x2d, 0x0, 0x80, 0x89, 0x7e, 0x61, 0x0, 0xa8, 0xf3, 0xd8, 0x55,
-            // MTR Chain count=1, len=39, lsn=286
+            // MTR Chain count=1, len=39, lsn=304
             //   1: Mtr { space_id: 0, page_no: 0, op: FileModify }
             0xb0, 0x12, 0x4, 0x0, 0x2e, 0x2f, 0x6d, 0x79, 0x73, 0x71, 0x6c, 0x2f, 0x69, 0x6e, 0x6e,
             0x6f, 0x64, 0x62, 0x5f, 0x74, 0x61, 0x62, 0x6c, 0x65, 0x5f, 0x73, 0x74, 0x61, 0x74,
@@ -645,18 +1300,480 @@ mod test {
             0x00,
         ];
 
-        let mut r0 = RingReader::buf_at(buf.as_slice(), 0, buf.len());
+        // MTR Chain count=3, len=18, lsn=343
+        //   1: Mtr { space_id: 3, page_no: 45, op: Write } at 0x26
+        //   2: Mtr { space_id: 3, page_no: 45, op: Memset } at 0x28 + 0x10
+        //   3: Mtr { space_id: 3, page_no: 45, op: Write } at 0x3d + 0x01
+        let records = [
+            0x35, 0x03, 0x2d, 0x26, 0xaa, 0xbb, 0xc3, 0x10, 0x04, 0xcc, 0xb2, 0x01, 0xdd,
+        ];
+        let mut buf = buf;
+        buf.pop();
+        let lsn = buf.len() as u64;
+        let len = buf.len() + records.len() + 1 + 4 + 1;
+        MtrChain::build(&mut buf, 0, len as u64, len as u64 + lsn, &records).unwrap();
+        buf.push(0x00);
+
+        let mut r0 = RingReader::buf_at(buf.as_slice(), 0, buf.len() as u64);
         let chain = MtrChain::parse_next(&mut r0).unwrap();
         // println!("Parsed MTR chain: {chain:?}");
 
         assert_eq!(chain.len(), 123, "chain len in bytes");
         assert_eq!(chain.mtr.len(), 2, "chain mtr count");
+        chain.validate_lengths().unwrap();
+        assert_eq!(chain.mtr[0].op, MtrOperation::Extended);
+        assert_eq!(chain.mtr[0].ext_subtype, Some(mrec_ext_t::UNDO_APPEND));
+        assert_eq!(chain.mtr[1].ext_subtype, None);
+        assert_eq!(chain.mtr[1].op, MtrOperation::Option);
+        assert_eq!(chain.mtr[1].opt_subtype, Some(mrec_opt::OPT_PAGE_CHECKSUM));
+        // EXTENDED and OPTION records do not modify a byte range.
+        assert_eq!(
+            chain.mtr.iter().map(|mtr| mtr.offset).collect::<Vec<_>>(),
+            vec![None, None]
+        );
+        assert!(chain.mtr.iter().all(|mtr| mtr.data.is_none()));
 
         let chain = MtrChain::parse_next(&mut r0).unwrap();
         // println!("Parsed MTR chain: {chain:?}");
 
         assert_eq!(chain.len(), 39, "chain len in bytes");
         assert_eq!(chain.mtr.len(), 1, "chain mtr count");
+        chain.validate_lengths().unwrap();
+        assert_eq!(chain.mtr[0].op, MtrOperation::FileModify);
+        assert_eq!(chain.mtr[0].space_id, 4);
+        assert_eq!(
+            chain.mtr[0].file_name.as_deref(),
+            Some("./mysql/innodb_table_stats.ibd")
+        );
+        assert_eq!(chain.mtr[0].new_file_name, None);
+
+        // the offsets of same-page records continue from the end of the previous one.
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        assert_eq!(chain.lsn, 343);
+        assert_eq!(chain.len(), 18, "chain len in bytes");
+        chain.validate_lengths().unwrap();
+        assert_eq!(
+            chain
+                .mtr
+                .iter()
+                .map(|mtr| (mtr.op, mtr.offset, mtr.data.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (MtrOperation::Write, Some(0x26), Some(vec![0xaa, 0xbb])),
+                (MtrOperation::Memset, Some(0x38), Some(vec![0xcc])),
+                (MtrOperation::Write, Some(0x3e), Some(vec![0xdd])),
+            ]
+        );
+        assert!(MtrChain::parse_next(&mut r0).is_err());
+    }
+
+    #[test]
+    fn test_decode_extended() {
+        let buf = [
+            0x23, 0x05, 0x07, 0x0a, // EXTENDED 5:7 TRIM_PAGES
+            0xa1, 0x01, // same page EXTENDED INIT_ROW_FORMAT_DYNAMIC
+            0xb2, 0x00, 0xff, // same page WRITE at FIL_PAGE_TYPE
+            0x23, 0x05, 0x08, 0x7f, // EXTENDED 5:8 with an unknown subtype
+            0x01, // termination marker
+            0xff, 0xff, 0xff, 0xff, // checksum
+        ];
+        let mut buf = buf.to_vec();
+        let crc = crc32c::crc32c(&buf[..13]);
+        buf[14..18].copy_from_slice(&crc.to_be_bytes());
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        chain.validate_lengths().unwrap();
+
+        let subtypes = chain
+            .mtr
+            .iter()
+            .map(|mtr| mtr.ext_subtype)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            subtypes,
+            vec![
+                Some(mrec_ext_t::TRIM_PAGES),
+                Some(mrec_ext_t::INIT_ROW_FORMAT_DYNAMIC),
+                None,
+                None
+            ]
+        );
+        assert_eq!(chain.mtr[1].page_no, 7);
+        // an EXTENDED record resets the byte offset to FIL_PAGE_TYPE.
+        assert_eq!(chain.mtr[2].offset, Some(fil0fil::FIL_PAGE_TYPE));
+        assert_eq!(chain.mtr[3].op, MtrOperation::Extended);
+        assert!(
+            chain.mtr[0]
+                .to_string()
+                .contains("op: Extended(TRIM_PAGES) }")
+        );
+    }
+
+    #[test]
+    fn test_decode_option() {
+        let buf = [
+            0x35, 0x03, 0x2d, 0x26, 0xaa, 0xbb, // WRITE 3:45 at 0x26
+            0xf5, 0x00, 0x12, 0x34, 0x56, 0x78, // same page OPT_PAGE_CHECKSUM
+            0xb2, 0x02, 0xcc, // same page WRITE at 0x28 + 0x02
+            0x73, 0x03, 0x2e, 0x05, // OPTION 3:46 with an unknown subtype
+            0x01, // termination marker
+            0xff, 0xff, 0xff, 0xff, // checksum
+        ];
+        let mut buf = buf.to_vec();
+        let crc = crc32c::crc32c(&buf[..19]);
+        buf[20..24].copy_from_slice(&crc.to_be_bytes());
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        chain.validate_lengths().unwrap();
+
+        let decoded = chain
+            .mtr
+            .iter()
+            .map(|mtr| (mtr.op, mtr.opt_subtype, mtr.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            vec![
+                (MtrOperation::Write, None, Some(0x26)),
+                (
+                    MtrOperation::Option,
+                    Some(mrec_opt::OPT_PAGE_CHECKSUM),
+                    None
+                ),
+                // the OPTION record does not move the byte offset.
+                (MtrOperation::Write, None, Some(0x2a)),
+                (MtrOperation::Option, None, None),
+            ]
+        );
+        assert_eq!(chain.mtr[1].payload, vec![0x00, 0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(chain.mtr[2].data, Some(vec![0xcc]));
+        assert_eq!(chain.mtr[3].page_no, 0x2e);
+    }
+
+    #[test]
+    fn test_build_page_writes() {
+        let long = (0..200u8).collect::<Vec<_>>();
+
+        let mut buf = vec![];
+        Mtr::build_write(&mut buf, 3, 45, 0x26, &[0xaa, 0xbb]).unwrap();
+        Mtr::build_memset(&mut buf, 3, 46, 0x100, 5, &[0xcc]).unwrap();
+        Mtr::build_write(&mut buf, 300, 70000, 16000, &long).unwrap();
+        assert_eq!(&buf[..6], &[0x35, 0x03, 0x2d, 0x26, 0xaa, 0xbb]);
+
+        assert!(Mtr::build_write(&mut buf, 3, 45, 0, &[]).is_err());
+        assert!(Mtr::build_write(&mut buf, 3, 45, UNIV_PAGE_SIZE_MAX - 1, &[1, 2]).is_err());
+        assert!(Mtr::build_memset(&mut buf, 3, 45, 0, 1, &[0xcc]).is_err());
+        assert!(Mtr::build_memset(&mut buf, 3, 45, 0, 4, &[]).is_err());
+
+        let records = buf.len();
+        buf.push(0x01); // termination marker
+        let crc = crc32c::crc32c(&buf[..records]);
+        buf.extend_from_slice(&crc.to_be_bytes());
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        chain.validate_lengths().unwrap();
+
+        let decoded = chain
+            .mtr
+            .iter()
+            .map(|mtr| {
+                (
+                    mtr.space_id,
+                    mtr.page_no,
+                    mtr.op,
+                    mtr.offset,
+                    mtr.data.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decoded,
+            vec![
+                (
+                    3,
+                    45,
+                    MtrOperation::Write,
+                    Some(0x26),
+                    Some(vec![0xaa, 0xbb])
+                ),
+                (3, 46, MtrOperation::Memset, Some(0x100), Some(vec![0xcc])),
+                (300, 70000, MtrOperation::Write, Some(16000), Some(long)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_malformed_file_record() {
+        let buf = [
+            0x82, 0x00, 0x00, // FILE_CREATE 0:0 without a file name
+            0x35, 0x03, 0x2d, 0x26, 0xaa, 0xbb, // WRITE 3:45 at 0x26
+            0x01, // termination marker
+            0xff, 0xff, 0xff, 0xff, // checksum
+        ];
+        let mut buf = buf.to_vec();
+        let crc = crc32c::crc32c(&buf[..9]);
+        buf[10..14].copy_from_slice(&crc.to_be_bytes());
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        assert_eq!(chain.len(), 14);
+        assert_eq!(
+            chain.mtr.iter().map(|mtr| mtr.op).collect::<Vec<_>>(),
+            vec![MtrOperation::Write]
+        );
+        assert_eq!(chain.mtr[0].offset, Some(0x26));
+    }
+
+    #[test]
+    fn test_build_chain() {
+        let header = 0x3000;
+        let capacity = 0x1000;
+        let mut ring = vec![0u8; (header + capacity) as usize];
+
+        // the chain ends in the second generation of the ring.
+        let lsn = header + capacity - 8;
+        let mut records = vec![];
+        Mtr::build_write(&mut records, 5, 9, 0x1234, &[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        let mut chain = vec![];
+        MtrChain::build(&mut chain, header, capacity, lsn, &records).unwrap();
+        assert_eq!(chain.len(), records.len() + 1 + 4);
+        assert_eq!(chain[records.len()], 0, "marker of the second generation");
+        assert!(MtrChain::build(&mut chain, header, capacity, lsn, &[]).is_err());
+        assert!(MtrChain::build(&mut chain, header, capacity, lsn, &[0x01]).is_err());
+
+        let mut w = RingWriter::buf_at(ring.as_mut_slice(), header as usize, lsn);
+        w.write_all(&chain[..records.len() + 1 + 4]).unwrap();
+        w.write_all(&[0x01]).unwrap(); // end marker of the generation
+
+        let mut r0 = RingReader::buf_at(ring.as_slice(), header as usize, lsn);
+        let parsed = MtrChain::parse_next(&mut r0).unwrap();
+        parsed.validate_lengths().unwrap();
+        assert_eq!(parsed.lsn, lsn);
+        assert_eq!(parsed.len() as usize, records.len() + 1 + 4);
+        assert_eq!(parsed.mtr.len(), 1);
+        assert_eq!(parsed.mtr[0].op, MtrOperation::Write);
+        assert_eq!((parsed.mtr[0].space_id, parsed.mtr[0].page_no), (5, 9));
+        assert_eq!(parsed.mtr[0].offset, Some(0x1234));
+        assert_eq!(parsed.mtr[0].data, Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!(
+            &parsed.mtr[0].payload[parsed.mtr[0].payload.len() - 4..],
+            &[0xde, 0xad, 0xbe, 0xef]
+        );
+    }
+
+    #[test]
+    fn test_build_encrypted_chain() {
+        let header = 0x3000;
+        let capacity = 0x1000;
+        let mut ring = vec![0u8; (header + capacity) as usize];
+        let key: LogCryptKey = *b"0123456789abcdef";
+
+        // the chain wraps around the end of the ring.
+        let lsn = header + capacity - 8;
+        let mut records = vec![];
+        Mtr::build_write(&mut records, 5, 9, 0x1234, &[0xde, 0xad, 0xbe, 0xef]).unwrap();
+        Mtr::build_write(&mut records, 6, 7, 0x40, &[0x11, 0x22]).unwrap();
+        let mut chain = vec![];
+        MtrChain::build_encrypted(&mut chain, header, capacity, lsn, &records, &key).unwrap();
+        assert_eq!(chain.len(), records.len() + 1 + MTR_CRYPT_IV_SIZE + 4);
+        assert_eq!(chain[0], records[0], "record header is in plaintext");
+        assert_ne!(&chain[..records.len()], records.as_slice());
+        assert_eq!(
+            &chain[records.len() + 1..records.len() + 1 + MTR_CRYPT_IV_SIZE],
+            &lsn.to_be_bytes()
+        );
+
+        let mut w = RingWriter::buf_at(ring.as_mut_slice(), header as usize, lsn);
+        w.write_all(&chain).unwrap();
+        w.write_all(&[0x00]).unwrap(); // end marker of the generation
+
+        let mut r0 = RingReader::buf_at(ring.as_slice(), header as usize, lsn);
+        let parsed = MtrChain::parse_next_with(&mut r0, Some(&key)).unwrap();
+        parsed.validate_lengths().unwrap();
+        assert_eq!(parsed.lsn, lsn);
+        assert_eq!(parsed.len() as usize, chain.len());
+        assert_eq!(r0.pos(), lsn + chain.len() as u64);
+        assert_eq!(parsed.mtr.len(), 2);
+        assert_eq!((parsed.mtr[0].space_id, parsed.mtr[0].page_no), (5, 9));
+        assert_eq!(parsed.mtr[0].offset, Some(0x1234));
+        assert_eq!(parsed.mtr[0].data, Some(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_eq!((parsed.mtr[1].space_id, parsed.mtr[1].page_no), (6, 7));
+        assert_eq!(parsed.mtr[1].offset, Some(0x40));
+        assert_eq!(parsed.mtr[1].data, Some(vec![0x11, 0x22]));
+
+        // the records can not be parsed without the key, and not with the wrong key.
+        let mut r0 = RingReader::buf_at(ring.as_slice(), header as usize, lsn);
+        assert!(MtrChain::parse_next(&mut r0).is_err());
+        let mut r0 = RingReader::buf_at(ring.as_slice(), header as usize, lsn);
+        let wrong = MtrChain::parse_next_with(&mut r0, Some(&[0u8; 16]));
+        assert!(!wrong.is_ok_and(|c| c.mtr.iter().any(|m| m.data == parsed.mtr[0].data)));
+    }
+
+    #[test]
+    fn test_decode_page_writes() {
+        let buf = [
+            0x35, 0x03, 0x2d, 0x26, 0xaa, 0xbb, // WRITE 3:45 at 0x26
+            0xc3, 0x10, 0x04, 0xcc, // same page MEMSET 5 bytes at 0x28 + 0x10
+            0xd3, 0x02, 0x03, 0x01, // same page MEMMOVE 4 bytes at 0x3d + 0x02
+            0x12, 0x03, 0x2e, // INIT_PAGE 3:46
+            0xb2, 0x00, 0xff, // same page WRITE at FIL_PAGE_TYPE
+            0x01, // termination marker
+            0xff, 0xff, 0xff, 0xff, // checksum
+        ];
+        let mut buf = buf.to_vec();
+        let crc = crc32c::crc32c(&buf[..20]);
+        buf[21..25].copy_from_slice(&crc.to_be_bytes());
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        chain.validate_lengths().unwrap();
+
+        let decoded = chain
+            .mtr
+            .iter()
+            .map(|mtr| (mtr.page_no, mtr.op, mtr.offset, mtr.data.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(chain.mtr[2].memmove_src, Some(-1));
+        assert_eq!(
+            decoded,
+            vec![
+                (45, MtrOperation::Write, Some(0x26), Some(vec![0xaa, 0xbb])),
+                (45, MtrOperation::Memset, Some(0x38), Some(vec![0xcc])),
+                (45, MtrOperation::Memmove, Some(0x3f), None),
+                (46, MtrOperation::InitPage, None, None),
+                (46, MtrOperation::Write, Some(24), Some(vec![0xff])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_memmove() {
+        let parse = |src: i32| {
+            // MEMMOVE 3:45 of 16 bytes at 0x100
+            let mut buf = vec![0x50, 0x03, 0x2d];
+            mlog_encode_varint(&mut buf, 0x100).unwrap();
+            mlog_encode_varint(&mut buf, 16 - 1).unwrap();
+            mlog_encode_varint(&mut buf, mlog_encode_memmove_src(src)).unwrap();
+            buf[0] |= (buf.len() - 1) as u8;
+            let crc = crc32c::crc32c(&buf);
+            buf.push(0x01); // termination marker
+            buf.extend_from_slice(&crc.to_be_bytes());
+
+            MtrChain::parse_next(&mut RingReader::new(buf.as_slice())).unwrap()
+        };
+
+        let chain = parse(0x40);
+        assert_eq!(chain.mtr[0].op, MtrOperation::Memmove);
+        assert_eq!(chain.mtr[0].offset, Some(0x100));
+        assert_eq!(chain.mtr[0].memmove_src, Some(0x40));
+        assert_eq!(chain.mtr[0].data, None);
+
+        assert_eq!(parse(-0x100).mtr[0].memmove_src, Some(-0x100));
+
+        // the source range would start before or end after the page.
+        assert!(parse(-0x101).mtr.is_empty());
+        assert!(parse(UNIV_PAGE_SIZE_MAX as i32 - 0x100 - 15).mtr.is_empty());
+        assert_eq!(parse(UNIV_PAGE_SIZE_MAX as i32 - 0x100 - 16).mtr.len(), 1);
+    }
+
+    #[test]
+    fn test_decode_file_names() {
+        let mut buf = vec![0xad, 0x05, 0x00]; // FILE_RENAME 5
+        buf.extend_from_slice(b"a.ibd\0b.ibd");
+        buf.extend_from_slice(&[0x85, 0x06, 0x00, b'x', 0xff, b'y']); // FILE_CREATE 6
+        let crc = crc32c::crc32c(&buf);
+        buf.push(0x01); // termination marker
+        buf.extend_from_slice(&crc.to_be_bytes());
+
+        let chain = MtrChain::parse_next(&mut RingReader::new(buf.as_slice())).unwrap();
+        chain.validate_lengths().unwrap();
+
+        assert_eq!(chain.mtr[0].op, MtrOperation::FileRename);
+        assert_eq!(chain.mtr[0].file_name.as_deref(), Some("a.ibd"));
+        assert_eq!(chain.mtr[0].new_file_name.as_deref(), Some("b.ibd"));
+
+        assert_eq!(chain.mtr[1].op, MtrOperation::FileCreate);
+        assert_eq!(chain.mtr[1].space_id, 6);
+        assert_eq!(chain.mtr[1].file_name.as_deref(), Some("x\u{fffd}y"));
+        assert_eq!(chain.mtr[1].new_file_name, None);
+    }
+
+    #[test]
+    fn test_export_records() {
+        let buf = [
+            // MTR Chain count=2: WRITE 3:45 at offset 0x26 and a same page MEMSET.
+            0x35, 0x03, 0x2d, 0x26, 0xaa, 0xbb, // WRITE
+            0xc3, 0x10, 0x04, 0xcc, // MEMSET
+            0x01, // termination marker
+            0xff, 0xff, 0xff, 0xff, // checksum
+        ];
+        let mut buf = buf.to_vec();
+        let crc = crc32c::crc32c(&buf[..10]);
+        buf[11..15].copy_from_slice(&crc.to_be_bytes());
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        assert_eq!(chain.mtr.len(), 2);
+        chain.validate_lengths().unwrap();
+
+        let mut stream = vec![];
+        for mtr in &chain.mtr {
+            ExportedRecord::from(mtr).write_to(&mut stream).unwrap();
+        }
+
+        let mut r = stream.as_slice();
+        let mut records = vec![];
+        while let Some(record) = ExportedRecord::read_from(&mut r).unwrap() {
+            records.push(record);
+        }
+
+        assert_eq!(
+            records,
+            vec![
+                ExportedRecord {
+                    space_id: 3,
+                    page_no: 45,
+                    op: MtrOperation::Write,
+                    payload: vec![0x26, 0xaa, 0xbb],
+                },
+                ExportedRecord {
+                    space_id: 3,
+                    page_no: 45,
+                    op: MtrOperation::Memset,
+                    payload: vec![0x10, 0x04, 0xcc],
+                },
+            ]
+        );
+
+        // truncated second frame.
+        let mut r = &stream[..stream.len() - 1];
+        assert!(ExportedRecord::read_from(&mut r).unwrap().is_some());
+        assert!(ExportedRecord::read_from(&mut r).is_err());
+    }
+
+    #[test]
+    fn test_validate_lengths() {
+        let mut buf = Vec::new();
+        Mtr::build_file_checkpoint(&mut buf, 0, 0xffff, 0xde3d).unwrap();
+
+        let mut r0 = RingReader::new(buf.as_slice());
+        let chain = MtrChain::parse_next(&mut r0).unwrap();
+        chain.validate_lengths().unwrap();
+
+        let mut short = chain.clone();
+        short.mtr[0].len -= 1;
+        assert!(short.validate_lengths().is_err());
+
+        let mut gap = chain.clone();
+        gap.mtr[0].lsn += 1;
+        assert!(gap.validate_lengths().is_err());
+
+        let mut skipped = chain;
+        skipped.mtr.clear();
+        assert!(skipped.validate_lengths().is_err());
     }
 
     // Case when we didn't deduct 8 bytes from rlen after reading the file checkpoint LSN.
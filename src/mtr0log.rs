@@ -117,9 +117,26 @@ pub fn mlog_encode_varint(mut w: impl Write, mut i: u32) -> Result<()> {
     w.write_u8(i as u8)
 }
 
+/// Decode the signed source offset of a MEMMOVE record, relative to the target offset.
+/// +x is encoded as (x-1)<<1 and -x as (x-1)<<1|1.
+pub fn mlog_decode_memmove_src(v: u32) -> i32 {
+    let x = (v >> 1) as i32 + 1;
+    if v & 1 != 0 { -x } else { x }
+}
+
+/// Encode the signed nonzero source offset of a MEMMOVE record.
+/// @see mlog_decode_memmove_src()
+pub fn mlog_encode_memmove_src(x: i32) -> u32 {
+    assert!(x != 0);
+    let v = (x.unsigned_abs() - 1) << 1;
+    if x < 0 { v | 1 } else { v }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{mlog_decode_varint, mlog_encode_varint};
+    use super::{
+        mlog_decode_memmove_src, mlog_decode_varint, mlog_encode_memmove_src, mlog_encode_varint,
+    };
 
     #[test]
     fn test_varint() {
@@ -134,4 +151,12 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_memmove_src() {
+        for (x, v) in [(1, 0), (-1, 1), (2, 2), (-2, 3), (100, 198), (-100, 199)] {
+            assert_eq!(mlog_encode_memmove_src(x), v);
+            assert_eq!(mlog_decode_memmove_src(v), x);
+        }
+    }
 }
@@ -43,6 +43,103 @@ pub enum mrec_type_t {
     OPTION = 0x70,
 }
 
+/// Subtypes of EXTENDED records, encoded in the byte following the page identifier.
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum mrec_ext_t {
+    /** Partly initialize a ROW_FORMAT=REDUNDANT B-tree or R-tree index page,
+    including writing the "infimum" and "supremum" pseudo-records.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    INIT_ROW_FORMAT_REDUNDANT = 0,
+    /** Partly initialize a ROW_FORMAT=COMPACT or DYNAMIC index page,
+    including writing the "infimum" and "supremum" pseudo-records.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    INIT_ROW_FORMAT_DYNAMIC = 1,
+    /** Initialize an undo log page.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    UNDO_INIT = 2,
+    /** Append a record to an undo log page.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    UNDO_APPEND = 3,
+    /** Insert a ROW_FORMAT=REDUNDANT record, extending PAGE_HEAP_TOP.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    INSERT_HEAP_REDUNDANT = 4,
+    /** Insert a ROW_FORMAT=REDUNDANT record, reusing PAGE_FREE.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    INSERT_REUSE_REDUNDANT = 5,
+    /** Insert a ROW_FORMAT=COMPACT or DYNAMIC record, extending PAGE_HEAP_TOP.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    INSERT_HEAP_DYNAMIC = 6,
+    /** Insert a ROW_FORMAT=COMPACT or DYNAMIC record, reusing PAGE_FREE.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    INSERT_REUSE_DYNAMIC = 7,
+    /** Delete a record on a ROW_FORMAT=REDUNDANT page.
+    We point to the predecessor of the record to be deleted.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    DELETE_ROW_FORMAT_REDUNDANT = 8,
+    /** Delete a record on a ROW_FORMAT=COMPACT or DYNAMIC page.
+    We point to the predecessor of the record to be deleted
+    and include the total size of the record being deleted.
+    The current byte offset will be reset to FIL_PAGE_TYPE. */
+    DELETE_ROW_FORMAT_DYNAMIC = 9,
+    /** Truncate a data file. */
+    TRIM_PAGES = 10,
+}
+
+impl TryFrom<u8> for mrec_ext_t {
+    type Error = std::io::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use mrec_ext_t::*;
+
+        Ok(match value {
+            0 => INIT_ROW_FORMAT_REDUNDANT,
+            1 => INIT_ROW_FORMAT_DYNAMIC,
+            2 => UNDO_INIT,
+            3 => UNDO_APPEND,
+            4 => INSERT_HEAP_REDUNDANT,
+            5 => INSERT_REUSE_REDUNDANT,
+            6 => INSERT_HEAP_DYNAMIC,
+            7 => INSERT_REUSE_DYNAMIC,
+            8 => DELETE_ROW_FORMAT_REDUNDANT,
+            9 => DELETE_ROW_FORMAT_DYNAMIC,
+            10 => TRIM_PAGES,
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Invalid extended record subtype {value}"),
+                ));
+            }
+        })
+    }
+}
+
+/// Subtypes of OPTION records, encoded in the byte following the page identifier.
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum mrec_opt {
+    /** page checksum at the end of the mini-transaction */
+    OPT_PAGE_CHECKSUM = 0,
+}
+
+impl TryFrom<u8> for mrec_opt {
+    type Error = std::io::Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(mrec_opt::OPT_PAGE_CHECKSUM),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Invalid option record subtype {value}"),
+            )),
+        }
+    }
+}
+
 /// Redo log record types for file-level operations. These bit
 /// patterns will be written to redo log files, so the existing codes or
 /// their interpretation on crash recovery must not be changed.
@@ -67,6 +164,7 @@ pub enum mfile_type_t {
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum MtrOperation {
     FreePage = mrec_type_t::FREE_PAGE as u8,
     InitPage = mrec_type_t::INIT_PAGE as u8,
@@ -83,6 +181,21 @@ pub enum MtrOperation {
     FileCheckpoint = mfile_type_t::FILE_CHECKPOINT as u8,
 }
 
+impl MtrOperation {
+    /// Returns whether the record is a FILE_ record about a whole tablespace file rather
+    /// than a change of a page.
+    pub fn is_file_op(self) -> bool {
+        matches!(
+            self,
+            MtrOperation::FileCreate
+                | MtrOperation::FileDelete
+                | MtrOperation::FileRename
+                | MtrOperation::FileModify
+                | MtrOperation::FileCheckpoint
+        )
+    }
+}
+
 impl TryFrom<u8> for MtrOperation {
     type Error = std::io::Error;
 
@@ -1,5 +1,103 @@
 use crate::{fil0fil, mach, univ, ut0byte};
 
+// Index page header
+// -------------------------------------------------------------
+
+/// Index page header starts at the first offset left free by the FIL-module
+pub const PAGE_HEADER: u32 = fil0fil::FIL_PAGE_DATA;
+
+/// number of slots in page directory
+pub const PAGE_N_DIR_SLOTS: u32 = 0;
+/// pointer to record heap top
+pub const PAGE_HEAP_TOP: u32 = 2;
+/// number of records in the heap, bit 15=flag: new-style compact page format
+pub const PAGE_N_HEAP: u32 = 4;
+/// pointer to start of page free record list
+pub const PAGE_FREE: u32 = 6;
+/// number of bytes in deleted records
+pub const PAGE_GARBAGE: u32 = 8;
+/// pointer to the last inserted record, or 0 if this info has been reset by a delete
+pub const PAGE_LAST_INSERT: u32 = 10;
+/// last insert direction: PAGE_LEFT, ...; the most significant bits of PAGE_DIRECTION
+/// are used for instant ALTER TABLE
+pub const PAGE_DIRECTION_B: u32 = 13;
+/// number of consecutive inserts to the same direction
+pub const PAGE_N_DIRECTION: u32 = 14;
+/// number of user records on the page
+pub const PAGE_N_RECS: u32 = 16;
+/// highest id of a trx which may have modified a record on the page; trx_id_t;
+/// defined only in secondary indexes and in the insert buffer tree
+pub const PAGE_MAX_TRX_ID: u32 = 18;
+/// level of the node in an index tree; the leaf level is the level 0
+pub const PAGE_LEVEL: u32 = 26;
+/// index id where the page belongs
+pub const PAGE_INDEX_ID: u32 = 28;
+/// file segment header for the leaf pages in a B-tree: defined only on the root page of
+/// a B-tree, but not in the root of an ibuf tree
+pub const PAGE_BTR_SEG_LEAF: u32 = 36;
+/// in the ibuf tree root page, the base of the list of free pages
+pub const PAGE_BTR_IBUF_FREE_LIST: u32 = PAGE_BTR_SEG_LEAF;
+/// in a page which is in the ibuf free list, the list node
+pub const PAGE_BTR_IBUF_FREE_LIST_NODE: u32 = PAGE_BTR_SEG_LEAF;
+
+/// Size of the index page header up to and including PAGE_INDEX_ID.
+pub const PAGE_HEADER_SIZE: u32 = PAGE_INDEX_ID + 8;
+
+/// Flag of the compact page format in PAGE_N_HEAP.
+pub const PAGE_N_HEAP_COMPACT: u16 = 0x8000;
+
+/// Index page header of FIL_PAGE_INDEX and FIL_PAGE_RTREE pages.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct page_header_t {
+    pub n_dir_slots: u16,
+    pub heap_top: u16,
+    /// Number of records in the heap, without the compact flag.
+    pub n_heap: u16,
+    /// Whether the page is in the compact (ROW_FORMAT!=REDUNDANT) format.
+    pub compact: bool,
+    pub free: u16,
+    pub garbage: u16,
+    pub last_insert: u16,
+    pub direction: u8,
+    pub n_direction: u16,
+    pub n_recs: u16,
+    pub max_trx_id: u64,
+    pub level: u16,
+    pub index_id: u64,
+}
+
+impl page_header_t {
+    pub fn from_page(page: &[u8]) -> page_header_t {
+        page_header_t::from_buf(&page[PAGE_HEADER as usize..])
+    }
+
+    /// Reads an index page header from the given buffer.
+    /// The buffer must be at least `PAGE_HEADER_SIZE` bytes long.
+    pub fn from_buf(buf: &[u8]) -> page_header_t {
+        assert!(buf.len() >= PAGE_HEADER_SIZE as usize);
+
+        let n_heap = mach::mach_read_from_2(&buf[PAGE_N_HEAP as usize..]);
+
+        page_header_t {
+            n_dir_slots: mach::mach_read_from_2(&buf[PAGE_N_DIR_SLOTS as usize..]),
+            heap_top: mach::mach_read_from_2(&buf[PAGE_HEAP_TOP as usize..]),
+            n_heap: n_heap & !PAGE_N_HEAP_COMPACT,
+            compact: n_heap & PAGE_N_HEAP_COMPACT != 0,
+            free: mach::mach_read_from_2(&buf[PAGE_FREE as usize..]),
+            garbage: mach::mach_read_from_2(&buf[PAGE_GARBAGE as usize..]),
+            last_insert: mach::mach_read_from_2(&buf[PAGE_LAST_INSERT as usize..]),
+            direction: buf[PAGE_DIRECTION_B as usize],
+            n_direction: mach::mach_read_from_2(&buf[PAGE_N_DIRECTION as usize..]),
+            n_recs: mach::mach_read_from_2(&buf[PAGE_N_RECS as usize..]),
+            max_trx_id: mach::mach_read_from_8(&buf[PAGE_MAX_TRX_ID as usize..]),
+            level: mach::mach_read_from_2(&buf[PAGE_LEVEL as usize..]),
+            index_id: mach::mach_read_from_8(&buf[PAGE_INDEX_ID as usize..]),
+        }
+    }
+}
+
 /// Get the start of a page frame.
 ///
 /// # Arguments
@@ -41,3 +139,34 @@ pub fn page_get_page_no(buf: &[u8], ptr: usize, page_size: usize) -> u32 {
     debug_assert!(ptr == page_align(ptr, page_size));
     mach::mach_read_from_4(&buf[ptr + fil0fil::FIL_PAGE_OFFSET as usize..])
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_buf::{PageBuf, make_page_footer, make_page_header};
+
+    #[test]
+    fn test_page_header() {
+        let page_size = 16384;
+        let mut buf = vec![0u8; page_size];
+        make_page_header(&mut buf, 5, 4, fil0fil::FIL_PAGE_INDEX, 100, 0x15).unwrap();
+
+        let hdr = PAGE_HEADER as usize;
+        mach::mach_write_to_2(&mut buf[hdr + PAGE_N_DIR_SLOTS as usize..], 2).unwrap();
+        mach::mach_write_to_2(&mut buf[hdr + PAGE_N_HEAP as usize..], 0x8004).unwrap();
+        mach::mach_write_to_2(&mut buf[hdr + PAGE_N_RECS as usize..], 2).unwrap();
+        mach::mach_write_to_2(&mut buf[hdr + PAGE_LEVEL as usize..], 1).unwrap();
+        mach::mach_write_to_8(&mut buf[hdr + PAGE_INDEX_ID as usize..], 42).unwrap();
+        make_page_footer(&mut buf).unwrap();
+
+        let page = PageBuf::new(0x15, &buf);
+        let header = page_header_t::from_page(&page);
+
+        assert_eq!(header.n_dir_slots, 2);
+        assert_eq!(header.n_heap, 4);
+        assert!(header.compact);
+        assert_eq!(header.n_recs, 2);
+        assert_eq!(header.level, 1);
+        assert_eq!(header.index_id, 42);
+    }
+}
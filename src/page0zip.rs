@@ -0,0 +1,109 @@
+use crc32c::crc32c;
+
+use crate::{buf0buf::BUF_NO_CHECKSUM_MAGIC, fil0fil, mach};
+
+// Compressed page checksums
+// -------------------------------------------------------------
+
+/// Calculates the checksum of a ROW_FORMAT=COMPRESSED page. FIL_PAGE_SPACE_OR_CHKSUM,
+/// FIL_PAGE_LSN and FIL_PAGE_FILE_FLUSH_LSN are excluded from the checksum.
+/// Reference: page0zip.cc:page_zip_calc_checksum().
+pub fn page_zip_calc_checksum(data: &[u8], use_adler: bool) -> u32 {
+    assert!(data.len() > fil0fil::FIL_PAGE_ARCH_LOG_NO_OR_SPACE_ID as usize);
+
+    let parts = [
+        &data[fil0fil::FIL_PAGE_OFFSET as usize..fil0fil::FIL_PAGE_LSN as usize],
+        &data[fil0fil::FIL_PAGE_TYPE as usize..fil0fil::FIL_PAGE_TYPE as usize + 2],
+        &data[fil0fil::FIL_PAGE_ARCH_LOG_NO_OR_SPACE_ID as usize..],
+    ];
+
+    if use_adler {
+        parts.iter().fold(0, |adler, part| adler32(adler, part))
+    } else {
+        parts.iter().fold(0, |crc, part| crc ^ crc32c(part))
+    }
+}
+
+/// Verifies the checksum of a ROW_FORMAT=COMPRESSED page. An empty page is valid. Like
+/// any non-strict innodb_checksum_algorithm, the innodb (adler32) and none checksums are
+/// accepted as well.
+/// Reference: page0zip.cc:page_zip_verify_checksum().
+pub fn page_zip_verify_checksum(data: &[u8]) -> bool {
+    let stored = mach::mach_read_from_4(&data[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..]);
+
+    // Check if page is empty.
+    if stored == 0 && mach::mach_read_from_8(&data[fil0fil::FIL_PAGE_LSN as usize..]) == 0 {
+        // make sure that the page is really empty.
+        return data.iter().all(|&b| b == 0);
+    }
+
+    if stored == page_zip_calc_checksum(data, false) {
+        return true;
+    }
+
+    stored == BUF_NO_CHECKSUM_MAGIC || stored == page_zip_calc_checksum(data, true)
+}
+
+/// zlib adler32() continuing from the checksum `adler`.
+fn adler32(adler: u32, data: &[u8]) -> u32 {
+    const BASE: u32 = 65521;
+
+    let (mut a, mut b) = (adler & 0xffff, adler >> 16);
+    for &byte in data {
+        a = (a + byte as u32) % BASE;
+        b = (b + a) % BASE;
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(1, b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn test_page_zip_verify_checksum() {
+        let zip_size = 8192;
+        let mut page = vec![0u8; zip_size];
+        assert!(page_zip_verify_checksum(&page));
+
+        for (i, b) in page.iter_mut().enumerate().skip(4) {
+            *b = (i % 253) as u8;
+        }
+
+        let crc32 = page_zip_calc_checksum(&page, false);
+        mach::mach_write_to_4(
+            &mut page[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..],
+            crc32,
+        )
+        .unwrap();
+        assert!(page_zip_verify_checksum(&page));
+
+        // FIL_PAGE_LSN is not covered by the checksum.
+        page[fil0fil::FIL_PAGE_LSN as usize] ^= 1;
+        assert!(page_zip_verify_checksum(&page));
+
+        page[1000] ^= 1;
+        assert!(!page_zip_verify_checksum(&page));
+
+        let adler = page_zip_calc_checksum(&page, true);
+        mach::mach_write_to_4(
+            &mut page[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..],
+            adler,
+        )
+        .unwrap();
+        assert!(page_zip_verify_checksum(&page));
+
+        mach::mach_write_to_4(
+            &mut page[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..],
+            BUF_NO_CHECKSUM_MAGIC,
+        )
+        .unwrap();
+        assert!(page_zip_verify_checksum(&page));
+    }
+}
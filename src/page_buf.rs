@@ -1,15 +1,19 @@
 use std::{
     fmt::{Debug, Display},
-    io::{Read, Result},
+    io::Result,
     ops::{Index, RangeFrom, RangeTo},
 };
 
 use crc32c::crc32c;
 
-use crate::{Lsn, buf0buf, fil0fil, fsp0types, fut0lst, mach, trx0undo};
+use crate::{
+    Lsn, buf0buf, fil0fil, fsp0fsp, fsp0types, fut0lst, mach, page0page, trx0rseg, trx0sys,
+    trx0undo,
+};
 
 // TODO: support for compression and encryption
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PageBuf<'a> {
     pub space_id: u32,
     pub page_no: u32,
@@ -33,8 +37,10 @@ pub struct PageBuf<'a> {
     pub foot_lsn: u32,
 
     // tablespace flags
+    #[cfg_attr(feature = "serde", serde(skip))]
     flags: u32,
 
+    #[cfg_attr(feature = "serde", serde(skip))]
     buf: &'a [u8],
 }
 
@@ -102,10 +108,145 @@ impl<'a> PageBuf<'a> {
         self.buf.len()
     }
 
+    /// Returns whether the page belongs to a ROW_FORMAT=COMPRESSED tablespace, i.e. is
+    /// stored in the compressed page size.
+    pub fn is_compressed(&self) -> bool {
+        fil0fil::zip_size(self.flags) != 0
+    }
+
+    /// Returns whether the page contents are encrypted, judging by the key version
+    /// stored in the page header. Page 0 is never encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        if self.page_no == 0 {
+            return false;
+        }
+
+        if self.page_type == fil0fil::FIL_PAGE_PAGE_COMPRESSED_ENCRYPTED {
+            return true;
+        }
+
+        if fil0fil::full_crc32(self.flags) {
+            self.read_4(fil0fil::FIL_PAGE_FCRC32_KEY_VERSION as usize) != 0
+        } else if self.page_type == fil0fil::FIL_PAGE_PAGE_COMPRESSED {
+            // the field holds FIL_PAGE_COMP_ALGO.
+            false
+        } else {
+            self.read_4(fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize) != 0
+        }
+    }
+
+    /// Returns the version of the key the page is encrypted with, or None if the page is
+    /// not encrypted.
+    pub fn key_version(&self) -> Option<u32> {
+        if !self.is_encrypted() {
+            return None;
+        }
+
+        if fil0fil::full_crc32(self.flags) {
+            Some(self.read_4(fil0fil::FIL_PAGE_FCRC32_KEY_VERSION as usize))
+        } else {
+            Some(self.read_4(fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize))
+        }
+    }
+
     pub fn corrupted(&self, check_lsn: Option<Lsn>) -> Result<()> {
         buf0buf::buf_page_is_corrupted(self, check_lsn)
     }
 
+    /// Returns whether the page is a rollback segment header page. The system tablespace
+    /// also uses FIL_PAGE_TYPE_SYS for the ibuf header and the dictionary header pages.
+    pub fn is_rseg_header(&self) -> bool {
+        self.page_type == fil0fil::FIL_PAGE_TYPE_SYS
+            && !(self.space_id == 0
+                && (self.page_no == fsp0types::FSP_IBUF_HEADER_PAGE_NO
+                    || self.page_no == fsp0types::FSP_DICT_HDR_PAGE_NO))
+    }
+
+    /// Decodes the structure stored in the page body by the page type: the FSP header,
+    /// the TRX_SYS header, the rollback segment header, the undo log page header, the index
+    /// page header or the extent descriptors. Other page types are `DecodedPage::Other`.
+    pub fn decode(&self) -> DecodedPage {
+        match self.page_type {
+            fil0fil::FIL_PAGE_TYPE_FSP_HDR => {
+                DecodedPage::FspHeader(fsp0fsp::fsp_header_t::from_page(self.buf))
+            }
+            fil0fil::FIL_PAGE_TYPE_TRX_SYS => {
+                DecodedPage::TrxSys(trx0sys::trx_sys_t::from_page(self.buf))
+            }
+            fil0fil::FIL_PAGE_TYPE_SYS if self.is_rseg_header() => {
+                DecodedPage::Rseg(trx0rseg::trx_rseg_t::from_page(self.buf))
+            }
+            fil0fil::FIL_PAGE_UNDO_LOG => {
+                DecodedPage::UndoPage(trx0undo::trx_undo_page_t::from_page(self.buf))
+            }
+            fil0fil::FIL_PAGE_INDEX | fil0fil::FIL_PAGE_RTREE => {
+                DecodedPage::Index(page0page::page_header_t::from_page(self.buf))
+            }
+            fil0fil::FIL_PAGE_TYPE_XDES => DecodedPage::Xdes(fsp0fsp::xdes_page_entries(self.buf)),
+            page_type => DecodedPage::Other { page_type },
+        }
+    }
+
+    /// Returns the page header fields together with the decoded page body.
+    pub fn dump(&self) -> PageDump {
+        PageDump {
+            space_id: self.space_id,
+            page_no: self.page_no,
+            prev_page: (self.prev_page != FIL_NULL).then_some(self.prev_page),
+            next_page: (self.next_page != FIL_NULL).then_some(self.next_page),
+            page_lsn: self.page_lsn,
+            page_type: format!("{:?}", fil0fil::fil_page_type_t::from(self.page_type)),
+            checksum: self.foot_checksum,
+            body: self.decode(),
+        }
+    }
+
+    /// Returns the file segment header embedded into the page, if the page type
+    /// is known to carry one:
+    /// - FIL_PAGE_TYPE_TRX_SYS: the TRX_SYS segment header,
+    /// - FIL_PAGE_TYPE_SYS: the rollback segment header, see `is_rseg_header()`,
+    /// - FIL_PAGE_UNDO_LOG: the undo log segment header on the first page of the segment.
+    pub fn fseg_header(&self) -> Option<fsp0types::fseg_header_t> {
+        match self.page_type {
+            fil0fil::FIL_PAGE_TYPE_TRX_SYS => {
+                Some(trx0sys::trx_sys_t::from_page(self.buf).fseg_header)
+            }
+            fil0fil::FIL_PAGE_TYPE_SYS if self.is_rseg_header() => {
+                Some(trx0rseg::trx_rseg_t::from_page(self.buf).fseg_header)
+            }
+            fil0fil::FIL_PAGE_UNDO_LOG => {
+                let undo_page = trx0undo::trx_undo_page_t::from_page(self.buf);
+                if !undo_page.node.prev.is_empty() {
+                    return None;
+                }
+
+                let offset = (trx0undo::TRX_UNDO_SEG_HDR + trx0undo::TRX_UNDO_FSEG_HEADER) as usize;
+                Some(fsp0types::fseg_header_t::from_buf(&self.buf[offset..]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a one-line summary of the page header.
+    pub fn summary(&self) -> String {
+        let link = |page_no: u32| {
+            if page_no == FIL_NULL {
+                "-".to_string()
+            } else {
+                page_no.to_string()
+            }
+        };
+
+        format!(
+            "page {}: type={:?}, prev={}, next={}, lsn={}",
+            self.page_no,
+            fil0fil::fil_page_type_t::from(self.page_type),
+            link(self.prev_page),
+            link(self.next_page),
+            self.page_lsn
+        )
+    }
+
     pub fn read_4(&self, offset: usize) -> u32 {
         mach::mach_read_from_4(&self.buf[offset..])
     }
@@ -192,6 +333,33 @@ impl Display for PageBuf<'_> {
     }
 }
 
+/// Structure decoded from the page body. See `PageBuf::decode()`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DecodedPage {
+    FspHeader(fsp0fsp::fsp_header_t),
+    TrxSys(trx0sys::trx_sys_t),
+    Rseg(trx0rseg::trx_rseg_t),
+    UndoPage(trx0undo::trx_undo_page_t),
+    Index(page0page::page_header_t),
+    Xdes(Vec<fsp0fsp::xdes_entry_t>),
+    Other { page_type: u16 },
+}
+
+/// Page header fields with the decoded page body. See `PageBuf::dump()`.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PageDump {
+    pub space_id: u32,
+    pub page_no: u32,
+    pub prev_page: Option<u32>,
+    pub next_page: Option<u32>,
+    pub page_lsn: Lsn,
+    pub page_type: String,
+    pub checksum: u32,
+    pub body: DecodedPage,
+}
+
 pub fn make_undo_log_page(
     page: &mut [u8],
     space_id: u32,
@@ -199,21 +367,7 @@ pub fn make_undo_log_page(
     page_lsn: Lsn,
     flags: u32,
 ) -> Result<()> {
-    assert!(fil0fil::full_crc32(flags));
-    assert!(fsp0types::FSP_FLAGS_GET_POST_ANTELOPE(flags) != 0);
-    assert!(fsp0types::FSP_FLAGS_HAS_PAGE_COMPRESSION(flags) == 0);
-
-    if flags != 0x15 {
-        // only support general tablespace without encryption and compression.
-        // just to be sure we didn't miss anything.
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            format!("Unsupported tablespace flags: {:#x}", flags),
-        ));
-    }
-
-    let page_size = fil0fil::logical_size(flags);
-    assert_eq!(page.len(), page_size);
+    check_page_flags(flags, page.len())?;
 
     page.fill(0);
 
@@ -231,6 +385,31 @@ pub fn make_undo_log_page(
     Ok(())
 }
 
+/// Checks the pages of a tablespace with the flags can be built: only full_crc32
+/// tablespaces without page compression are supported, and `page_size` must match the
+/// logical page size of the flags.
+fn check_page_flags(flags: u32, page_size: usize) -> Result<()> {
+    if !fil0fil::full_crc32(flags) || fil0fil::is_full_crc32_compressed(flags) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Unsupported tablespace flags: {:#x}", flags),
+        ));
+    }
+
+    let logical_size = fil0fil::logical_size(flags);
+    if logical_size != page_size {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Page size {page_size} does not match the page size {logical_size} of the \
+                 tablespace flags {flags:#x}"
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 // TODO: write trait
 pub fn make_page_header(
     buf: &mut [u8],
@@ -240,7 +419,7 @@ pub fn make_page_header(
     page_lsn: Lsn,
     flags: u32,
 ) -> Result<()> {
-    assert_eq!(flags, 0x15);
+    check_page_flags(flags, buf.len())?;
 
     mach::mach_write_to_4(&mut buf[fil0fil::FIL_PAGE_SPACE_OR_CHKSUM as usize..], 0)?; // 0
     mach::mach_write_to_4(&mut buf[fil0fil::FIL_PAGE_OFFSET as usize..], page_no)?; // 4
@@ -274,11 +453,10 @@ pub fn make_undo_log_page_header(buf: &mut [u8]) -> Result<()> {
         trx0undo::TRX_UNDO_PAGE_HDR as u16 + trx0undo::TRX_UNDO_PAGE_HDR_SIZE as u16,
     )?; // 2, 38 + 6 + 2 * 6 = 56
 
-    let mut empty_page_node = fut0lst::flst_node_t::default();
-    empty_page_node.read_exact(
-        &mut buf[trx0undo::TRX_UNDO_PAGE_NODE as usize
-            ..trx0undo::TRX_UNDO_PAGE_NODE as usize + fut0lst::FLST_NODE_SIZE as usize],
-    )?;
+    // empty page node: prev and next are null.
+    let node = trx0undo::TRX_UNDO_PAGE_NODE as usize;
+    fil0fil::fil_addr_t::null().write_to(&mut buf[node + fut0lst::FLST_PREV as usize..])?;
+    fil0fil::fil_addr_t::null().write_to(&mut buf[node + fut0lst::FLST_NEXT as usize..])?;
 
     Ok(())
 }
@@ -302,8 +480,9 @@ pub fn make_page_footer(page_buf: &mut [u8]) -> Result<()> {
 
 #[cfg(test)]
 mod test {
-    use super::PageBuf;
-    use crate::fil0fil;
+    use super::{DecodedPage, PageBuf};
+    use crate::tablespace::TablespaceReader;
+    use crate::{fil0fil, fsp0types, mach, trx0sys};
 
     #[test]
     pub fn make_undo_log_page_test() {
@@ -328,4 +507,163 @@ mod test {
 
         page.corrupted(Some(789)).unwrap();
     }
+
+    #[test]
+    pub fn make_undo_log_page_large_test() {
+        // full_crc32 flags of 32K and 64K tablespaces.
+        for (flags, page_size) in [(0x16u32, 32 * 1024), (0x17, 64 * 1024)] {
+            let mut page = vec![0xffu8; page_size];
+
+            super::make_undo_log_page(&mut page, 1, 50, 789, flags).unwrap();
+
+            let page = PageBuf::new(flags, &page);
+            assert_eq!(page.page_size(), page_size);
+            assert_eq!(page.page_no, 50);
+            assert_eq!(page.page_type, fil0fil::FIL_PAGE_UNDO_LOG);
+            assert_eq!(page.foot_lsn, 789);
+            page.corrupted(Some(789)).unwrap();
+        }
+
+        // the page buffer must match the page size of the flags.
+        let mut page = vec![0u8; 16 * 1024];
+        assert!(super::make_undo_log_page(&mut page, 1, 50, 789, 0x16).is_err());
+        // page compression and the pre-full_crc32 formats are not supported.
+        let mut page = vec![0u8; 32 * 1024];
+        assert!(super::make_undo_log_page(&mut page, 1, 50, 789, 0x36).is_err());
+        assert!(super::make_undo_log_page(&mut page, 1, 50, 789, 0x61).is_err());
+    }
+
+    #[test]
+    pub fn fseg_header_deref_test() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size * 4];
+
+        // TRX_SYS page pointing to an inode on page 2.
+        let trx_sys = &mut buf[page_size * 3..page_size * 4];
+        mach::mach_write_to_4(&mut trx_sys[fil0fil::FIL_PAGE_OFFSET as usize..], 3).unwrap();
+        mach::mach_write_to_2(
+            &mut trx_sys[fil0fil::FIL_PAGE_TYPE as usize..],
+            fil0fil::FIL_PAGE_TYPE_TRX_SYS,
+        )
+        .unwrap();
+        let fseg = (trx0sys::TRX_SYS + trx0sys::TRX_SYS_FSEG_HEADER) as usize;
+        mach::mach_write_to_4(&mut trx_sys[fseg + fsp0types::FSEG_HDR_SPACE as usize..], 7)
+            .unwrap();
+        mach::mach_write_to_4(
+            &mut trx_sys[fseg + fsp0types::FSEG_HDR_PAGE_NO as usize..],
+            2,
+        )
+        .unwrap();
+        mach::mach_write_to_2(
+            &mut trx_sys[fseg + fsp0types::FSEG_HDR_OFFSET as usize..],
+            50,
+        )
+        .unwrap();
+
+        buf[page_size * 2 + 50..page_size * 2 + 54].copy_from_slice(b"node");
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let page = reader.page(3).unwrap();
+        let header = page.fseg_header().unwrap();
+        assert_eq!(header.page_no, 2);
+        assert_eq!(header.offset, 50);
+
+        let (space, addr) = header.as_fil_addr();
+        assert_eq!(space, 7);
+        assert_eq!(reader.deref_addr(&addr).unwrap()[..4], *b"node");
+        assert_eq!(reader.deref_addr(&addr).unwrap().len(), page_size - 50);
+
+        assert!(reader.deref_addr(&fil0fil::fil_addr_t::default()).is_err());
+        assert!(reader.page(2).unwrap().fseg_header().is_none());
+    }
+
+    #[test]
+    pub fn sys_page_decode_test() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size * 8];
+        for page_no in [3, 6, 7] {
+            let page = &mut buf[page_size * page_no..page_size * (page_no + 1)];
+            mach::mach_write_to_4(
+                &mut page[fil0fil::FIL_PAGE_OFFSET as usize..],
+                page_no as u32,
+            )
+            .unwrap();
+            mach::mach_write_to_2(
+                &mut page[fil0fil::FIL_PAGE_TYPE as usize..],
+                fil0fil::FIL_PAGE_TYPE_SYS,
+            )
+            .unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+
+        // the ibuf header and the dictionary header pages are not rollback segments.
+        for page_no in [
+            fsp0types::FSP_IBUF_HEADER_PAGE_NO,
+            fsp0types::FSP_DICT_HDR_PAGE_NO,
+        ] {
+            let page = reader.page(page_no).unwrap();
+            assert!(!page.is_rseg_header());
+            assert!(matches!(
+                page.decode(),
+                DecodedPage::Other {
+                    page_type: fil0fil::FIL_PAGE_TYPE_SYS
+                }
+            ));
+        }
+
+        assert!(
+            reader
+                .page(fsp0types::FSP_IBUF_HEADER_PAGE_NO)
+                .unwrap()
+                .fseg_header()
+                .is_none()
+        );
+
+        let page = reader.page(fsp0types::FSP_FIRST_RSEG_PAGE_NO).unwrap();
+        assert!(page.is_rseg_header());
+        assert!(page.fseg_header().is_some());
+        assert!(matches!(page.decode(), DecodedPage::Rseg(_)));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    pub fn trx_sys_page_yaml_test() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size];
+
+        super::make_page_header(
+            &mut buf,
+            0,
+            fsp0types::FSP_TRX_SYS_PAGE_NO,
+            fil0fil::FIL_PAGE_TYPE_TRX_SYS,
+            1234,
+            0x15,
+        )
+        .unwrap();
+        let trx_sys = trx0sys::TRX_SYS as usize;
+        mach::mach_write_to_8(
+            &mut buf[trx_sys + trx0sys::TRX_SYS_TRX_ID_STORE as usize..],
+            42,
+        )
+        .unwrap();
+        let xid = trx0sys::TRX_SYS_WSREP_XID_INFO(page_size) as usize;
+        mach::mach_write_to_4(&mut buf[xid..], trx0sys::TRX_SYS_WSREP_XID_MAGIC_N).unwrap();
+        buf[xid + trx0sys::TRX_SYS_WSREP_XID_DATA as usize] = 0xab;
+        super::make_page_footer(&mut buf).unwrap();
+
+        let page = PageBuf::new(0x15, &buf);
+        let yaml = serde_yaml::to_string(&page.dump()).unwrap();
+
+        assert!(yaml.contains("page_no: 5\n"), "{yaml}");
+        assert!(yaml.contains("page_lsn: 1234\n"), "{yaml}");
+        assert!(yaml.contains("page_type: TrxSys\n"), "{yaml}");
+        assert!(yaml.contains("prev_page: null\n"), "{yaml}");
+        assert!(yaml.contains("body: !TrxSys\n  id_store: 42\n"), "{yaml}");
+        assert!(
+            yaml.contains(&format!("xid_data: ab{}\n", "00".repeat(127))),
+            "{yaml}"
+        );
+        assert!(yaml.contains("mysql_log: null\n"), "{yaml}");
+    }
 }
@@ -4,17 +4,21 @@ use std::{
     ops::{Add, Index},
 };
 
-use crc32c::crc32c;
+use crc32c::crc32c_append;
 use mmap_rs::MmapMut;
 
-use crate::mach;
+use crate::{lsn, mach};
 
 #[derive(Debug, Clone)]
 pub struct RingReader<'a> {
     buf: &'a [u8],
-    pos: usize,
+    /// The position in the log sequence space (LSN), not limited by the address space.
+    pos: u64,
     /// The size of the header in the beginning.
     header: usize,
+    /// The first LSN and the capacity of the log ring, if the buffer is only a window of
+    /// it. See `RingReader::window()`.
+    log: Option<(u64, u64)>,
 }
 
 impl<'a> RingReader<'a> {
@@ -24,46 +28,94 @@ impl<'a> RingReader<'a> {
 
     /// Creates a new `RingReader` at the given position in the buffer.
     /// Buffer must be at least `hdr` bytes long and includes the header.
-    pub fn buf_at(buf: &'a [u8], hdr: usize, pos: usize) -> RingReader<'a> {
+    pub fn buf_at(buf: &'a [u8], hdr: usize, pos: u64) -> RingReader<'a> {
         RingReader {
             buf,
             pos,
             header: hdr,
+            log: None,
         }
     }
 
-    /// returns the position in the header+ring_buffer for a given pos.
-    pub fn pos_to_offset(&self, pos: usize) -> usize {
-        pos_to_offset(self.header, self.buf.len() - self.header, pos)
+    /// Creates a new `RingReader` at the given position over a window of a larger log
+    /// ring of `capacity` bytes after `first_lsn`: the byte of the position `pos` is at
+    /// `buf[pos % buf.len()]`. Termination markers follow the generations of the log.
+    pub fn window(buf: &'a [u8], first_lsn: u64, capacity: u64, pos: u64) -> RingReader<'a> {
+        RingReader {
+            buf,
+            pos,
+            header: 0,
+            log: Some((first_lsn, capacity)),
+        }
     }
 
-    pub fn block(&self, mut buf: &mut [u8]) -> usize {
-        if buf.len() > self.buf.len() {
-            buf = &mut buf[..self.buf.len()];
-        }
+    /// Returns the termination marker of the generation of the ring at `pos`.
+    pub fn sequence_bit(&self, pos: u64) -> u8 {
+        let (first_lsn, capacity) = self
+            .log
+            .unwrap_or((self.header as u64, self.capacity() as u64));
 
-        let start = self.pos_to_offset(self.pos);
-        let end = self.pos_to_offset(self.pos + buf.len());
-        if start < end {
-            buf.copy_from_slice(&self.buf[start..end]);
-        } else {
-            let size1 = self.buf.len() - start;
-            buf[..size1].copy_from_slice(&self.buf[start..]);
-            buf[size1..].copy_from_slice(&self.buf[self.header..end]);
+        lsn::get_sequence_bit(first_lsn, capacity, pos)
+    }
+
+    /// returns the position in the header+ring_buffer for a given pos.
+    pub fn pos_to_offset(&self, pos: u64) -> usize {
+        pos_to_offset(self.header, self.capacity(), pos)
+    }
+
+    /// Copies up to `buf.len()` bytes starting at the current position without advancing.
+    /// Returns the number of bytes copied.
+    pub fn block(&self, buf: &mut [u8]) -> usize {
+        let want = min(buf.len(), self.buf.len());
+        let mut pos = self.pos;
+        let mut copied = 0;
+
+        while copied < want && self.capacity() > 0 {
+            let offset = self.pos_to_offset(pos);
+            let size = min(self.buf.len() - offset, want - copied);
+            buf[copied..copied + size].copy_from_slice(&self.buf[offset..offset + size]);
+
+            copied += size;
+            let Some(next) = pos.checked_add(size as u64) else {
+                break;
+            };
+            pos = next;
         }
 
-        buf.len()
+        copied
     }
 
+    /// Computes the checksum of the next `size` bytes directly over the buffer slices,
+    /// following the same wrapping as `block()`.
     pub fn crc32c(&self, size: usize) -> Result<u32> {
-        let mut buf = vec![0u8; size];
-        if self.block(&mut buf) != size {
+        if size > self.buf.len() || (size > 0 && self.capacity() == 0) {
             return Err(Error::from(ErrorKind::UnexpectedEof));
         }
-        Ok(crc32c(&buf))
+
+        let mut crc = 0;
+        let mut pos = self.pos;
+        let mut hashed = 0;
+
+        while hashed < size {
+            let offset = self.pos_to_offset(pos);
+            let len = min(self.buf.len() - offset, size - hashed);
+            crc = crc32c_append(crc, &self.buf[offset..offset + len]);
+
+            hashed += len;
+            let Some(next) = pos.checked_add(len as u64) else {
+                break;
+            };
+            pos = next;
+        }
+
+        if hashed != size {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
+        }
+
+        Ok(crc)
     }
 
-    pub fn pos(&self) -> usize {
+    pub fn pos(&self) -> u64 {
         self.pos
     }
 
@@ -72,7 +124,7 @@ impl<'a> RingReader<'a> {
     }
 
     pub fn capacity(&self) -> usize {
-        self.buf.len() - self.header
+        self.buf.len().saturating_sub(self.header)
     }
 
     pub fn len(&self) -> usize {
@@ -84,11 +136,11 @@ impl<'a> RingReader<'a> {
     }
 
     pub fn ensure(&self, t: usize) -> Result<()> {
-        if self.len() < t {
+        if self.len() < t || self.capacity() == 0 {
             return Err(Error::from(ErrorKind::UnexpectedEof));
         }
 
-        if self.pos.checked_add(t).is_none() {
+        if self.pos.checked_add(t as u64).is_none() {
             return Err(Error::from(ErrorKind::UnexpectedEof));
         }
 
@@ -96,8 +148,7 @@ impl<'a> RingReader<'a> {
     }
 
     pub fn advance(&mut self, bytes: usize) -> bool {
-        // TODO: overflowing u64 pos.
-        if let Some(new_pos) = self.pos.checked_add(bytes) {
+        if let Some(new_pos) = self.pos.checked_add(bytes as u64) {
             self.pos = new_pos;
             true
         } else {
@@ -147,23 +198,13 @@ impl<'a> RingReader<'a> {
 }
 
 impl<'a> Read for RingReader<'a> {
-    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize> {
-        let offset0 = self.pos_to_offset(self.pos);
-        let size1 = min(self.buf.len() - offset0, buf.len());
-        buf[..size1].copy_from_slice(&self.buf[offset0..offset0 + size1]);
-
-        self.pos += size1;
-        if size1 == buf.len() {
-            return Ok(size1);
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let size = self.block(buf);
+        if !self.advance(size) {
+            return Err(Error::from(ErrorKind::UnexpectedEof));
         }
 
-        buf = &mut buf[size1..];
-        let size2 = min(offset0, buf.len());
-        buf[0..size2].copy_from_slice(&self.buf[self.header..self.header + size2]);
-
-        self.pos += size2;
-
-        Ok(size1 + size2)
+        Ok(size)
     }
 }
 
@@ -198,28 +239,22 @@ impl<'a> Index<usize> for RingReader<'a> {
 
     fn index(&self, index: usize) -> &Self::Output {
         // TODO: use peek_1()
-        // TODO: overflowing u64 pos.
-        let Some(pos) = self.pos.checked_add(index) else {
-            todo!("overflowing index access in RingReader");
-        };
-        let offset = self.pos_to_offset(pos);
+        // the position space is a ring as well, so overflowing pos wraps around.
+        let offset = self.pos_to_offset(self.pos.wrapping_add(index as u64));
         &self.buf[offset]
     }
 }
 
 /// returns the position in the header+ring_buffer for a given pos.
-pub fn pos_to_offset(hdr: usize, body: usize, pos: usize) -> usize {
-    if pos < hdr {
-        return pos; // within the header
-    }
-
-    hdr + (pos - hdr) % body
+pub fn pos_to_offset(hdr: usize, body: usize, pos: u64) -> usize {
+    lsn::lsn_to_offset(hdr as u64, body as u64, pos) as usize
 }
 
 #[derive(Debug)]
 pub struct RingWriter<'a> {
     buf: &'a mut [u8],
-    pos: usize,
+    /// The position in the log sequence space (LSN), not limited by the address space.
+    pos: u64,
     /// The size of the header in the beginning.
     header: usize,
 }
@@ -231,7 +266,7 @@ impl<'a> RingWriter<'a> {
 
     /// Creates a new `RingWriter` at the given position in the buffer.
     /// Buffer must be at least `hdr` bytes long and includes the header.
-    pub fn buf_at(buf: &'a mut [u8], hdr: usize, pos: usize) -> RingWriter<'a> {
+    pub fn buf_at(buf: &'a mut [u8], hdr: usize, pos: u64) -> RingWriter<'a> {
         RingWriter {
             buf,
             pos,
@@ -240,11 +275,11 @@ impl<'a> RingWriter<'a> {
     }
 
     /// returns the position in the header+ring_buffer for a given pos.
-    pub fn pos_to_offset(&self, pos: usize) -> usize {
+    pub fn pos_to_offset(&self, pos: u64) -> usize {
         pos_to_offset(self.header, self.buf.len() - self.header, pos)
     }
 
-    pub fn pos(&self) -> usize {
+    pub fn pos(&self) -> u64 {
         self.pos
     }
 
@@ -273,41 +308,41 @@ impl<'a> RingWriter<'a> {
     }
 
     pub fn advance(&mut self, bytes: usize) {
-        self.pos += bytes;
+        self.pos += bytes as u64;
     }
 }
 
 impl<'a> Seek for RingWriter<'a> {
     fn seek(&mut self, pos: std::io::SeekFrom) -> Result<u64> {
         let new_pos = match pos {
-            std::io::SeekFrom::Start(offset) => offset as usize,
+            std::io::SeekFrom::Start(offset) => offset,
             std::io::SeekFrom::End(offset) => {
-                if offset > 0 && offset as usize > self.pos {
+                if offset > 0 && offset as u64 > self.pos {
                     return Err(Error::from(ErrorKind::InvalidInput));
                 }
 
                 if offset < 0 {
-                    self.pos + (-offset) as usize
+                    self.pos + offset.unsigned_abs()
                 } else {
-                    self.pos - offset as usize
+                    self.pos - offset as u64
                 }
             }
             std::io::SeekFrom::Current(offset) => {
-                if offset < 0 && self.pos < (-offset) as usize {
+                if offset < 0 && self.pos < offset.unsigned_abs() {
                     return Err(Error::from(ErrorKind::InvalidInput));
                 }
 
                 if offset < 0 {
-                    self.pos - (-offset) as usize
+                    self.pos - offset.unsigned_abs()
                 } else {
-                    self.pos + offset as usize
+                    self.pos + offset as u64
                 }
             }
         };
 
         self.pos = new_pos;
 
-        Ok(self.pos as u64)
+        Ok(self.pos)
     }
 }
 
@@ -317,7 +352,7 @@ impl<'a> Write for RingWriter<'a> {
         let size1 = min(self.buf.len() - offset0, buf.len());
         self.buf[offset0..offset0 + size1].copy_from_slice(&buf[..size1]);
 
-        self.pos += size1;
+        self.pos += size1 as u64;
         if size1 == buf.len() {
             return Ok(size1);
         }
@@ -325,7 +360,7 @@ impl<'a> Write for RingWriter<'a> {
         let remaining = &buf[size1..];
         let size2 = min(offset0 - self.header, remaining.len());
         self.buf[self.header..self.header + size2].copy_from_slice(&remaining[..size2]);
-        self.pos += size2;
+        self.pos += size2 as u64;
         Ok(size1 + size2)
     }
 
@@ -362,6 +397,60 @@ mod test {
 
     use super::{RingReader, RingWriter};
 
+    #[test]
+    fn test_crc32c_wrapped() {
+        let storage = (0..64u8).collect::<Vec<_>>();
+        let r = RingReader::buf_at(&storage, 16, 56);
+
+        for size in [0, 1, 8, 9, 48, 64] {
+            let mut buf = vec![0u8; size];
+            assert_eq!(r.block(&mut buf), size);
+            assert_eq!(r.crc32c(size).unwrap(), crc32c::crc32c(&buf), "size {size}");
+        }
+
+        assert!(r.crc32c(65).is_err());
+    }
+
+    #[test]
+    fn test_ring_reader_beyond_u32() {
+        let storage = (0..24u8).collect::<Vec<_>>();
+        let hdr = 8;
+        let capacity = (storage.len() - hdr) as u64;
+        // an LSN above u32::MAX that is 3 bytes before the end of the ring.
+        let pos = hdr as u64 + (u32::MAX as u64 / capacity + 1) * capacity + capacity - 3;
+        assert!(pos > u32::MAX as u64);
+
+        let mut r = RingReader::buf_at(&storage, hdr, pos);
+        assert_eq!(r.pos_to_offset(pos), 21);
+        assert_eq!(r.peek_1().unwrap(), 21);
+        assert_eq!(r[4u32], 9);
+
+        let mut buf = [0u8; 5];
+        r.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [21, 22, 23, 8, 9]);
+        assert_eq!(r.pos(), pos + 5);
+
+        let mut r = RingReader::buf_at(&storage, hdr, u64::MAX - 1);
+        assert!(r.read_1().is_ok());
+        assert!(r.read_4().is_err());
+    }
+
+    #[test]
+    fn test_ring_reader_no_capacity() {
+        let storage = [1u8, 2, 3];
+
+        for hdr in [3, 4] {
+            let mut r = RingReader::buf_at(&storage, hdr, 5);
+            assert_eq!(r.capacity(), 0);
+            assert!(r.peek_1().is_err());
+            assert!(r.read_4().is_err());
+            assert!(r.crc32c(2).is_err());
+        }
+
+        let r = RingReader::buf_at(&storage, 0, u64::MAX);
+        assert!(r.crc32c(3).is_ok());
+    }
+
     #[test]
     fn test_ring_reader() {
         let storage = [1u8, 2, 3, 4, 5];
@@ -0,0 +1,138 @@
+use std::io::{Error, ErrorKind, Read, Result};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+/// The version of the .cfg metadata file format written by FLUSH TABLES ... FOR EXPORT.
+pub const IB_EXPORT_CFG_VERSION_V1: u32 = 1;
+
+/// Maximum length of a string in the .cfg file, including the terminating NUL.
+pub const IB_EXPORT_CFG_MAX_STRING: u32 = 4096;
+
+/// Header of the .cfg metadata file of a transportable tablespace.
+/// See row_quiesce_write_header() and row_import_read_v1().
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct row_import_cfg_t {
+    /// IB_EXPORT_CFG_VERSION_V1
+    pub version: u32,
+    /// Hostname of the server the tablespace was exported from.
+    pub hostname: String,
+    /// Table name in the `database/table` form.
+    pub table_name: String,
+    /// Next autoinc value of the table.
+    pub autoinc: u64,
+    /// innodb_page_size of the exporting server.
+    pub page_size: u32,
+    /// Table flags (dict_table_t::flags, not FSP_SPACE_FLAGS).
+    pub table_flags: u32,
+    /// Number of columns, including the system columns.
+    pub n_cols: u32,
+}
+
+impl row_import_cfg_t {
+    pub fn from_buf(buf: &[u8]) -> Result<row_import_cfg_t> {
+        row_import_cfg_t::from_reader(buf)
+    }
+
+    /// Reads the .cfg header up to and including the number of columns. The per-column
+    /// and index metadata that follow are not parsed.
+    pub fn from_reader(mut r: impl Read) -> Result<row_import_cfg_t> {
+        let version = r.read_u32::<BigEndian>()?;
+        if version != IB_EXPORT_CFG_VERSION_V1 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported .cfg version: {version}"),
+            ));
+        }
+
+        let hostname = read_string(&mut r).map_err(|err| context(err, "hostname"))?;
+        let table_name = read_string(&mut r).map_err(|err| context(err, "table name"))?;
+        let autoinc = r.read_u64::<BigEndian>()?;
+        let page_size = r.read_u32::<BigEndian>()?;
+        let table_flags = r.read_u32::<BigEndian>()?;
+        let n_cols = r.read_u32::<BigEndian>()?;
+
+        Ok(row_import_cfg_t {
+            version,
+            hostname,
+            table_name,
+            autoinc,
+            page_size,
+            table_flags,
+            n_cols,
+        })
+    }
+}
+
+/// Reads a 4-byte length followed by a NUL-terminated string of that length.
+/// See row_import_cfg_read_string().
+fn read_string(mut r: impl Read) -> Result<String> {
+    let len = r.read_u32::<BigEndian>()?;
+    if len == 0 || len > IB_EXPORT_CFG_MAX_STRING {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid string length: {len}"),
+        ));
+    }
+
+    let mut buf = vec![0u8; len as usize];
+    r.read_exact(&mut buf)?;
+
+    if buf.pop() != Some(0) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "string is not NUL-terminated",
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+fn context(err: Error, field: &str) -> Error {
+    Error::new(err.kind(), format!("read {field}: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_cfg_header() {
+        // header of test/t1.cfg with innodb_page_size=16k and 3 user + 3 system columns.
+        let cfg: &[u8] = &[
+            0x00, 0x00, 0x00, 0x01, // version
+            0x00, 0x00, 0x00, 0x05, b'd', b'b', b'0', b'1', 0x00, // hostname
+            0x00, 0x00, 0x00, 0x08, b't', b'e', b's', b't', b'/', b't', b'1', 0x00, // table
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2a, // autoinc
+            0x00, 0x00, 0x40, 0x00, // page size
+            0x00, 0x00, 0x00, 0x21, // table flags
+            0x00, 0x00, 0x00, 0x06, // n_cols
+            0x00, 0x00, 0x00, 0x01, // (first column metadata, ignored)
+        ];
+
+        let hdr = row_import_cfg_t::from_buf(cfg).unwrap();
+        assert_eq!(
+            hdr,
+            row_import_cfg_t {
+                version: IB_EXPORT_CFG_VERSION_V1,
+                hostname: "db01".to_string(),
+                table_name: "test/t1".to_string(),
+                autoinc: 42,
+                page_size: 16384,
+                table_flags: 0x21,
+                n_cols: 6,
+            }
+        );
+
+        let mut bad_version = cfg.to_vec();
+        bad_version[3] = 2;
+        assert!(row_import_cfg_t::from_buf(&bad_version).is_err());
+
+        let mut unterminated = cfg.to_vec();
+        unterminated[12] = b'!';
+        assert!(row_import_cfg_t::from_buf(&unterminated).is_err());
+
+        assert!(row_import_cfg_t::from_buf(&cfg[..30]).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+//! Serializes byte arrays as hex strings instead of sequences of numbers.
+//! Use with `#[serde(serialize_with = "crate::serde_hex::serialize")]`.
+
+use serde::Serializer;
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub fn serialize<S: Serializer>(bytes: impl AsRef<[u8]>, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&to_hex(bytes.as_ref()))
+}
+
+pub fn serialize_option<S: Serializer>(
+    bytes: &Option<impl AsRef<[u8]>>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    match bytes {
+        Some(bytes) => serialize(bytes, s),
+        None => s.serialize_none(),
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::len_without_is_empty)]
 
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fmt::Display,
     io::{Error, ErrorKind, Result},
     ops::Range,
@@ -10,7 +11,65 @@ use std::{
 use anyhow::Context;
 use mmap_rs::{Mmap, MmapFlags, MmapMut, MmapOptions};
 
-use crate::{fil0fil, fsp0fsp, fsp0types, mach, page_buf::PageBuf, page0page};
+use crate::{
+    Lsn, fil0fil, fsp0fsp, fsp0types, fut0lst, mach,
+    page_buf::{self, DecodedPage, PageBuf},
+    page0page, trx0undo, univ,
+};
+
+/// Kind of a tablespace datafile. It decides which page 0 rules apply: the system
+/// tablespace may carry FIL_PAGE_FILE_FLUSH_LSN on page 0 and is validated as non-.ibd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TablespaceKind {
+    /// System tablespace (ibdata*), space id 0.
+    System,
+    /// Dedicated undo tablespace (undo001, ...).
+    Undo,
+    /// Temporary tablespace (ibtmp*).
+    Temporary,
+    /// File-per-table tablespace (*.ibd).
+    File,
+}
+
+impl TablespaceKind {
+    /// Guesses the kind from the datafile name as created by the server.
+    pub fn from_path(path: &Path) -> Option<TablespaceKind> {
+        let name = path.file_name()?.to_str()?;
+
+        if name.starts_with("ibdata") {
+            Some(TablespaceKind::System)
+        } else if name.starts_with("ibtmp") {
+            Some(TablespaceKind::Temporary)
+        } else if name
+            .strip_prefix("undo")
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+        {
+            Some(TablespaceKind::Undo)
+        } else if path.extension().is_some_and(|ext| ext == "ibd") {
+            Some(TablespaceKind::File)
+        } else {
+            None
+        }
+    }
+
+    /// Guesses the kind from the tablespace id. Undo tablespaces can't be told apart
+    /// from .ibd files by id alone, so they are reported as `File`.
+    pub fn from_space_id(space_id: u32) -> TablespaceKind {
+        if space_id == 0 {
+            TablespaceKind::System
+        } else if fsp0types::FSP_IS_SYSTEM_TEMPORARY(space_id) {
+            TablespaceKind::Temporary
+        } else {
+            TablespaceKind::File
+        }
+    }
+
+    /// Whether is_valid_flags() should apply the .ibd rules, i.e. anything but the
+    /// system tablespace.
+    pub fn is_ibd(self) -> bool {
+        self != TablespaceKind::System
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct TablespaceReader<'a> {
@@ -25,6 +84,8 @@ pub struct TablespaceReader<'a> {
     space_id: u32,
     /// tablespace flags
     flags: u32,
+    /// kind of the datafile, if known from the outside (i.e. the file name).
+    kind: Option<TablespaceKind>,
 }
 
 impl<'a> TablespaceReader<'a> {
@@ -37,9 +98,32 @@ impl<'a> TablespaceReader<'a> {
             order: 0,
             space_id: 0,
             flags: 0,
+            kind: None,
         }
     }
 
+    /// Sets the datafile kind instead of guessing it from the space id.
+    pub fn with_kind(mut self, kind: Option<TablespaceKind>) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    fn kind_of(&self, space_id: u32) -> TablespaceKind {
+        self.kind
+            .unwrap_or_else(|| TablespaceKind::from_space_id(space_id))
+    }
+
+    /// Bypasses the page 0 flags detection and validation, using the given tablespace flags
+    /// instead. The space id is still taken from the page 0 header as is. Use it to read
+    /// tablespaces with a damaged first page when the real format is known.
+    pub fn with_flags_override(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self.space_id = self
+            .read_4(fil0fil::FIL_PAGE_SPACE_ID as usize)
+            .unwrap_or_default();
+        self
+    }
+
     // Reads a few significant fields from the first page of the first
     // datafile. Reference: fsp0file.cc:Datafile::read_first_page().
     pub fn parse_first_page(&mut self) -> Result<()> {
@@ -79,10 +163,14 @@ impl<'a> TablespaceReader<'a> {
         let space_id = self.read_4(fil0fil::FIL_PAGE_SPACE_ID as usize)?;
         let flags =
             self.read_4((fsp0fsp::FSP_HEADER_OFFSET + fsp0fsp::FSP_SPACE_FLAGS) as usize)?;
-        // is_ibd is true if this is an .ibd file (not the system tablespace).
-        let is_ibd = space_id != 0;
+        if self.kind == Some(TablespaceKind::System) && space_id != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("System tablespace file has non-zero tablespace ID {space_id}"),
+            ));
+        }
 
-        if !fil0fil::is_valid_flags(flags, is_ibd, self.page) {
+        if !fil0fil::is_valid_flags(flags, self.kind_of(space_id).is_ibd(), self.page) {
             // original code tries to convert flags from old version (fsp_flags_convert_from_101).
             // we don't need that.
             return Err(Error::new(
@@ -130,7 +218,7 @@ impl<'a> TablespaceReader<'a> {
         }
 
         // Check if the whole page is blank.
-        if self.space_id == 0 && self.flags == 0 {
+        if self.is_system() && self.flags == 0 {
             let mut nonzero_bytes = self.page;
 
             while nonzero_bytes > 0 && self.buf[nonzero_bytes - 1] == 0 {
@@ -148,10 +236,7 @@ impl<'a> TablespaceReader<'a> {
             }
         }
 
-        // is_ibd is true if this is an .ibd file (not the system tablespace).
-        let is_ibd = self.space_id != 0;
-
-        if !fil0fil::is_valid_flags(self.flags, is_ibd, self.page) {
+        if !fil0fil::is_valid_flags(self.flags, self.is_ibd(), self.page) {
             return Err(Error::new(
                 ErrorKind::InvalidData,
                 format!(
@@ -203,6 +288,26 @@ impl<'a> TablespaceReader<'a> {
         Ok(())
     }
 
+    /// Checks every page of the tablespace for corruption and returns the failing page
+    /// numbers with the reason. All-zero pages (never written) are valid.
+    pub fn verify_all(&self) -> Vec<(u32, Error)> {
+        self.pages()
+            .enumerate()
+            .filter_map(|(page_no, page)| {
+                let page = match page {
+                    Ok(page) => page,
+                    Err(err) => return Some((page_no as u32, err)),
+                };
+
+                if page.iter().all(|&b| b == 0) {
+                    return None;
+                }
+
+                page.corrupted(None).err().map(|err| (page_no as u32, err))
+            })
+            .collect()
+    }
+
     pub fn ensure(&self, pos: usize, len: usize) -> Result<()> {
         match pos.checked_add(len) {
             Some(end) if end <= self.buf.len() => Ok(()),
@@ -218,21 +323,226 @@ impl<'a> TablespaceReader<'a> {
     }
 
     pub fn page(&self, page_no: u32) -> Result<PageBuf<'a>> {
+        let physical = self.physical_page_size();
         let pos = (page_no as usize)
-            .checked_mul(self.page)
+            .checked_mul(physical)
             .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "page_id overflow"))?;
 
-        Ok(PageBuf::new(self.flags, self.block(pos, self.page)?))
+        Ok(PageBuf::new(self.flags, self.block(pos, physical)?))
+    }
+
+    /// Returns the number of full pages in the tablespace.
+    pub fn npages(&self) -> u32 {
+        (self.len() / self.physical_page_size()) as u32
+    }
+
+    /// Iterates over all pages of the tablespace. A trailing partial page is yielded
+    /// as an `UnexpectedEof` error.
+    pub fn pages(&self) -> impl Iterator<Item = Result<PageBuf<'a>>> + '_ {
+        let num_pages = self.len().div_ceil(self.physical_page_size());
+
+        (0..num_pages as u32).map(|page_no| self.page(page_no))
+    }
+
+    /// Iterates over all pages of the tablespace decoding each one by its page type,
+    /// together with the page number. A corrupted page or a trailing partial page is
+    /// yielded as an error, and the iteration goes on with the next page.
+    pub fn decoded_pages(&self) -> impl Iterator<Item = Result<(u32, DecodedPage)>> + '_ {
+        self.pages().enumerate().map(|(page_no, page)| {
+            let page = page?;
+            page.corrupted(None)?;
+
+            Ok((page_no as u32, page.decode()))
+        })
+    }
+
+    /// Counts the pages of the tablespace per page type, most frequent first. Types
+    /// not known to `fil_page_type_t` are counted as `Unknown`.
+    pub fn page_type_histogram(&self) -> Result<Vec<(fil0fil::fil_page_type_t, usize)>> {
+        let mut counts = BTreeMap::<u16, usize>::new();
+
+        for page in self.pages() {
+            let page_type = fil0fil::fil_page_type_t::from(fil0fil::fil_page_get_type(page?.buf()));
+            *counts.entry(page_type as u16).or_default() += 1;
+        }
+
+        let mut histogram = counts
+            .into_iter()
+            .map(|(page_type, count)| (fil0fil::fil_page_type_t::from(page_type), count))
+            .collect::<Vec<_>>();
+        histogram.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+        Ok(histogram)
+    }
+
+    /// Counts the pages consisting of zero bytes only, i.e. allocated but never written.
+    pub fn zero_page_count(&self) -> usize {
+        self.pages()
+            .filter(|p| p.as_ref().is_ok_and(|p| p.iter().all(|&b| b == 0)))
+            .count()
+    }
+
+    /// Returns the distinct FIL_PAGE_LSN values of the tablespace in ascending order, each
+    /// with the pages carrying it. Pages with a zero LSN (never written) are skipped.
+    pub fn lsn_map(&self) -> Result<BTreeMap<Lsn, Vec<u32>>> {
+        let num_pages = self.npages();
+        let mut map = BTreeMap::<Lsn, Vec<u32>>::new();
+
+        for page_no in 0..num_pages {
+            let page_lsn = PageBuf::read_page_lsn(self.page(page_no)?.buf());
+            if page_lsn != 0 {
+                map.entry(page_lsn).or_default().push(page_no);
+            }
+        }
+
+        Ok(map)
+    }
+
+    /// Returns the pages with FIL_PAGE_LSN above `redo_end_lsn` as (page_no, page_lsn).
+    /// No page of a consistent snapshot can be ahead of the end of the redo log.
+    pub fn lsn_outliers(&self, redo_end_lsn: Lsn) -> Result<Vec<(u32, Lsn)>> {
+        let map = self.lsn_map()?;
+
+        Ok(map
+            .range(redo_end_lsn.saturating_add(1)..)
+            .flat_map(|(lsn, pages)| pages.iter().map(|page_no| (*page_no, *lsn)))
+            .collect())
+    }
+
+    /// Returns the numbers of the pages that are entirely zero although their extent
+    /// descriptor marks them as used. In page_compressed tablespaces these are likely
+    /// holes punched into the file rather than pages that were never written.
+    pub fn sparse_pages(&self) -> Result<Vec<u32>> {
+        let num_pages = self.npages();
+        // every descriptor page describes the next `physical page size` pages, which is
+        // zip_size for ROW_FORMAT=COMPRESSED, see xdes_calc_descriptor_page().
+        let pages_per_xdes = self.physical_page_size() as u32;
+        let extent_size = fsp0types::FSP_EXTENT_SIZE(self.page.trailing_zeros());
+
+        let mut sparse = vec![];
+        let mut xdes: Option<(u32, Vec<fsp0fsp::xdes_entry_t>)> = None;
+
+        for page_no in 0..num_pages {
+            let page = self.page(page_no)?;
+            if page.iter().any(|&b| b != 0) {
+                continue;
+            }
+
+            let xdes_page_no = page_no - page_no % pages_per_xdes;
+            if xdes.as_ref().is_none_or(|(no, _)| *no != xdes_page_no) {
+                let entries = fsp0fsp::xdes_page_entries(&self.page(xdes_page_no)?);
+                xdes = Some((xdes_page_no, entries));
+            }
+
+            let (_, entries) = xdes.as_ref().unwrap();
+            let nth = page_no % pages_per_xdes;
+            let Some(entry) = entries.get((nth / extent_size) as usize) else {
+                continue;
+            };
+
+            let (free, _) = entry.bitmap[(nth % extent_size) as usize];
+            if entry.state != 0 && !free {
+                sparse.push(page_no);
+            }
+        }
+
+        Ok(sparse)
+    }
+
+    /// Returns the page `page_no` followed by up to `max_next` pages linked through
+    /// FIL_PAGE_NEXT. Stops early at FIL_NULL.
+    pub fn follow_next(&self, page_no: u32, max_next: usize) -> Result<Vec<PageBuf<'a>>> {
+        let mut pages = vec![self.page(page_no)?];
+
+        while pages.len() <= max_next {
+            let next_page = pages[pages.len() - 1].next_page;
+            if next_page == fil0fil::FIL_NULL {
+                break;
+            }
+
+            pages.push(self.page(next_page)?);
+        }
+
+        Ok(pages)
+    }
+
+    /// Iterates over the pages of an undo log segment starting at `first_page` and
+    /// following the TRX_UNDO_PAGE_NODE next pointers until FIL_NULL. A list longer than
+    /// the tablespace (a cycle) ends with an `InvalidData` error.
+    pub fn undo_pages(&self, first_page: u32) -> impl Iterator<Item = Result<PageBuf<'a>>> + '_ {
+        let npages = self.npages();
+        // The list has no base node to take the length from: allow one node more than
+        // the tablespace has pages to detect a cycle.
+        let base = fut0lst::flst_base_node_t {
+            len: npages.saturating_add(1),
+            first: fil0fil::fil_addr_t::new(
+                first_page,
+                (trx0undo::TRX_UNDO_PAGE_HDR + trx0undo::TRX_UNDO_PAGE_NODE) as u16,
+            ),
+            last: fil0fil::fil_addr_t::null(),
+        };
+
+        fut0lst::FlstWalker::new(self, &base)
+            .zip(0..)
+            .map(move |(node, i)| {
+                let (addr, _) = node?;
+                if i >= npages {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("undo page list cycle at page {}", addr.page),
+                    ));
+                }
+
+                self.page(addr.page)
+            })
     }
 
     pub fn read_4(&self, pos: usize) -> Result<u32> {
         Ok(mach::mach_read_from_4(self.block(pos, 4)?))
     }
 
+    /// Resolves a file address within this tablespace into the page bytes starting at
+    /// `addr.boffset` up to the end of the page.
+    pub fn deref_addr(&self, addr: &fil0fil::fil_addr_t) -> Result<&'a [u8]> {
+        if addr.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "null file address"));
+        }
+
+        let physical = self.physical_page_size();
+        if addr.boffset as usize >= physical {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "byte offset {} is beyond the page size {}",
+                    addr.boffset, physical
+                ),
+            ));
+        }
+
+        let pos = (addr.page as usize)
+            .checked_mul(physical)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "page_id overflow"))?;
+
+        self.block(
+            pos + addr.boffset as usize,
+            physical - addr.boffset as usize,
+        )
+    }
+
     pub fn order(&self) -> usize {
         self.order
     }
 
+    pub fn page_size(&self) -> usize {
+        self.page
+    }
+
+    /// Returns the size of a page in the file: the compressed page size (KEY_BLOCK_SIZE)
+    /// of ROW_FORMAT=COMPRESSED tablespaces, otherwise the page size.
+    pub fn physical_page_size(&self) -> usize {
+        fil0fil::physical_size(self.flags, self.page)
+    }
+
     pub fn space_id(&self) -> u32 {
         self.space_id
     }
@@ -241,6 +551,26 @@ impl<'a> TablespaceReader<'a> {
         self.flags
     }
 
+    /// Returns whether page 0 consists of zero bytes. In this state space_id and flags
+    /// are 0 and only later pages may contain something useful.
+    pub fn is_uninitialized(&self) -> bool {
+        self.buf.len() >= self.page && self.buf[..self.page].iter().all(|&b| b == 0)
+    }
+
+    /// Returns the datafile kind: the one given with `with_kind()`, or else the one
+    /// implied by the space id.
+    pub fn kind(&self) -> TablespaceKind {
+        self.kind_of(self.space_id)
+    }
+
+    pub fn is_system(&self) -> bool {
+        self.kind() == TablespaceKind::System
+    }
+
+    pub fn is_ibd(&self) -> bool {
+        self.kind().is_ibd()
+    }
+
     pub fn len(&self) -> usize {
         self.buf.len()
     }
@@ -249,11 +579,16 @@ impl<'a> TablespaceReader<'a> {
 pub struct MmapTablespaceReader {
     m: Mmap,
     page: usize,
+    kind: Option<TablespaceKind>,
 }
 
 impl MmapTablespaceReader {
     pub fn new(m: Mmap, page: usize) -> MmapTablespaceReader {
-        MmapTablespaceReader { m, page }
+        MmapTablespaceReader {
+            m,
+            page,
+            kind: None,
+        }
     }
 
     pub fn open(file_path: &Path, page_size: usize) -> anyhow::Result<MmapTablespaceReader> {
@@ -268,9 +603,11 @@ impl MmapTablespaceReader {
             return Err(anyhow::anyhow!("tablespace file is empty"));
         }
 
-        if size % page_size as u64 != 0 {
+        // the physical page size of ROW_FORMAT=COMPRESSED tablespaces is checked below.
+        if size % univ::UNIV_ZIP_SIZE_MIN as u64 != 0 {
             return Err(anyhow::anyhow!(
-                "tablespace file size {size} is not a multiple of page size {page_size}",
+                "tablespace file size {size} is not a multiple of {}",
+                univ::UNIV_ZIP_SIZE_MIN
             ));
         }
 
@@ -283,19 +620,38 @@ impl MmapTablespaceReader {
                 .context("mmap tablespace file")?
         };
 
-        Ok(MmapTablespaceReader::new(mmap, page_size))
+        // ROW_FORMAT=COMPRESSED tablespaces are a multiple of the physical page size.
+        let flags_pos = (fsp0fsp::FSP_HEADER_OFFSET + fsp0fsp::FSP_SPACE_FLAGS) as usize;
+        if let Some(flags) = mmap.as_slice().get(flags_pos..flags_pos + 4) {
+            let physical = fil0fil::physical_size(mach::mach_read_from_4(flags), page_size);
+            if physical != 0 && size % physical as u64 != 0 {
+                return Err(anyhow::anyhow!(
+                    "tablespace file size {size} is not a multiple of page size {physical}",
+                ));
+            }
+        }
+
+        let mut reader = MmapTablespaceReader::new(mmap, page_size);
+        reader.kind = TablespaceKind::from_path(file_path);
+
+        Ok(reader)
     }
 
     pub fn mmap(&self) -> &Mmap {
         &self.m
     }
 
+    /// Returns the datafile kind detected from the file name, if any.
+    pub fn kind(&self) -> Option<TablespaceKind> {
+        self.kind
+    }
+
     pub fn len(&self) -> usize {
         self.m.len()
     }
 
     pub fn reader(&self) -> anyhow::Result<TablespaceReader<'_>> {
-        let mut reader = TablespaceReader::new(self.m.as_slice(), self.page);
+        let mut reader = TablespaceReader::new(self.m.as_slice(), self.page).with_kind(self.kind);
 
         reader
             .parse_first_page()
@@ -307,6 +663,34 @@ impl MmapTablespaceReader {
 
         Ok(reader)
     }
+
+    /// Same as `reader()`, but treats an all-zero page 0 as an uninitialized tablespace
+    /// instead of an error. See `TablespaceReader::is_uninitialized()`.
+    pub fn reader_allow_uninitialized(&self) -> anyhow::Result<TablespaceReader<'_>> {
+        let reader = TablespaceReader::new(self.m.as_slice(), self.page).with_kind(self.kind);
+
+        if reader.is_uninitialized() {
+            return Ok(reader);
+        }
+
+        self.reader()
+    }
+
+    /// Same as `reader()`, but skips page 0 validation and uses the given flags, which
+    /// must still match the page size. See `TablespaceReader::with_flags_override()`.
+    pub fn reader_with_flags_override(&self, flags: u32) -> anyhow::Result<TablespaceReader<'_>> {
+        let logical_size = fil0fil::logical_size(flags);
+        if logical_size != self.page {
+            return Err(anyhow::anyhow!(
+                "tablespace flags {flags:#x} imply the page size {logical_size}, not {}",
+                self.page
+            ));
+        }
+
+        Ok(TablespaceReader::new(self.m.as_slice(), self.page)
+            .with_kind(self.kind)
+            .with_flags_override(flags))
+    }
 }
 
 impl Display for TablespaceReader<'_> {
@@ -322,11 +706,19 @@ impl Display for TablespaceReader<'_> {
 pub struct MmapTablespaceWriter {
     m: MmapMut,
     page: usize,
+    kind: Option<TablespaceKind>,
+    /// pages modified since the last flush.
+    dirty: BTreeSet<u32>,
 }
 
 impl MmapTablespaceWriter {
     pub fn new(m: MmapMut, page: usize) -> MmapTablespaceWriter {
-        MmapTablespaceWriter { m, page }
+        MmapTablespaceWriter {
+            m,
+            page,
+            kind: None,
+            dirty: BTreeSet::new(),
+        }
     }
 
     pub fn open(file_path: &Path, page_size: usize) -> anyhow::Result<MmapTablespaceWriter> {
@@ -347,7 +739,8 @@ impl MmapTablespaceWriter {
 
         if size % page_size as u64 != 0 {
             return Err(anyhow::anyhow!(
-                "tablespace file size {size} is not a multiple of page size {page_size}",
+                "tablespace file size {size} is not a multiple of {}",
+                univ::UNIV_ZIP_SIZE_MIN
             ));
         }
 
@@ -360,7 +753,10 @@ impl MmapTablespaceWriter {
                 .context("mmap tablespace file")?
         };
 
-        Ok(MmapTablespaceWriter::new(mmap, page_size))
+        let mut writer = MmapTablespaceWriter::new(mmap, page_size);
+        writer.kind = TablespaceKind::from_path(file_path);
+
+        Ok(writer)
     }
 
     pub fn mmap_mut(&self) -> &MmapMut {
@@ -376,13 +772,67 @@ impl MmapTablespaceWriter {
         Ok(())
     }
 
-    pub fn flush_all(&self) -> anyhow::Result<()> {
+    pub fn flush_all(&mut self) -> anyhow::Result<()> {
         self.m.flush(0..self.len())?;
+        self.dirty.clear();
+        Ok(())
+    }
+
+    /// Overwrites the page with the given contents as is and marks it dirty. The footer is
+    /// not recomputed, see `TablespaceWriter::write_page_with_footer()`.
+    pub fn write_page(&mut self, page_no: u32, data: &[u8]) -> anyhow::Result<()> {
+        if data.len() != self.page {
+            return Err(anyhow::anyhow!(
+                "page data length {} does not match page size {}",
+                data.len(),
+                self.page
+            ));
+        }
+
+        let pos = (page_no as usize)
+            .checked_mul(self.page)
+            .filter(|pos| pos + self.page <= self.len())
+            .ok_or_else(|| anyhow::anyhow!("page {page_no} is beyond the end of tablespace"))?;
+
+        self.m.as_mut_slice()[pos..pos + self.page].copy_from_slice(data);
+        self.dirty.insert(page_no);
+
+        Ok(())
+    }
+
+    /// Marks the page as modified, i.e. when it was changed through `TablespaceWriter`.
+    pub fn mark_dirty(&mut self, page_no: u32) {
+        self.dirty.insert(page_no);
+    }
+
+    /// Returns the byte ranges of the dirty pages, with adjacent pages coalesced.
+    pub fn dirty_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+
+        for &page_no in &self.dirty {
+            let start = page_no as usize * self.page;
+
+            match ranges.last_mut() {
+                Some(last) if last.end == start => last.end += self.page,
+                _ => ranges.push(start..start + self.page),
+            }
+        }
+
+        ranges
+    }
+
+    /// Flushes only the dirty pages, one call per coalesced range.
+    pub fn flush_dirty(&mut self) -> anyhow::Result<()> {
+        for range in self.dirty_ranges() {
+            self.m.flush(range)?;
+        }
+
+        self.dirty.clear();
         Ok(())
     }
 
     pub fn reader(&self) -> anyhow::Result<TablespaceReader<'_>> {
-        let mut reader = TablespaceReader::new(self.m.as_slice(), self.page);
+        let mut reader = TablespaceReader::new(self.m.as_slice(), self.page).with_kind(self.kind);
 
         reader
             .parse_first_page()
@@ -449,6 +899,35 @@ impl<'a> TablespaceWriter<'a> {
         Ok(&mut self.buf[pos..pos + self.page_size])
     }
 
+    /// Overwrites the page with the given contents and recomputes its footer: the
+    /// full_crc32 checksum and the end LSN. Only full_crc32 tablespaces are supported.
+    pub fn write_page_with_footer(&mut self, page_no: u32, page: &[u8]) -> Result<()> {
+        if page.len() != self.page_size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "page data length {} does not match page size {}",
+                    page.len(),
+                    self.page_size
+                ),
+            ));
+        }
+
+        if !fil0fil::full_crc32(self.flags) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "can't compute the checksum of tablespace flags {:#x}",
+                    self.flags
+                ),
+            ));
+        }
+
+        let buf = self.page_buf(page_no)?;
+        buf.copy_from_slice(page);
+        page_buf::make_page_footer(buf)
+    }
+
     pub fn mmap_mut(&'a mut self) -> &'a mut [u8] {
         self.buf
     }
@@ -478,3 +957,511 @@ impl Display for TablespaceWriter<'_> {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_buf::{make_page_footer, make_page_header};
+
+    fn make_page0(page_size: usize, space_id: u32, flush_lsn: Lsn) -> Vec<u8> {
+        let flags = 0x15;
+        let mut buf = vec![0u8; page_size];
+
+        make_page_header(
+            &mut buf,
+            space_id,
+            0,
+            fil0fil::FIL_PAGE_TYPE_FSP_HDR,
+            1000,
+            flags,
+        )
+        .unwrap();
+        let fsp = fsp0fsp::FSP_HEADER_OFFSET as usize;
+        mach::mach_write_to_4(&mut buf[fsp + fsp0fsp::FSP_SPACE_ID as usize..], space_id).unwrap();
+        mach::mach_write_to_4(&mut buf[fsp + fsp0fsp::FSP_SPACE_FLAGS as usize..], flags).unwrap();
+        mach::mach_write_to_8(
+            &mut buf[fil0fil::FIL_PAGE_FILE_FLUSH_LSN_OR_KEY_VERSION as usize..],
+            flush_lsn,
+        )
+        .unwrap();
+        make_page_footer(&mut buf).unwrap();
+
+        buf
+    }
+
+    #[test]
+    fn test_writer_flush_dirty() {
+        let page_size = 16384;
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), vec![0u8; page_size * 8]).unwrap();
+
+        let mut writer = MmapTablespaceWriter::open(file.path(), page_size).unwrap();
+        let page = vec![0xaau8; page_size];
+        for page_no in [6, 1, 3, 2] {
+            writer.write_page(page_no, &page).unwrap();
+        }
+        assert!(writer.write_page(8, &page).is_err());
+        assert!(writer.write_page(0, &page[1..]).is_err());
+
+        assert_eq!(
+            writer.dirty_ranges(),
+            vec![page_size..page_size * 4, page_size * 6..page_size * 7]
+        );
+
+        writer.flush_dirty().unwrap();
+        assert!(writer.dirty_ranges().is_empty());
+
+        let data = std::fs::read(file.path()).unwrap();
+        assert!(data[page_size..page_size * 4].iter().all(|&b| b == 0xaa));
+        assert!(data[page_size * 4..page_size * 6].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_writer_write_page_with_footer() {
+        let page_size = 16384;
+        let mut buf = vec![0u8; page_size * 4];
+        let mut page = vec![0u8; page_size];
+        make_page_header(&mut page, 1, 2, fil0fil::FIL_PAGE_INDEX, 1000, 0x15).unwrap();
+        page[200] = 0xaa;
+
+        let mut writer = TablespaceWriter::new(&mut buf, page_size, 1, 0x15);
+        writer.write_page_with_footer(2, &page).unwrap();
+        assert!(writer.write_page_with_footer(4, &page).is_err());
+        assert!(writer.write_page_with_footer(2, &page[1..]).is_err());
+
+        let mut writer = TablespaceWriter::new(&mut buf, page_size, 1, 0);
+        assert!(writer.write_page_with_footer(3, &page).is_err());
+
+        let reader = TablespaceReader::new(&buf, page_size).with_flags_override(0x15);
+        let written = reader.page(2).unwrap();
+        assert_eq!(written.buf()[200], 0xaa);
+        assert_eq!(written.foot_lsn, 1000);
+        written.corrupted(Some(1000)).unwrap();
+        assert!(reader.page(3).unwrap().iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_tablespace_kind_from_path() {
+        let kind = |name: &str| TablespaceKind::from_path(Path::new(name));
+
+        assert_eq!(kind("/data/ibdata1"), Some(TablespaceKind::System));
+        assert_eq!(kind("ibtmp1"), Some(TablespaceKind::Temporary));
+        assert_eq!(kind("undo001"), Some(TablespaceKind::Undo));
+        assert_eq!(kind("db/t1.ibd"), Some(TablespaceKind::File));
+        assert_eq!(kind("undo_log.ibd"), Some(TablespaceKind::File));
+        assert_eq!(kind("undo"), None);
+        assert_eq!(kind("ib_logfile0"), None);
+    }
+
+    #[test]
+    fn test_reader_system_vs_ibd() {
+        let page_size = 16384;
+        let dir = tempfile::tempdir().unwrap();
+
+        let sys_path = dir.path().join("ibdata1");
+        std::fs::write(&sys_path, make_page0(page_size, 0, 5000)).unwrap();
+        let ibd_path = dir.path().join("t1.ibd");
+        std::fs::write(&ibd_path, make_page0(page_size, 5, 5000)).unwrap();
+
+        let sys = MmapTablespaceReader::open(&sys_path, page_size).unwrap();
+        assert_eq!(sys.kind(), Some(TablespaceKind::System));
+        let reader = sys.reader().unwrap();
+        assert!(reader.is_system());
+        assert!(!reader.is_ibd());
+        // the checksum does not cover FIL_PAGE_FILE_FLUSH_LSN, which is written later.
+        assert!(reader.page(0).unwrap().corrupted(Some(4000)).is_ok());
+
+        let ibd = MmapTablespaceReader::open(&ibd_path, page_size).unwrap();
+        assert_eq!(ibd.kind(), Some(TablespaceKind::File));
+        let reader = ibd.reader().unwrap();
+        assert!(reader.is_ibd());
+        assert!(reader.page(0).unwrap().corrupted(Some(4000)).is_ok());
+
+        // A system tablespace file must have space id 0.
+        let bad_path = dir.path().join("ibdata2");
+        std::fs::write(&bad_path, make_page0(page_size, 5, 0)).unwrap();
+        let bad = MmapTablespaceReader::open(&bad_path, page_size).unwrap();
+        assert!(bad.reader().is_err());
+
+        // Without a file name hint the kind follows the space id.
+        let buf = make_page0(page_size, 0, 5000);
+        let mut reader = TablespaceReader::new(&buf, page_size);
+        reader.parse_first_page().unwrap();
+        assert_eq!(reader.kind(), TablespaceKind::System);
+    }
+
+    #[test]
+    fn test_reader_allow_uninitialized() {
+        let page_size = 16384;
+        let mut buf = vec![0u8; page_size * 2];
+        buf[page_size + 100] = 0xaa;
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &buf).unwrap();
+
+        let mmap_reader = MmapTablespaceReader::open(file.path(), page_size).unwrap();
+        assert!(mmap_reader.reader().is_err());
+
+        let reader = mmap_reader.reader_allow_uninitialized().unwrap();
+        assert!(reader.is_uninitialized());
+        assert_eq!(reader.space_id(), 0);
+        assert_eq!(reader.flags(), 0);
+        assert_eq!(reader.page(1).unwrap()[100], 0xaa);
+    }
+
+    #[test]
+    fn test_reader_with_flags_override() {
+        let page_size = 16384;
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), make_page0(page_size, 5, 0)).unwrap();
+
+        let mmap_reader = MmapTablespaceReader::open(file.path(), page_size).unwrap();
+        for flags in [0, 0x15] {
+            let reader = mmap_reader.reader_with_flags_override(flags).unwrap();
+            assert_eq!(reader.space_id(), 5);
+            assert_eq!(reader.flags(), flags);
+        }
+
+        // full_crc32 flags of 4K, 8K and 64K pages.
+        for flags in [0x13, 0x14, 0x17] {
+            assert!(mmap_reader.reader_with_flags_override(flags).is_err());
+        }
+    }
+
+    #[test]
+    fn test_follow_next() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 1, 0);
+        buf.resize(page_size * 6, 0);
+
+        // chain 2 -> 5 -> 3.
+        for (page_no, next_page) in [(2, 5), (5, 3), (3, fil0fil::FIL_NULL)] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 1, page_no, fil0fil::FIL_PAGE_INDEX, 100, 0x15).unwrap();
+            mach::mach_write_to_4(&mut page[fil0fil::FIL_PAGE_NEXT as usize..], next_page).unwrap();
+            make_page_footer(page).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let page_nos =
+            |pages: Vec<PageBuf<'_>>| pages.iter().map(|p| p.page_no).collect::<Vec<_>>();
+
+        assert_eq!(page_nos(reader.follow_next(2, 5).unwrap()), vec![2, 5, 3]);
+        assert_eq!(page_nos(reader.follow_next(2, 1).unwrap()), vec![2, 5]);
+        assert_eq!(page_nos(reader.follow_next(2, 0).unwrap()), vec![2]);
+    }
+
+    #[test]
+    fn test_pages() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 1, 0);
+        buf.resize(page_size * 3, 0);
+        for page_no in 1..3 {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 1, page_no, fil0fil::FIL_PAGE_INDEX, 100, 0x15).unwrap();
+            make_page_footer(page).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        assert_eq!(reader.npages(), 3);
+        assert_eq!(reader.pages().count(), 3);
+        let page_nos = reader
+            .pages()
+            .map(|p| p.unwrap().page_no)
+            .collect::<Vec<_>>();
+        assert_eq!(page_nos, vec![0, 1, 2]);
+
+        // a trailing partial page is reported instead of being dropped.
+        buf.resize(page_size * 3 + 100, 0);
+        let reader = TablespaceReader::new(&buf, page_size);
+        let pages = reader.pages().collect::<Vec<_>>();
+        assert_eq!(reader.npages(), 3);
+        assert_eq!(pages.len(), 4);
+        assert!(pages[..3].iter().all(|p| p.is_ok()));
+        assert_eq!(
+            pages[3].as_ref().unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_page_type_histogram() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 1, 0);
+        buf.resize(page_size * 6, 0);
+
+        for (page_no, page_type) in [
+            (1, fil0fil::FIL_PAGE_INDEX),
+            (2, fil0fil::FIL_PAGE_INDEX),
+            (3, fil0fil::FIL_PAGE_INDEX),
+            (4, 0x7777),
+        ] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 1, page_no, page_type, 100, 0x15).unwrap();
+            make_page_footer(page).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let histogram = reader.page_type_histogram().unwrap();
+
+        assert_eq!(
+            histogram,
+            vec![
+                (fil0fil::fil_page_type_t::Index, 3),
+                (fil0fil::fil_page_type_t::Allocated, 1),
+                (fil0fil::fil_page_type_t::FspHdr, 1),
+                (fil0fil::fil_page_type_t::Unknown, 1),
+            ]
+        );
+        assert_eq!(reader.zero_page_count(), 1);
+    }
+
+    #[test]
+    fn test_verify_all() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 1, 0);
+        buf.resize(page_size * 4, 0);
+
+        for page_no in [1, 3] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 1, page_no, fil0fil::FIL_PAGE_INDEX, 100, 0x15).unwrap();
+            make_page_footer(page).unwrap();
+        }
+        // page 2 stays all zero, page 3 gets a flipped byte.
+        buf[3 * page_size + 100] ^= 0xff;
+
+        let mut reader = TablespaceReader::new(&buf, page_size);
+        reader.parse_first_page().unwrap();
+        let corrupted = reader.verify_all();
+
+        assert_eq!(corrupted.len(), 1);
+        assert_eq!(corrupted[0].0, 3);
+        assert_eq!(corrupted[0].1.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_compressed_page_stride() {
+        let page_size = 16384;
+        let zip_size = 8192;
+        // ROW_FORMAT=COMPRESSED KEY_BLOCK_SIZE=8.
+        let flags = fsp0types::FSP_FLAGS_MASK_POST_ANTELOPE
+            | fsp0types::FSP_FLAGS_MASK_ATOMIC_BLOBS
+            | (4 << fsp0types::FSP_FLAGS_POS_ZIP_SSIZE);
+        assert_eq!(fil0fil::zip_size(flags) as usize, zip_size);
+
+        let mut buf = vec![0u8; zip_size * 3];
+        for page_no in 0..3u32 {
+            let page = &mut buf[page_no as usize * zip_size..(page_no as usize + 1) * zip_size];
+            mach::mach_write_to_4(&mut page[fil0fil::FIL_PAGE_OFFSET as usize..], page_no).unwrap();
+            mach::mach_write_to_4(&mut page[fil0fil::FIL_PAGE_SPACE_ID as usize..], 7).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size).with_flags_override(flags);
+        assert_eq!(reader.physical_page_size(), zip_size);
+        assert_eq!(reader.npages(), 3);
+
+        let page = reader.page(2).unwrap();
+        assert_eq!(page.page_no, 2);
+        assert_eq!(page.page_size(), zip_size);
+        assert!(page.is_compressed());
+        assert!(reader.page(3).is_err());
+
+        // the file size must be a multiple of the physical page size.
+        let fsp = fsp0fsp::FSP_HEADER_OFFSET as usize;
+        mach::mach_write_to_4(&mut buf[fsp + fsp0fsp::FSP_SPACE_FLAGS as usize..], flags).unwrap();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &buf).unwrap();
+        let mmap_reader = MmapTablespaceReader::open(file.path(), page_size).unwrap();
+        assert_eq!(mmap_reader.len(), zip_size * 3);
+
+        // page 0 is read with the physical page size once the flags are parsed.
+        mach::mach_write_to_4(&mut buf[fsp + fsp0fsp::FSP_SPACE_ID as usize..], 7).unwrap();
+        let mut reader = TablespaceReader::new(&buf, page_size);
+        reader.parse_first_page().unwrap();
+        assert_eq!((reader.space_id(), reader.flags()), (7, flags));
+        assert_eq!(reader.physical_page_size(), zip_size);
+        assert_eq!(reader.npages(), 3);
+        assert_eq!(reader.page(0).unwrap().page_size(), zip_size);
+
+        std::fs::write(file.path(), &buf[..zip_size * 3 - 1024]).unwrap();
+        assert!(MmapTablespaceReader::open(file.path(), page_size).is_err());
+        std::fs::write(file.path(), &buf[..zip_size * 3 - 512]).unwrap();
+        assert_eq!(
+            MmapTablespaceReader::open(file.path(), page_size)
+                .err()
+                .unwrap()
+                .to_string(),
+            "tablespace file size 24064 is not a multiple of 1024"
+        );
+    }
+
+    #[test]
+    fn test_undo_pages() {
+        let page_size = 16384;
+        let node = (trx0undo::TRX_UNDO_PAGE_HDR + trx0undo::TRX_UNDO_PAGE_NODE) as usize;
+        let next_of = node + crate::fut0lst::FLST_NEXT as usize;
+        let node_addr = |page_no| fil0fil::fil_addr_t::new(page_no, node as u16);
+
+        // undo pages 3 -> 4 -> 6.
+        let mut buf = vec![0u8; page_size * 7];
+        for (page_no, next) in [(3, Some(4)), (4, Some(6)), (6, None)] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            crate::page_buf::make_undo_log_page(page, 1, page_no, 100, 0x15).unwrap();
+            if let Some(next) = next {
+                node_addr(next).write_to(&mut page[next_of..]).unwrap();
+            }
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let page_nos = reader
+            .undo_pages(3)
+            .map(|p| p.unwrap().page_no)
+            .collect::<Vec<_>>();
+        assert_eq!(page_nos, vec![3, 4, 6]);
+
+        // 6 -> 3 closes a cycle.
+        node_addr(3)
+            .write_to(&mut buf[6 * page_size + next_of..])
+            .unwrap();
+        let reader = TablespaceReader::new(&buf, page_size);
+        let pages = reader.undo_pages(3).collect::<Vec<_>>();
+        assert_eq!(pages.len(), 8);
+        assert!(pages[..7].iter().all(|p| p.is_ok()));
+        assert_eq!(
+            pages[7].as_ref().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_decoded_pages() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 0, 0);
+        buf.resize(page_size * 9, 0);
+
+        for (page_no, page_type) in [
+            (1, fil0fil::FIL_PAGE_IBUF_BITMAP),
+            (2, fil0fil::FIL_PAGE_INODE),
+            (4, fil0fil::FIL_PAGE_INDEX),
+            (5, fil0fil::FIL_PAGE_TYPE_TRX_SYS),
+            (6, fil0fil::FIL_PAGE_TYPE_SYS),
+            (7, fil0fil::FIL_PAGE_TYPE_SYS),
+            (8, fil0fil::FIL_PAGE_TYPE_XDES),
+        ] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 0, page_no, page_type, 100, 0x15).unwrap();
+            make_page_footer(page).unwrap();
+        }
+
+        // the index root page on level 1 with 2 records.
+        let page4 = 4 * page_size + page0page::PAGE_HEADER as usize;
+        mach::mach_write_to_2(&mut buf[page4 + page0page::PAGE_N_RECS as usize..], 2).unwrap();
+        mach::mach_write_to_2(&mut buf[page4 + page0page::PAGE_LEVEL as usize..], 1).unwrap();
+        make_page_footer(&mut buf[4 * page_size..5 * page_size]).unwrap();
+
+        // page 3 in the middle is corrupted.
+        buf[3 * page_size + 100] = 0x01;
+
+        let mut reader = TablespaceReader::new(&buf, page_size);
+        reader.parse_first_page().unwrap();
+        let pages = reader.decoded_pages().collect::<Vec<_>>();
+
+        assert_eq!(pages.len(), 9);
+        assert_eq!(
+            pages[3].as_ref().unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        for (i, page) in pages.iter().enumerate().filter(|&(i, _)| i != 3) {
+            assert_eq!(page.as_ref().unwrap().0, i as u32);
+        }
+
+        let page = |i: usize| &pages[i].as_ref().unwrap().1;
+        assert!(matches!(page(0), DecodedPage::FspHeader(_)));
+        assert!(matches!(
+            page(1),
+            DecodedPage::Other {
+                page_type: fil0fil::FIL_PAGE_IBUF_BITMAP
+            }
+        ));
+        assert!(matches!(
+            page(2),
+            DecodedPage::Other {
+                page_type: fil0fil::FIL_PAGE_INODE
+            }
+        ));
+        assert!(matches!(
+            page(4),
+            DecodedPage::Index(hdr) if hdr.level == 1 && hdr.n_recs == 2
+        ));
+        assert!(matches!(page(5), DecodedPage::TrxSys(_)));
+        assert!(matches!(page(6), DecodedPage::Rseg(_)));
+        // the data dictionary header page is not a rollback segment.
+        assert!(matches!(
+            page(7),
+            DecodedPage::Other {
+                page_type: fil0fil::FIL_PAGE_TYPE_SYS
+            }
+        ));
+        assert!(matches!(
+            page(8),
+            DecodedPage::Xdes(entries) if entries.len() == 256
+        ));
+    }
+
+    #[test]
+    fn test_sparse_pages() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 5, 0);
+        buf.resize(page_size * 8, 0);
+
+        // the first extent is a fragment extent with pages 0-5 in use.
+        let xdes = fsp0fsp::XDES_ARR_OFFSET as usize;
+        mach::mach_write_to_4(
+            &mut buf[xdes + fsp0fsp::XDES_STATE as usize..],
+            fsp0fsp::XDES_FREE_FRAG,
+        )
+        .unwrap();
+        let bitmap = xdes + fsp0fsp::XDES_BITMAP as usize;
+        buf[bitmap..bitmap + 16].fill(0xff);
+        buf[bitmap] = 0x00; // pages 0-3 are used
+        buf[bitmap + 1] = 0xf0; // pages 4-5 are used
+        make_page_footer(&mut buf[..page_size]).unwrap();
+
+        for page_no in [1, 2, 4] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 5, page_no, fil0fil::FIL_PAGE_INDEX, 100, 0x15).unwrap();
+            make_page_footer(page).unwrap();
+        }
+
+        // pages 3 and 5 are punched, pages 6 and 7 are free.
+        let reader = TablespaceReader::new(&buf, page_size);
+        assert_eq!(reader.sparse_pages().unwrap(), vec![3, 5]);
+    }
+
+    #[test]
+    fn test_lsn_outliers() {
+        let page_size = 16384;
+        let mut buf = make_page0(page_size, 5, 0);
+        buf.resize(page_size * 6, 0);
+
+        for (page_no, lsn) in [(1, 2000), (2, 1000), (3, 2000), (4, 1_000_000)] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_page_header(page, 5, page_no, fil0fil::FIL_PAGE_INDEX, lsn, 0x15).unwrap();
+            make_page_footer(page).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let map = reader.lsn_map().unwrap();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![(1000, vec![0, 2]), (2000, vec![1, 3]), (1_000_000, vec![4]),]
+        );
+
+        assert_eq!(reader.lsn_outliers(5000).unwrap(), vec![(4, 1_000_000)]);
+        assert_eq!(reader.lsn_outliers(1_000_000).unwrap(), vec![]);
+        assert_eq!(
+            reader.lsn_outliers(1500).unwrap(),
+            vec![(1, 2000), (3, 2000), (4, 1_000_000)]
+        );
+    }
+}
@@ -1,8 +1,11 @@
 /// Undo log segment slot in a rollback segment header
 use std::collections::HashMap;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
-use crate::{fsp0types, fut0lst, mach, trx0sys::mysql_log_t, wsrep};
+use crate::{
+    fil0fil, fsp0types, fut0lst, mach, page_buf::PageBuf, tablespace::TablespaceReader,
+    trx0sys::mysql_log_t, trx0undo, wsrep,
+};
 
 /// Number of undo log slots in a rollback segment file copy
 #[allow(non_snake_case)]
@@ -84,9 +87,35 @@ pub const TRX_RSEG_WSREP_XID_BQUAL_LEN: u32 = TRX_RSEG_WSREP_XID_INFO + 8;
 /// Offset after TRX_RSEG_MAX_TRX_ID.
 pub const TRX_RSEG_WSREP_XID_DATA: u32 = TRX_RSEG_WSREP_XID_INFO + 12;
 
+/// TRX_RSEG_FORMAT value of the pre-MariaDB 10.3.5 rollback segment header.
+pub const TRX_RSEG_FORMAT_PRE_10_3_5: u32 = 0xfffffffe;
+
+/// Format of the rollback segment header page, see TRX_RSEG_FORMAT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum RsegFormat {
+    /// MariaDB 10.3.5 or later: TRX_RSEG_MAX_TRX_ID, the binlog and WSREP XID fields follow
+    /// the undo slots.
+    Modern,
+    /// Before MariaDB 10.3.5: the fields after the undo slots are undefined.
+    PreMariaDB10_3_5,
+}
+
+impl From<u32> for RsegFormat {
+    /// Only 0 means the modern format, like in trx_rseg_mem_restore().
+    fn from(format: u32) -> Self {
+        if format == 0 {
+            RsegFormat::Modern
+        } else {
+            RsegFormat::PreMariaDB10_3_5
+        }
+    }
+}
+
 #[allow(non_camel_case_types)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct trx_rseg_t {
-    pub format: u32,
+    pub format: RsegFormat,
     /// Number of pages in the TRX_RSEG_HISTORY list
     pub history_size: u32,
     /// Committed transaction logs that have not been purged yet
@@ -95,8 +124,8 @@ pub struct trx_rseg_t {
     pub fseg_header: fsp0types::fseg_header_t,
     /// Undo log segment slots
     pub undo_slots: HashMap<u32, u32>, // slot number -> page number
-    /// Maximum transaction ID (valid only if TRX_RSEG_FORMAT is 0)
-    pub max_trx_id: u64,
+    /// Maximum transaction ID (None unless the format is `RsegFormat::Modern`)
+    pub max_trx_id: Option<u64>,
     pub mysql_log: Option<mysql_log_t>,
     pub wsrep_xid: Option<wsrep::wsrep_xid_t>,
 }
@@ -117,7 +146,7 @@ impl trx_rseg_t {
                     + TRX_RSEG_WSREP_XID_LEN) as usize
         );
 
-        let format = mach::mach_read_from_4(&buf[TRX_RSEG_FORMAT as usize..]); // 0
+        let format = RsegFormat::from(mach::mach_read_from_4(&buf[TRX_RSEG_FORMAT as usize..])); // 0
         let history_size = mach::mach_read_from_4(&buf[TRX_RSEG_HISTORY_SIZE as usize..]); // 4
         let history = fut0lst::flst_base_node_t::from_buf(&buf[TRX_RSEG_HISTORY as usize..]); // 8
         let fseg_header = fsp0types::fseg_header_t::from_buf(&buf[TRX_RSEG_FSEG_HEADER as usize..]); // 8+16
@@ -131,13 +160,20 @@ impl trx_rseg_t {
             }
         }
 
-        let max_trx_id_offset = TRX_RSEG_MAX_TRX_ID(page_size) as usize;
-        let max_trx_id = mach::mach_read_from_8(&buf[max_trx_id_offset..]);
-
-        let mysql_log = mysql_log_t_from_trx_rseg_buf(&buf[max_trx_id_offset..]);
-        let wsrep_xid = wsrep_xid_t_from_trx_rseg_buf(
-            &buf[max_trx_id_offset + TRX_RSEG_WSREP_XID_INFO as usize..],
-        );
+        // The fields after the undo slots are only valid in the modern format.
+        let (max_trx_id, mysql_log, wsrep_xid) = if format == RsegFormat::Modern {
+            let max_trx_id_offset = TRX_RSEG_MAX_TRX_ID(page_size) as usize;
+
+            (
+                Some(mach::mach_read_from_8(&buf[max_trx_id_offset..])),
+                mysql_log_t_from_trx_rseg_buf(&buf[max_trx_id_offset..]),
+                wsrep_xid_t_from_trx_rseg_buf(
+                    &buf[max_trx_id_offset + TRX_RSEG_WSREP_XID_INFO as usize..],
+                ),
+            )
+        } else {
+            (None, None, None)
+        };
 
         trx_rseg_t {
             format,
@@ -150,6 +186,133 @@ impl trx_rseg_t {
             wsrep_xid,
         }
     }
+
+    /// Resolves the undo log page referenced by an undo slot and checks that it is
+    /// a FIL_PAGE_UNDO_LOG page.
+    pub fn undo_slot_page<'a>(
+        reader: &TablespaceReader<'a>,
+        slot: u32,
+        page_no: u32,
+    ) -> Result<PageBuf<'a>, UndoSlotError> {
+        let page = reader.page(page_no).map_err(|err| UndoSlotError {
+            slot,
+            page_no,
+            reason: format!("failed to read page: {err}"),
+        })?;
+
+        if page.page_type != fil0fil::FIL_PAGE_UNDO_LOG {
+            return Err(UndoSlotError {
+                slot,
+                page_no,
+                reason: format!(
+                    "expected FIL_PAGE_UNDO_LOG, found {:?}",
+                    fil0fil::fil_page_type_t::from(page.page_type)
+                ),
+            });
+        }
+
+        Ok(page)
+    }
+
+    /// Walks the TRX_RSEG_HISTORY list and reads the undo log header of each node, from
+    /// the first (newest) to the last, stopping after `limit` entries. The list node of an
+    /// undo log header is at TRX_UNDO_HISTORY_NODE within the header.
+    pub fn history(
+        &self,
+        reader: &TablespaceReader<'_>,
+        limit: usize,
+    ) -> std::io::Result<Vec<HistoryEntry>> {
+        let mut entries = Vec::new();
+
+        for node in fut0lst::FlstWalker::new(reader, &self.history).take(limit) {
+            let (addr, _) = node?;
+            let hdr_addr = history_node_hdr_addr(addr, reader.page_size())?;
+
+            let page = reader.page(hdr_addr.page)?;
+            entries.push(HistoryEntry {
+                page_no: hdr_addr.page,
+                offset: hdr_addr.boffset,
+                hdr: trx0undo::trx_undo_log_hdr_t::from_page(page.buf(), hdr_addr.boffset),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the addresses of the undo log headers on the TRX_RSEG_HISTORY list, from
+    /// the first (newest) to the last. The walk stops at a FIL_NULL link or after
+    /// `history_size` nodes, so a corrupted list cannot loop forever.
+    pub fn history_chain(
+        &self,
+        reader: &TablespaceReader<'_>,
+    ) -> std::io::Result<Vec<fil0fil::fil_addr_t>> {
+        fut0lst::FlstWalker::new(reader, &self.history)
+            .take(self.history_size as usize)
+            .map(|node| history_node_hdr_addr(node?.0, reader.page_size()))
+            .collect()
+    }
+
+    /// Checks all used undo slots point to undo log pages. Instead of failing on
+    /// the first broken slot, returns all of them sorted by the slot number.
+    pub fn verify_undo_slots(&self, reader: &TablespaceReader<'_>) -> Vec<UndoSlotError> {
+        let mut slots = self.undo_slots.iter().collect::<Vec<_>>();
+        slots.sort();
+
+        slots
+            .into_iter()
+            .filter(|(_, page_no)| **page_no != 0 && **page_no != fil0fil::FIL_NULL)
+            .filter_map(|(slot, page_no)| Self::undo_slot_page(reader, *slot, *page_no).err())
+            .collect()
+    }
+}
+
+/// An undo log header on the TRX_RSEG_HISTORY list of a rollback segment.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HistoryEntry {
+    /// Page number of the undo log header page.
+    pub page_no: u32,
+    /// Offset of the undo log header on the page.
+    pub offset: u16,
+    pub hdr: trx0undo::trx_undo_log_hdr_t,
+}
+
+/// An undo slot of a rollback segment that does not reference a valid undo log page.
+#[derive(Debug)]
+pub struct UndoSlotError {
+    pub slot: u32,
+    pub page_no: u32,
+    pub reason: String,
+}
+
+impl Display for UndoSlotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "undo slot {} references page {}: {}",
+            self.slot, self.page_no, self.reason
+        )
+    }
+}
+
+impl std::error::Error for UndoSlotError {}
+
+/// Converts the address of a TRX_UNDO_HISTORY_NODE to the address of its undo log header.
+fn history_node_hdr_addr(
+    addr: fil0fil::fil_addr_t,
+    page_size: usize,
+) -> std::io::Result<fil0fil::fil_addr_t> {
+    let offset = (addr.boffset as u32)
+        .checked_sub(trx0undo::TRX_UNDO_HISTORY_NODE)
+        .filter(|offset| (offset + trx0undo::TRX_UNDO_LOG_OLD_HDR_SIZE) as usize <= page_size)
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("history node at {addr:?} is not inside an undo log header"),
+            )
+        })?;
+
+    Ok(fil0fil::fil_addr_t::new(addr.page, offset as u16))
 }
 
 pub fn mysql_log_t_from_trx_rseg_buf(buf: &[u8]) -> Option<mysql_log_t> {
@@ -237,3 +400,211 @@ impl Debug for UndoSlotPrinter {
         write!(f, "({} -> {})", self.0, self.1)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_verify_undo_slots() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size * 4];
+
+        let set_type = |buf: &mut [u8], page_no: usize, page_type: u16| {
+            let page = &mut buf[page_no * page_size..];
+            mach::mach_write_to_4(
+                &mut page[fil0fil::FIL_PAGE_OFFSET as usize..],
+                page_no as u32,
+            )
+            .unwrap();
+            mach::mach_write_to_2(&mut page[fil0fil::FIL_PAGE_TYPE as usize..], page_type).unwrap();
+        };
+
+        set_type(&mut buf, 1, fil0fil::FIL_PAGE_TYPE_SYS);
+        set_type(&mut buf, 2, fil0fil::FIL_PAGE_INDEX);
+        set_type(&mut buf, 3, fil0fil::FIL_PAGE_UNDO_LOG);
+
+        let rseg = &mut buf[page_size..page_size * 2];
+        for i in 0..TRX_RSEG_N_SLOTS(page_size) {
+            let offset = (TRX_RSEG + TRX_RSEG_UNDO_SLOTS + i * TRX_RSEG_SLOT_SIZE) as usize;
+            let page_no = match i {
+                0 => 2,
+                1 => 3,
+                2 => 99,
+                _ => fil0fil::FIL_NULL,
+            };
+            mach::mach_write_to_4(&mut rseg[offset..], page_no).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let rseg = trx_rseg_t::from_page(reader.page(1).unwrap().buf());
+        assert_eq!(rseg.undo_slots.len(), 3);
+
+        let errors = rseg.verify_undo_slots(&reader);
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].slot, errors[0].page_no), (0, 2));
+        assert_eq!((errors[1].slot, errors[1].page_no), (2, 99));
+
+        assert!(trx_rseg_t::undo_slot_page(&reader, 1, 3).is_ok());
+    }
+
+    #[test]
+    fn test_rseg_format() {
+        let page_size = 16 * 1024;
+        let mut page = vec![0u8; page_size];
+
+        // garbage in the fields after the undo slots.
+        let max_trx_id = (TRX_RSEG + TRX_RSEG_MAX_TRX_ID(page_size)) as usize;
+        mach::mach_write_to_8(&mut page[max_trx_id..], 1234).unwrap();
+        page[max_trx_id + TRX_RSEG_BINLOG_NAME_OFFSET as usize] = b'x';
+        mach::mach_write_to_4(
+            &mut page[max_trx_id + TRX_RSEG_WSREP_XID_FORMAT as usize..],
+            0xdead,
+        )
+        .unwrap();
+
+        mach::mach_write_to_4(
+            &mut page[(TRX_RSEG + TRX_RSEG_FORMAT) as usize..],
+            TRX_RSEG_FORMAT_PRE_10_3_5,
+        )
+        .unwrap();
+
+        let rseg = trx_rseg_t::from_page(&page);
+        assert_eq!(rseg.format, RsegFormat::PreMariaDB10_3_5);
+        assert_eq!(rseg.max_trx_id, None);
+        assert!(rseg.mysql_log.is_none());
+        assert!(rseg.wsrep_xid.is_none());
+
+        // the modern format trusts the fields.
+        mach::mach_write_to_4(
+            &mut page[max_trx_id + TRX_RSEG_WSREP_XID_FORMAT as usize..],
+            0,
+        )
+        .unwrap();
+        mach::mach_write_to_4(&mut page[(TRX_RSEG + TRX_RSEG_FORMAT) as usize..], 0).unwrap();
+
+        let rseg = trx_rseg_t::from_page(&page);
+        assert_eq!(rseg.format, RsegFormat::Modern);
+        assert_eq!(rseg.max_trx_id, Some(1234));
+        assert_eq!(rseg.mysql_log.unwrap().log_name, "x");
+    }
+
+    #[test]
+    fn test_history() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size * 5];
+        let hdr_offset = (trx0undo::TRX_UNDO_SEG_HDR + trx0undo::TRX_UNDO_SEG_HDR_SIZE) as u16;
+        let node_addr = |page_no| {
+            fil0fil::fil_addr_t::new(page_no, hdr_offset + trx0undo::TRX_UNDO_HISTORY_NODE as u16)
+        };
+
+        // history list: page 3 (trx_no 20) -> page 4 (trx_no 10, purged).
+        for (page_no, trx_no, needs_purge, prev, next) in [
+            (3u32, 20u64, 1u16, fil0fil::fil_addr_t::null(), node_addr(4)),
+            (4, 10, 0, node_addr(3), fil0fil::fil_addr_t::null()),
+        ] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            mach::mach_write_to_4(&mut page[fil0fil::FIL_PAGE_OFFSET as usize..], page_no).unwrap();
+
+            let hdr = &mut page[hdr_offset as usize..];
+            mach::mach_write_to_8(&mut hdr[trx0undo::TRX_UNDO_TRX_ID as usize..], trx_no - 1)
+                .unwrap();
+            mach::mach_write_to_8(&mut hdr[trx0undo::TRX_UNDO_TRX_NO as usize..], trx_no).unwrap();
+            mach::mach_write_to_2(
+                &mut hdr[trx0undo::TRX_UNDO_NEEDS_PURGE as usize..],
+                needs_purge,
+            )
+            .unwrap();
+            hdr[trx0undo::TRX_UNDO_DICT_TRANS as usize] = 1;
+            mach::mach_write_to_8(&mut hdr[trx0undo::TRX_UNDO_TABLE_ID as usize..], 42).unwrap();
+
+            let node = trx0undo::TRX_UNDO_HISTORY_NODE as usize;
+            prev.write_to(&mut hdr[node + fut0lst::FLST_PREV as usize..])
+                .unwrap();
+            next.write_to(&mut hdr[node + fut0lst::FLST_NEXT as usize..])
+                .unwrap();
+        }
+
+        let rseg = &mut buf[page_size..page_size * 2];
+        let history = (TRX_RSEG + TRX_RSEG_HISTORY) as usize;
+        mach::mach_write_to_4(&mut rseg[(TRX_RSEG + TRX_RSEG_HISTORY_SIZE) as usize..], 2).unwrap();
+        mach::mach_write_to_4(&mut rseg[history..], 2).unwrap();
+        node_addr(3).write_to(&mut rseg[history + 4..]).unwrap();
+        node_addr(4)
+            .write_to(&mut rseg[history + 4 + fil0fil::FIL_ADDR_SIZE as usize..])
+            .unwrap();
+        for i in 0..TRX_RSEG_N_SLOTS(page_size) {
+            let offset = (TRX_RSEG + TRX_RSEG_UNDO_SLOTS + i * TRX_RSEG_SLOT_SIZE) as usize;
+            mach::mach_write_to_4(&mut rseg[offset..], fil0fil::FIL_NULL).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let rseg = trx_rseg_t::from_page(reader.page(1).unwrap().buf());
+
+        let entries = rseg.history(&reader, 100).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].page_no, entries[0].offset), (3, hdr_offset));
+        assert_eq!(entries[0].hdr.trx_no, 20);
+        assert!(entries[0].hdr.needs_purge);
+        assert_eq!(entries[0].hdr.dict_table_id, Some(42));
+        assert_eq!((entries[1].page_no, entries[1].hdr.trx_no), (4, 10));
+        assert!(!entries[1].hdr.needs_purge);
+
+        // the walk is capped.
+        assert_eq!(rseg.history(&reader, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_history_chain() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size * 4];
+        let first = (trx0undo::TRX_UNDO_SEG_HDR + trx0undo::TRX_UNDO_SEG_HDR_SIZE) as u16;
+        let second = first + trx0undo::TRX_UNDO_LOG_OLD_HDR_SIZE as u16;
+        let node_addr =
+            |offset| fil0fil::fil_addr_t::new(3, offset + trx0undo::TRX_UNDO_HISTORY_NODE as u16);
+
+        // history list: page 3 at `second` -> page 3 at `first`.
+        let page = &mut buf[3 * page_size..4 * page_size];
+        mach::mach_write_to_4(&mut page[fil0fil::FIL_PAGE_OFFSET as usize..], 3).unwrap();
+        for (offset, prev, next) in [
+            (second, fil0fil::fil_addr_t::null(), node_addr(first)),
+            (first, node_addr(second), fil0fil::fil_addr_t::null()),
+        ] {
+            let node = (offset + trx0undo::TRX_UNDO_HISTORY_NODE as u16) as usize;
+            prev.write_to(&mut page[node + fut0lst::FLST_PREV as usize..])
+                .unwrap();
+            next.write_to(&mut page[node + fut0lst::FLST_NEXT as usize..])
+                .unwrap();
+        }
+
+        // the base node length is corrupted, only history_size bounds the walk.
+        let rseg = &mut buf[page_size..page_size * 2];
+        let history = (TRX_RSEG + TRX_RSEG_HISTORY) as usize;
+        mach::mach_write_to_4(&mut rseg[(TRX_RSEG + TRX_RSEG_HISTORY_SIZE) as usize..], 2).unwrap();
+        mach::mach_write_to_4(&mut rseg[history..], u32::MAX).unwrap();
+        node_addr(second)
+            .write_to(&mut rseg[history + 4..])
+            .unwrap();
+        node_addr(first)
+            .write_to(&mut rseg[history + 4 + fil0fil::FIL_ADDR_SIZE as usize..])
+            .unwrap();
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let rseg = trx_rseg_t::from_page(reader.page(1).unwrap().buf());
+        assert_eq!(
+            rseg.history_chain(&reader).unwrap(),
+            vec![
+                fil0fil::fil_addr_t::new(3, second),
+                fil0fil::fil_addr_t::new(3, first)
+            ]
+        );
+
+        // a list looping back to its first node stops after history_size nodes.
+        let node = first as usize + trx0undo::TRX_UNDO_HISTORY_NODE as usize;
+        node_addr(second)
+            .write_to(&mut buf[3 * page_size + node + fut0lst::FLST_NEXT as usize..])
+            .unwrap();
+        let reader = TablespaceReader::new(&buf, page_size);
+        assert_eq!(rseg.history_chain(&reader).unwrap().len(), 2);
+    }
+}
@@ -1,6 +1,6 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, ops::Range};
 
-use crate::{fsp0types, mach, wsrep};
+use crate::{fil0fil, fsp0types, mach, page_buf::PageBuf, tablespace::TablespaceReader, wsrep};
 
 // The offset of the transaction system header on the page
 pub const TRX_SYS: u32 = fsp0types::FSEG_PAGE_DATA;
@@ -23,6 +23,23 @@ pub const TRX_SYS_RSEG_SPACE: u32 = 0;
 pub const TRX_SYS_RSEG_PAGE_NO: u32 = 4;
 // Size of a rollback segment specification slot
 pub const TRX_SYS_RSEG_SLOT_SIZE: u32 = 8;
+// Number of rollback segment specification slots
+pub const TRX_SYS_N_RSEGS: u32 = 128;
+
+/// Returns the offset of the rollback segment specification slot of the rseg id
+/// relative to TRX_SYS.
+pub fn rseg_slot_offset(rseg_id: u32) -> u32 {
+    TRX_SYS_RSEGS + rseg_id * TRX_SYS_RSEG_SLOT_SIZE
+}
+
+/// Returns the rseg id of the rollback segment specification slot starting at the offset
+/// relative to TRX_SYS, if any. Inverse of `rseg_slot_offset()`.
+pub fn rseg_id_from_offset(offset: u32) -> Option<u32> {
+    let rel = offset.checked_sub(TRX_SYS_RSEGS)?;
+    let rseg_id = rel / TRX_SYS_RSEG_SLOT_SIZE;
+
+    (rel % TRX_SYS_RSEG_SLOT_SIZE == 0 && rseg_id < TRX_SYS_N_RSEGS).then_some(rseg_id)
+}
 
 // Maximum length of MySQL binlog file name, in bytes.
 pub const TRX_SYS_MYSQL_LOG_NAME_LEN: usize = 512;
@@ -111,11 +128,35 @@ pub const TRX_SYS_WSREP_XID_DATA: u32 = 16;
 // The offset of the doublewrite buffer header on the trx system header page */
 pub const TRX_SYS_DOUBLEWRITE_END: u32 = 200;
 
+/// fseg header of the segment containing the doublewrite buffer
+pub const TRX_SYS_DOUBLEWRITE_FSEG: u32 = 0;
+/// 4-byte magic number which shows if we already have created the doublewrite buffer
+pub const TRX_SYS_DOUBLEWRITE_MAGIC: u32 = fsp0types::FSEG_HEADER_SIZE as u32;
+/// page number of the first page in the first sequence of 64 (= FSP_EXTENT_SIZE)
+/// consecutive pages in the doublewrite buffer
+pub const TRX_SYS_DOUBLEWRITE_BLOCK1: u32 = 4 + fsp0types::FSEG_HEADER_SIZE as u32;
+/// page number of the first page in the second sequence of 64 consecutive pages in the
+/// doublewrite buffer
+pub const TRX_SYS_DOUBLEWRITE_BLOCK2: u32 = 8 + fsp0types::FSEG_HEADER_SIZE as u32;
+/// we repeat TRX_SYS_DOUBLEWRITE_MAGIC, TRX_SYS_DOUBLEWRITE_BLOCK1,
+/// TRX_SYS_DOUBLEWRITE_BLOCK2 so that if the trx sys header is half-written to disk, we
+/// still may be able to recover the information
+pub const TRX_SYS_DOUBLEWRITE_REPEAT: u32 = 12;
+/// Contents of TRX_SYS_DOUBLEWRITE_MAGIC
+pub const TRX_SYS_DOUBLEWRITE_MAGIC_N: u32 = 536853855;
+
+/// Size of the doublewrite block in pages
+#[allow(non_snake_case)]
+pub fn TRX_SYS_DOUBLEWRITE_BLOCK_SIZE(page_size: usize) -> u32 {
+    fsp0types::FSP_EXTENT_SIZE(page_size.trailing_zeros())
+}
+
 /// Transaction system header structure.
 /// This structure is stored in the page TRX_SYS_PAGE_NO of the system tablespace and in the undo
 /// tablespaces.
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct trx_sys_t {
     pub id_store: u64,
     pub fseg_header: fsp0types::fseg_header_t,
@@ -123,11 +164,15 @@ pub struct trx_sys_t {
     pub wsrep_xid: Option<wsrep::wsrep_xid_t>,
     pub mysql_log: Option<mysql_log_t>,
     pub doublewrite: trx_sys_doublewrite_t,
+    /// Copy of the page bytes starting at TRX_SYS. See `raw_region()`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    raw: Vec<u8>,
 }
 
 /// MariaDB binlog info structure stored in the trx_sys_t header.
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct mysql_log_t {
     pub log_offset: u64,
     pub log_name: String,
@@ -136,6 +181,7 @@ pub struct mysql_log_t {
 /// Doublewrite buffer info structure stored in the trx_sys_t header.
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct trx_sys_doublewrite_t {
     pub fseg: fsp0types::fseg_header_t,
     pub magic: u32,
@@ -152,6 +198,7 @@ pub struct trx_sys_doublewrite_t {
 /// Part of the trx_sys_t structure.
 #[allow(non_camel_case_types)]
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct trx_sys_rseg_t {
     pub space_id: u32,
     pub page_no: u32,
@@ -166,6 +213,23 @@ impl trx_sys_rseg_t {
 
         trx_sys_rseg_t { space_id, page_no }
     }
+
+    pub fn is_unused(&self) -> bool {
+        self.space_id == fil0fil::FIL_NULL
+    }
+}
+
+impl Debug for trx_sys_t {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("trx_sys_t")
+            .field("id_store", &self.id_store)
+            .field("fseg_header", &self.fseg_header)
+            .field("rsegs", &self.rsegs)
+            .field("wsrep_xid", &self.wsrep_xid)
+            .field("mysql_log", &self.mysql_log)
+            .field("doublewrite", &self.doublewrite)
+            .finish()
+    }
 }
 
 impl Debug for trx_sys_rseg_t {
@@ -237,13 +301,17 @@ impl trx_sys_doublewrite_t {
         let fseg =
             fsp0types::fseg_header_t::from_buf(&buf[0..fsp0types::FSEG_HEADER_SIZE as usize]);
 
-        let magic = mach::mach_read_from_4(&buf[10..]);
-        let block1 = mach::mach_read_from_4(&buf[14..]);
-        let block2 = mach::mach_read_from_4(&buf[18..]);
+        let magic = mach::mach_read_from_4(&buf[TRX_SYS_DOUBLEWRITE_MAGIC as usize..]);
+        let block1 = mach::mach_read_from_4(&buf[TRX_SYS_DOUBLEWRITE_BLOCK1 as usize..]);
+        let block2 = mach::mach_read_from_4(&buf[TRX_SYS_DOUBLEWRITE_BLOCK2 as usize..]);
 
-        let magic_repeat = mach::mach_read_from_4(&buf[22..]);
-        let block1_repeat = mach::mach_read_from_4(&buf[26..]);
-        let block2_repeat = mach::mach_read_from_4(&buf[30..]);
+        let repeat = TRX_SYS_DOUBLEWRITE_REPEAT as usize;
+        let magic_repeat =
+            mach::mach_read_from_4(&buf[TRX_SYS_DOUBLEWRITE_MAGIC as usize + repeat..]);
+        let block1_repeat =
+            mach::mach_read_from_4(&buf[TRX_SYS_DOUBLEWRITE_BLOCK1 as usize + repeat..]);
+        let block2_repeat =
+            mach::mach_read_from_4(&buf[TRX_SYS_DOUBLEWRITE_BLOCK2 as usize + repeat..]);
 
         trx_sys_doublewrite_t {
             fseg,
@@ -255,6 +323,80 @@ impl trx_sys_doublewrite_t {
             block2_repeat,
         }
     }
+
+    /// Returns whether the doublewrite buffer has been created: the magic number is set
+    /// and the block locations agree with their repeated copies.
+    pub fn is_valid(&self) -> bool {
+        self.magic == TRX_SYS_DOUBLEWRITE_MAGIC_N
+            && self.magic_repeat == self.magic
+            && self.block1_repeat == self.block1
+            && self.block2_repeat == self.block2
+    }
+
+    /// Returns the page ranges of the two doublewrite blocks, if the buffer is valid.
+    pub fn blocks(&self, page_size: usize) -> Option<[Range<u32>; 2]> {
+        if !self.is_valid() {
+            return None;
+        }
+
+        let size = TRX_SYS_DOUBLEWRITE_BLOCK_SIZE(page_size);
+        Some([
+            self.block1..self.block1.saturating_add(size),
+            self.block2..self.block2.saturating_add(size),
+        ])
+    }
+
+    /// Cross-checks a page copy read from the doublewrite buffer of the system tablespace.
+    /// Returns the (space_id, page_no) of the page it is a copy of, None if the slot was
+    /// never written, or an error if the copy of a system tablespace page can not be used
+    /// to recover the page: its checksum is wrong, or its FIL_PAGE_OFFSET points into the
+    /// doublewrite buffer or beyond the end of the system tablespace. The copies of other
+    /// tablespaces are not verified, as their checksum depends on their own flags.
+    pub fn check_copy(
+        &self,
+        reader: &TablespaceReader<'_>,
+        copy: &PageBuf<'_>,
+    ) -> std::io::Result<Option<(u32, u32)>> {
+        if copy.buf().iter().all(|&b| b == 0) {
+            return Ok(None);
+        }
+
+        if copy.space_id == 0 {
+            copy.corrupted(None)?;
+
+            let in_doublewrite = self
+                .blocks(reader.page_size())
+                .is_some_and(|blocks| blocks.iter().any(|block| block.contains(&copy.page_no)));
+
+            if in_doublewrite || copy.page_no >= reader.npages() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("copy of invalid system tablespace page {}", copy.page_no),
+                ));
+            }
+        }
+
+        Ok(Some((copy.space_id, copy.page_no)))
+    }
+}
+
+/// Reads the pages of both doublewrite blocks of the system tablespace, block1 first.
+pub fn read_doublewrite<'a>(
+    reader: &TablespaceReader<'a>,
+    dw: &trx_sys_doublewrite_t,
+) -> std::io::Result<Vec<PageBuf<'a>>> {
+    let Some(blocks) = dw.blocks(reader.page_size()) else {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "the doublewrite buffer is not initialized",
+        ));
+    };
+
+    blocks
+        .into_iter()
+        .flatten()
+        .map(|page_no| reader.page(page_no))
+        .collect()
 }
 
 impl trx_sys_t {
@@ -266,11 +408,11 @@ impl trx_sys_t {
         let id_store = mach::mach_read_from_8(&buf[TRX_SYS_TRX_ID_STORE as usize..]); // 0
         let fseg_header = fsp0types::fseg_header_t::from_buf(&buf[TRX_SYS_FSEG_HEADER as usize..]); // 8
 
-        let num_slots = 127;
+        let num_slots = TRX_SYS_N_RSEGS;
         let mut rsegs: Vec<trx_sys_rseg_t> = Vec::with_capacity(num_slots as usize);
 
         for i in 0..num_slots {
-            let slot_offset = TRX_SYS_RSEGS + i * TRX_SYS_RSEG_SLOT_SIZE; // 18 + i*8
+            let slot_offset = rseg_slot_offset(i); // 18 + i*8
             let slot = trx_sys_rseg_t::from_buf(&buf[slot_offset as usize..]);
             rsegs.push(slot);
         }
@@ -288,6 +430,159 @@ impl trx_sys_t {
             wsrep_xid: wsrep_xid_t_from_trx_sys_buf(wsrep_xid_buf),
             mysql_log: mysql_log_t::from_trx_sys_buf(mysql_log_buf),
             doublewrite: trx_sys_doublewrite_t::from_buf(doublewrite_buf),
+            raw: buf[..page_size - TRX_SYS as usize].to_vec(),
+        }
+    }
+
+    /// Returns `len` bytes of the page at `offset` from the page start, e.g. the unused
+    /// space between the structures that other vendors may repurpose. Only the part of
+    /// the page from TRX_SYS up to the FIL trailer is retained.
+    pub fn raw_region(&self, offset: usize, len: usize) -> Option<&[u8]> {
+        let start = offset.checked_sub(TRX_SYS as usize)?;
+        let end = start.checked_add(len)?;
+
+        if end > self.raw.len() - fil0fil::FIL_PAGE_DATA_END as usize {
+            return None;
+        }
+
+        self.raw.get(start..end)
+    }
+
+    /// Returns the rollback segment specification slot of the rseg id, or None if the id is
+    /// out of range or the slot is unused.
+    pub fn rseg_slot(&self, rseg_id: u32) -> Option<&trx_sys_rseg_t> {
+        self.rsegs
+            .get(rseg_id as usize)
+            .filter(|slot| !slot.is_unused())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_doublewrite() {
+        let page_size = 16384;
+        let mut page = vec![0u8; page_size];
+        let dblwr = page_size - TRX_SYS_DOUBLEWRITE_END as usize;
+
+        let header = trx_sys_t::from_page(&page).doublewrite;
+        assert!(!header.is_valid());
+        assert_eq!(header.blocks(page_size), None);
+
+        for repeat in [0, TRX_SYS_DOUBLEWRITE_REPEAT as usize] {
+            for (field, value) in [
+                (TRX_SYS_DOUBLEWRITE_MAGIC, TRX_SYS_DOUBLEWRITE_MAGIC_N),
+                (TRX_SYS_DOUBLEWRITE_BLOCK1, 64),
+                (TRX_SYS_DOUBLEWRITE_BLOCK2, 128),
+            ] {
+                mach::mach_write_to_4(&mut page[dblwr + field as usize + repeat..], value).unwrap();
+            }
         }
+
+        let header = trx_sys_t::from_page(&page).doublewrite;
+        assert!(header.is_valid());
+        assert_eq!(header.blocks(page_size), Some([64..128, 128..192]));
+
+        // a torn write of the header leaves the repeated copy behind.
+        let block2_repeat =
+            dblwr + (TRX_SYS_DOUBLEWRITE_BLOCK2 + TRX_SYS_DOUBLEWRITE_REPEAT) as usize;
+        mach::mach_write_to_4(&mut page[block2_repeat..], 0).unwrap();
+        assert!(!trx_sys_t::from_page(&page).doublewrite.is_valid());
+    }
+
+    #[test]
+    fn test_read_doublewrite() {
+        let page_size = 16384;
+        let flags = 0x15;
+        let mut buf = vec![0u8; page_size * 192];
+
+        let dblwr = 6 * page_size - TRX_SYS_DOUBLEWRITE_END as usize;
+        for repeat in [0, TRX_SYS_DOUBLEWRITE_REPEAT as usize] {
+            for (field, value) in [
+                (TRX_SYS_DOUBLEWRITE_MAGIC, TRX_SYS_DOUBLEWRITE_MAGIC_N),
+                (TRX_SYS_DOUBLEWRITE_BLOCK1, 64),
+                (TRX_SYS_DOUBLEWRITE_BLOCK2, 128),
+            ] {
+                mach::mach_write_to_4(&mut buf[dblwr + field as usize + repeat..], value).unwrap();
+            }
+        }
+
+        // copies of space 0 page 7, space 3 page 9, a doublewrite page and a torn copy.
+        for (slot, space_id, page_no) in [(64, 0, 7), (65, 3, 9), (66, 0, 100), (128, 0, 8)] {
+            let page = &mut buf[slot * page_size..(slot + 1) * page_size];
+            crate::page_buf::make_undo_log_page(page, space_id, page_no, 100, flags).unwrap();
+        }
+        buf[128 * page_size + 200] ^= 1;
+        // the copy of another tablespace is not checksum verified.
+        buf[65 * page_size + 200] ^= 1;
+
+        let reader = TablespaceReader::new(&buf, page_size).with_flags_override(flags);
+        let dw = trx_sys_t::from_page(reader.page(5).unwrap().buf()).doublewrite;
+        let copies = read_doublewrite(&reader, &dw).unwrap();
+        assert_eq!(copies.len(), 128);
+
+        assert_eq!(dw.check_copy(&reader, &copies[0]).unwrap(), Some((0, 7)));
+        assert_eq!(dw.check_copy(&reader, &copies[1]).unwrap(), Some((3, 9)));
+        assert!(dw.check_copy(&reader, &copies[2]).is_err());
+        assert_eq!(dw.check_copy(&reader, &copies[3]).unwrap(), None);
+        assert!(dw.check_copy(&reader, &copies[64]).is_err());
+
+        let dw = trx_sys_t::from_page(reader.page(0).unwrap().buf()).doublewrite;
+        assert!(read_doublewrite(&reader, &dw).is_err());
+    }
+
+    #[test]
+    fn test_rseg_slot() {
+        let page_size = 16384;
+        let mut page = vec![0u8; page_size];
+        for rseg_id in 0..TRX_SYS_N_RSEGS {
+            let slot = (TRX_SYS + rseg_slot_offset(rseg_id)) as usize;
+            let (space_id, page_no) = match rseg_id {
+                0 => (0, 6),
+                3 => (2, 3),
+                _ => (fil0fil::FIL_NULL, fil0fil::FIL_NULL),
+            };
+            mach::mach_write_to_4(&mut page[slot + TRX_SYS_RSEG_SPACE as usize..], space_id)
+                .unwrap();
+            mach::mach_write_to_4(&mut page[slot + TRX_SYS_RSEG_PAGE_NO as usize..], page_no)
+                .unwrap();
+        }
+
+        let trx_sys = trx_sys_t::from_page(&page);
+        assert_eq!(trx_sys.rsegs.len(), TRX_SYS_N_RSEGS as usize);
+
+        let slot = trx_sys.rseg_slot(3).unwrap();
+        assert_eq!((slot.space_id, slot.page_no), (2, 3));
+        assert!(trx_sys.rseg_slot(0).is_some());
+        assert!(trx_sys.rseg_slot(1).is_none());
+        assert!(trx_sys.rseg_slot(TRX_SYS_N_RSEGS).is_none());
+
+        assert_eq!(TRX_SYS + rseg_slot_offset(3), 56 + 3 * 8);
+        assert_eq!(rseg_id_from_offset(rseg_slot_offset(3)), Some(3));
+        assert_eq!(rseg_id_from_offset(rseg_slot_offset(3) + 4), None);
+        assert_eq!(rseg_id_from_offset(TRX_SYS_RSEGS - 8), None);
+        assert_eq!(rseg_id_from_offset(rseg_slot_offset(TRX_SYS_N_RSEGS)), None);
+    }
+
+    #[test]
+    fn test_raw_region() {
+        let page_size = 16384;
+        let mut page = vec![0u8; page_size];
+        // a vendor marker right after the rollback segment slots.
+        let reserved = (TRX_SYS + rseg_slot_offset(TRX_SYS_N_RSEGS)) as usize;
+        page[reserved..reserved + 4].copy_from_slice(b"XTRA");
+        page[page_size - 9] = 0x5a;
+
+        let trx_sys = trx_sys_t::from_page(&page);
+        assert_eq!(trx_sys.raw_region(reserved, 4), Some(&b"XTRA"[..]));
+        assert_eq!(trx_sys.raw_region(reserved + 4, 1), Some(&[0u8][..]));
+        assert_eq!(trx_sys.raw_region(page_size - 9, 1), Some(&[0x5a][..]));
+
+        // the FIL header and trailer are not part of the trx_sys header.
+        assert_eq!(trx_sys.raw_region(TRX_SYS as usize - 1, 2), None);
+        assert_eq!(trx_sys.raw_region(page_size - 9, 2), None);
+        assert_eq!(trx_sys.raw_region(usize::MAX, 2), None);
     }
 }
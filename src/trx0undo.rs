@@ -1,6 +1,9 @@
-use std::fmt::Debug;
+use std::{
+    cmp::min,
+    fmt::{Debug, Display},
+};
 
-use crate::{fsp0types, fut0lst, univ, wsrep};
+use crate::{fil0fil, fsp0types, fut0lst, mach, tablespace::TablespaceReader, univ, wsrep};
 
 // Transaction undo log
 // -------------------------------------------------------------
@@ -31,6 +34,7 @@ pub fn TRX_UNDO_PAGE_REUSE_LIMIT(page_size: u32) -> u32 {
 
 #[allow(non_camel_case_types)]
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct trx_undo_page_t {
     /// unused; 0 (before MariaDB 10.3.1: 1=TRX_UNDO_INSERT or 2=TRX_UNDO_UPDATE).
     pub page_type: u16,
@@ -65,6 +69,116 @@ impl trx_undo_page_t {
             node,
         }
     }
+
+    /// Walks the undo log records of the page from `start` to `free`. The walk stops at
+    /// the first record whose next-record pointer does not move forward within the page.
+    pub fn records(&self, page: &[u8]) -> Vec<UndoRec> {
+        let free = min(self.free as usize, page.len());
+        let mut offset = self.start as usize;
+        let mut records = vec![];
+
+        while offset + TRX_UNDO_REC_HDR_SIZE <= free {
+            let next = mach::mach_read_from_2(&page[offset..]) as usize;
+            if next <= offset + TRX_UNDO_REC_HDR_SIZE || next > free {
+                break;
+            }
+
+            let type_cmpl = page[offset + 2];
+            records.push(UndoRec {
+                offset: offset as u16,
+                len: (next - offset) as u16,
+                rec_type: type_cmpl & (TRX_UNDO_CMPL_INFO_MULT - 1),
+                cmpl_info: (type_cmpl & !TRX_UNDO_UPD_EXTERN) / TRX_UNDO_CMPL_INFO_MULT,
+                extern_update: type_cmpl & TRX_UNDO_UPD_EXTERN != 0,
+                next: next as u16,
+            });
+
+            offset = next;
+        }
+
+        records
+    }
+}
+
+// Undo log records
+// -------------------------------------------------------------
+
+/// Size of the undo log record prefix: the next record offset and the type byte.
+pub const TRX_UNDO_REC_HDR_SIZE: usize = 3;
+
+/// RENAME TABLE
+pub const TRX_UNDO_RENAME_TABLE: u8 = 9;
+/// insert a metadata pseudo-record for instant ALTER TABLE
+pub const TRX_UNDO_INSERT_METADATA: u8 = 10;
+/// fresh insert into clustered index
+pub const TRX_UNDO_INSERT_REC: u8 = 11;
+/// update of a non-delete-marked record
+pub const TRX_UNDO_UPD_EXIST_REC: u8 = 12;
+/// update of a delete marked record to a not delete marked record; also the fields of
+/// the record can change
+pub const TRX_UNDO_UPD_DEL_REC: u8 = 13;
+/// delete marking of a record; fields do not change
+pub const TRX_UNDO_DEL_MARK_REC: u8 = 14;
+/// Bulk insert into an empty table
+pub const TRX_UNDO_EMPTY: u8 = 15;
+/// compilation info is multiplied by this and ORed to the type above
+pub const TRX_UNDO_CMPL_INFO_MULT: u8 = 16;
+/// This bit can be ORed to type_cmpl to denote that we updated external storage fields
+pub const TRX_UNDO_UPD_EXTERN: u8 = 128;
+
+/// Returns the name of the undo log record type.
+pub fn undo_rec_type_name(rec_type: u8) -> &'static str {
+    match rec_type {
+        TRX_UNDO_RENAME_TABLE => "RENAME_TABLE",
+        TRX_UNDO_INSERT_METADATA => "INSERT_METADATA",
+        TRX_UNDO_INSERT_REC => "INSERT_REC",
+        TRX_UNDO_UPD_EXIST_REC => "UPD_EXIST_REC",
+        TRX_UNDO_UPD_DEL_REC => "UPD_DEL_REC",
+        TRX_UNDO_DEL_MARK_REC => "DEL_MARK_REC",
+        TRX_UNDO_EMPTY => "EMPTY",
+        _ => "UNKNOWN",
+    }
+}
+
+/// An undo log record found on an undo log page by `trx_undo_page_t::records()`.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct UndoRec {
+    /// Byte offset of the record on the page.
+    pub offset: u16,
+    /// Length of the record in bytes, up to the next record.
+    pub len: u16,
+    /// TRX_UNDO_INSERT_REC, ...
+    pub rec_type: u8,
+    /// Compilation info of the update (UPD_NODE_NO_ORD_CHANGE, ...).
+    pub cmpl_info: u8,
+    /// Whether externally stored fields were updated (TRX_UNDO_UPD_EXTERN).
+    pub extern_update: bool,
+    /// Byte offset of the next record on the page.
+    pub next: u16,
+}
+
+impl UndoRec {
+    /// Returns the bytes of the record on the page.
+    pub fn bytes<'a>(&self, page: &'a [u8]) -> &'a [u8] {
+        &page[self.offset as usize..self.next as usize]
+    }
+}
+
+impl Display for UndoRec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "undo rec at {}: type={} ({}), cmpl_info={}, extern={}, len={}, next={}",
+            self.offset,
+            undo_rec_type_name(self.rec_type),
+            self.rec_type,
+            self.cmpl_info,
+            self.extern_update,
+            self.len,
+            self.next
+        )
+    }
 }
 
 // An update undo log segment may contain several undo logs on its first page if the undo logs took
@@ -94,6 +208,239 @@ pub const TRX_UNDO_PAGE_LIST: u32 = 4 + fsp0types::FSEG_HEADER_SIZE as u32;
 pub const TRX_UNDO_SEG_HDR_SIZE: u32 =
     4 + fsp0types::FSEG_HEADER_SIZE as u32 + fut0lst::FLST_BASE_NODE_SIZE;
 
+// States of an undo log segment (TRX_UNDO_STATE)
+
+/// contains an undo log of an active transaction
+pub const TRX_UNDO_ACTIVE: u16 = 1;
+/// cached for quick reuse
+pub const TRX_UNDO_CACHED: u16 = 2;
+/// can be freed in purge when all undo data in it is removed
+pub const TRX_UNDO_TO_PURGE: u16 = 3;
+/// contains an undo log of a prepared transaction
+pub const TRX_UNDO_PREPARED: u16 = 5;
+
+/// Returns the name of the TRX_UNDO_STATE value.
+pub fn undo_state_name(state: u16) -> &'static str {
+    match state {
+        TRX_UNDO_ACTIVE => "ACTIVE",
+        TRX_UNDO_CACHED => "CACHED",
+        TRX_UNDO_TO_PURGE => "TO_PURGE",
+        TRX_UNDO_PREPARED => "PREPARED",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Undo log segment header, stored on the first page of an undo log segment.
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct trx_undo_seg_hdr_t {
+    /// TRX_UNDO_ACTIVE, ...
+    pub state: u16,
+    /// Offset of the last undo log header on the segment header page, 0 if none.
+    pub last_log: u16,
+    /// Header for the file segment which the undo log segment occupies.
+    pub fseg_header: fsp0types::fseg_header_t,
+    /// Base node for the list of pages in the undo log segment.
+    pub page_list: fut0lst::flst_base_node_t,
+}
+
+impl trx_undo_seg_hdr_t {
+    pub fn from_page(page: &[u8]) -> trx_undo_seg_hdr_t {
+        trx_undo_seg_hdr_t::from_buf(&page[TRX_UNDO_SEG_HDR as usize..])
+    }
+
+    /// Reads an undo log segment header from the given buffer.
+    /// The buffer must be at least `TRX_UNDO_SEG_HDR_SIZE` bytes long.
+    pub fn from_buf(buf: &[u8]) -> trx_undo_seg_hdr_t {
+        assert!(buf.len() >= TRX_UNDO_SEG_HDR_SIZE as usize);
+
+        trx_undo_seg_hdr_t {
+            state: mach::mach_read_from_2(&buf[TRX_UNDO_STATE as usize..]),
+            last_log: mach::mach_read_from_2(&buf[TRX_UNDO_LAST_LOG as usize..]),
+            fseg_header: fsp0types::fseg_header_t::from_buf(&buf[TRX_UNDO_FSEG_HEADER as usize..]),
+            page_list: fut0lst::flst_base_node_t::from_buf(&buf[TRX_UNDO_PAGE_LIST as usize..]),
+        }
+    }
+}
+
+/// Summary of an undo log segment found by `scan_undo_segments()`.
+#[derive(Debug)]
+pub struct UndoSegmentSummary {
+    /// Page number of the segment header page.
+    pub page_no: u32,
+    pub state: u16,
+    pub last_log: u16,
+    /// Number of pages in the segment page list.
+    pub page_count: u32,
+    /// Table id of the dictionary transaction of the last undo log, if any.
+    pub dict_table_id: Option<u64>,
+    /// Set if the last undo log header could not be read.
+    pub error: Option<String>,
+}
+
+impl Display for UndoSegmentSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "undo segment at page {}: state={} ({}), last_log={}, pages={}",
+            self.page_no,
+            undo_state_name(self.state),
+            self.state,
+            self.last_log,
+            self.page_count
+        )?;
+
+        if let Some(table_id) = self.dict_table_id {
+            write!(f, ", dict_table_id={table_id}")?;
+        }
+
+        if let Some(err) = &self.error {
+            write!(f, ", ERROR: {err}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether the undo log page is the first page of an undo log segment: it is
+/// the first page in the chain and the segment page list starts at this page.
+pub fn is_undo_seg_hdr_page(page_no: u32, page: &[u8]) -> bool {
+    let undo_page = trx_undo_page_t::from_page(page);
+    if !undo_page.node.prev.is_empty() {
+        return false;
+    }
+
+    let seg_hdr = trx_undo_seg_hdr_t::from_page(page);
+    !seg_hdr.page_list.is_empty() && seg_hdr.page_list.first.page == page_no
+}
+
+/// Scans all pages of an undo tablespace for undo log segment header pages.
+pub fn scan_undo_segments(reader: &TablespaceReader<'_>) -> Vec<UndoSegmentSummary> {
+    (0..reader.npages())
+        .filter_map(|page_no| reader.page(page_no).ok())
+        .filter(|page| page.page_type == fil0fil::FIL_PAGE_UNDO_LOG)
+        .filter(|page| is_undo_seg_hdr_page(page.page_no, page.buf()))
+        .map(|page| {
+            let seg_hdr = trx_undo_seg_hdr_t::from_page(page.buf());
+            let mut dict_table_id = None;
+            let mut error = None;
+            if seg_hdr.last_log as usize + TRX_UNDO_LOG_OLD_HDR_SIZE as usize > page.len() {
+                error = Some(format!(
+                    "last undo log header at {} is beyond the page end",
+                    seg_hdr.last_log
+                ));
+            } else if seg_hdr.last_log != 0 {
+                dict_table_id =
+                    trx_undo_log_hdr_t::from_page(page.buf(), seg_hdr.last_log).dict_table_id;
+            }
+
+            UndoSegmentSummary {
+                page_no: page.page_no,
+                state: seg_hdr.state,
+                last_log: seg_hdr.last_log,
+                page_count: seg_hdr.page_list.len,
+                dict_table_id,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// A break in the page list of an undo log segment found by `verify_undo_page_list()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoPageListBreak {
+    /// Page where the break was detected.
+    pub page_no: u32,
+    pub reason: String,
+}
+
+impl Display for UndoPageListBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "undo page list break at page {}: {}",
+            self.page_no, self.reason
+        )
+    }
+}
+
+/// Walks the page list of the undo log segment with the header on `seg_hdr_page` and
+/// checks that the prev/next pointers of the undo pages are mutually consistent and match
+/// the segment page list base node. Returns the breaks found.
+pub fn verify_undo_page_list(
+    reader: &TablespaceReader<'_>,
+    seg_hdr_page: u32,
+) -> std::io::Result<Vec<UndoPageListBreak>> {
+    let page = reader.page(seg_hdr_page)?;
+    let base = trx_undo_seg_hdr_t::from_page(page.buf()).page_list;
+
+    let node_offset = (TRX_UNDO_PAGE_HDR + TRX_UNDO_PAGE_NODE) as u16;
+    let mut breaks = vec![];
+    let mut brk = |page_no, reason: String| breaks.push(UndoPageListBreak { page_no, reason });
+
+    let mut prev = fil0fil::fil_addr_t::null();
+    let mut count = 0;
+    for node in fut0lst::FlstWalker::new(reader, &base) {
+        let (addr, node) = match node {
+            Ok(node) => node,
+            Err(err) => {
+                brk(prev.page, format!("can't read the next page: {err}"));
+                return Ok(breaks);
+            }
+        };
+        count += 1;
+
+        if addr.boffset != node_offset {
+            brk(
+                addr.page,
+                format!("node offset {} != {node_offset}", addr.boffset),
+            );
+        }
+
+        if node.prev != prev {
+            brk(
+                addr.page,
+                format!("prev {:?} does not point back to {:?}", node.prev, prev),
+            );
+        }
+
+        match reader.page(addr.page) {
+            Ok(page) if page.page_type == fil0fil::FIL_PAGE_UNDO_LOG => {}
+            Ok(page) => brk(
+                addr.page,
+                format!("page type {} is not undo log", page.page_type),
+            ),
+            Err(err) => brk(addr.page, format!("can't read the page: {err}")),
+        }
+
+        if count == base.len && !node.next.is_empty() {
+            brk(
+                addr.page,
+                format!("next {:?} after the last page of the list", node.next),
+            );
+        }
+
+        prev = addr;
+    }
+
+    if count != base.len {
+        brk(
+            prev.page,
+            format!("list ends after {count} pages, expected {}", base.len),
+        );
+    }
+
+    if prev != base.last {
+        brk(
+            prev.page,
+            format!("last page {:?} is not the list last {:?}", prev, base.last),
+        );
+    }
+
+    Ok(breaks)
+}
+
 // The undo log header. There can be several undo log headers on the first page of an update undo
 // log segment.
 
@@ -119,3 +466,497 @@ pub const TRX_UNDO_XA_XID: u32 = TRX_UNDO_XA_BQUAL_LEN + 4; // Distributed trans
 
 /// Total size of the undo log header with the XA XID
 pub const TRX_UNDO_LOG_XA_HDR_SIZE: u32 = TRX_UNDO_XA_XID + wsrep::XIDDATASIZE;
+
+// System columns of a clustered index record
+// -------------------------------------------------------------
+
+/// Transaction identifier (DB_TRX_ID).
+pub type TrxId = u64;
+
+/// Length of the DB_TRX_ID column, in bytes.
+pub const DATA_TRX_ID_LEN: usize = 6;
+/// Length of the DB_ROLL_PTR column, in bytes.
+pub const DATA_ROLL_PTR_LEN: usize = 7;
+
+/// Position of the insert flag in a roll pointer.
+pub const ROLL_PTR_INSERT_FLAG_POS: u32 = 55;
+/// Position of the rollback segment id in a roll pointer.
+pub const ROLL_PTR_RSEG_ID_POS: u32 = 48;
+/// Position of the undo log page number in a roll pointer.
+pub const ROLL_PTR_PAGE_POS: u32 = 16;
+/// Position of the byte offset within the undo log page in a roll pointer.
+pub const ROLL_PTR_BYTE_POS: u32 = 0;
+
+/// Decoded DB_ROLL_PTR pointing to the undo log record of the previous version of a row.
+/// See trx_undo_decode_roll_ptr().
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RollPtr {
+    /// Whether the undo log record is of an insert (there is no previous version).
+    pub is_insert: bool,
+    /// Rollback segment id, the slot in the TRX_SYS rollback segment array.
+    pub rseg_id: u8,
+    /// Page number of the undo log record.
+    pub page_no: u32,
+    /// Byte offset of the undo log record within the page.
+    pub offset: u16,
+}
+
+impl RollPtr {
+    /// Decodes a 56-bit roll pointer value.
+    pub fn from_u64(roll_ptr: u64) -> RollPtr {
+        RollPtr {
+            is_insert: (roll_ptr >> ROLL_PTR_INSERT_FLAG_POS) & 1 != 0,
+            rseg_id: ((roll_ptr >> ROLL_PTR_RSEG_ID_POS) & 0x7f) as u8,
+            page_no: (roll_ptr >> ROLL_PTR_PAGE_POS) as u32,
+            offset: (roll_ptr >> ROLL_PTR_BYTE_POS) as u16,
+        }
+    }
+
+    /// Reads a 7-byte DB_ROLL_PTR column.
+    pub fn from_buf(buf: &[u8]) -> RollPtr {
+        RollPtr::from_u64(mach::mach_read_from_7(buf))
+    }
+
+    /// Returns the address of the undo log record in the rollback segment tablespace.
+    pub fn addr(&self) -> fil0fil::fil_addr_t {
+        fil0fil::fil_addr_t::new(self.page_no, self.offset)
+    }
+}
+
+impl Display for RollPtr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "roll_ptr(insert={}, rseg_id={}, page_no={}, offset={})",
+            self.is_insert, self.rseg_id, self.page_no, self.offset
+        )
+    }
+}
+
+/// Decodes the DB_TRX_ID and DB_ROLL_PTR system columns of a clustered index record.
+/// `rec` must start at DB_TRX_ID, right after the primary key columns.
+pub fn decode_sys_cols(rec: &[u8]) -> (TrxId, RollPtr) {
+    assert!(rec.len() >= DATA_TRX_ID_LEN + DATA_ROLL_PTR_LEN);
+
+    let trx_id = mach::mach_read_from_6(rec);
+    let roll_ptr = RollPtr::from_buf(&rec[DATA_TRX_ID_LEN..]);
+
+    (trx_id, roll_ptr)
+}
+
+/// Undo log header, stored on the first page of an undo log segment at `TRX_UNDO_LAST_LOG`
+/// (or linked from another header through `TRX_UNDO_NEXT_LOG` and `TRX_UNDO_PREV_LOG`).
+#[allow(non_camel_case_types)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct trx_undo_log_hdr_t {
+    /// Transaction start identifier, or 0 if the undo log segment has been completely purged.
+    pub trx_id: u64,
+    /// Transaction end identifier (if the log is in a history list), or 0 if not committed.
+    pub trx_no: u64,
+    /// Whether purge still has to process the log (TRX_UNDO_NEEDS_PURGE, removed in
+    /// MariaDB 11.0).
+    pub needs_purge: bool,
+    /// Offset of the first undo log record of this log on the header page.
+    pub log_start: u16,
+    /// Whether the undo log header includes the XA XID.
+    pub xid_exists: bool,
+    /// Whether the transaction is a table create, index create, or drop transaction.
+    pub dict_trans: bool,
+    /// Id of the table of the dictionary transaction, if `dict_trans` is set.
+    pub dict_table_id: Option<u64>,
+    /// Offset of the next undo log header on this page, 0 if none.
+    pub next_log: u16,
+    /// Offset of the previous undo log header on this page, 0 if none.
+    pub prev_log: u16,
+    /// The file list node in the history list.
+    pub history_node: fut0lst::flst_node_t,
+    /// The XA XID of the transaction, if `xid_exists` is set.
+    pub xid: Option<wsrep::wsrep_xid_t>,
+}
+
+impl trx_undo_log_hdr_t {
+    /// Reads the undo log header at the given offset of the undo log segment header page.
+    pub fn from_page(page: &[u8], offset: u16) -> trx_undo_log_hdr_t {
+        trx_undo_log_hdr_t::from_buf(&page[offset as usize..])
+    }
+
+    /// Reads an undo log header from the given buffer.
+    /// The buffer must be at least `TRX_UNDO_LOG_OLD_HDR_SIZE` bytes long.
+    pub fn from_buf(buf: &[u8]) -> trx_undo_log_hdr_t {
+        assert!(buf.len() >= TRX_UNDO_LOG_OLD_HDR_SIZE as usize);
+
+        let dict_trans = buf[TRX_UNDO_DICT_TRANS as usize] != 0;
+        let dict_table_id =
+            dict_trans.then(|| mach::mach_read_from_8(&buf[TRX_UNDO_TABLE_ID as usize..]));
+
+        let xid_exists = buf[TRX_UNDO_XID_EXISTS as usize] != 0;
+        let xid = (xid_exists && buf.len() >= TRX_UNDO_LOG_XA_HDR_SIZE as usize).then(|| {
+            let mut xid_data = [0u8; wsrep::XIDDATASIZE as usize];
+            xid_data
+                .copy_from_slice(&buf[TRX_UNDO_XA_XID as usize..TRX_UNDO_LOG_XA_HDR_SIZE as usize]);
+
+            wsrep::wsrep_xid_t {
+                format: mach::mach_read_from_4(&buf[TRX_UNDO_XA_FORMAT as usize..]),
+                gtrid_len: mach::mach_read_from_4(&buf[TRX_UNDO_XA_TRID_LEN as usize..]),
+                bqual_len: mach::mach_read_from_4(&buf[TRX_UNDO_XA_BQUAL_LEN as usize..]),
+                xid_data,
+            }
+        });
+
+        trx_undo_log_hdr_t {
+            trx_id: mach::mach_read_from_8(&buf[TRX_UNDO_TRX_ID as usize..]),
+            trx_no: mach::mach_read_from_8(&buf[TRX_UNDO_TRX_NO as usize..]),
+            needs_purge: mach::mach_read_from_2(&buf[TRX_UNDO_NEEDS_PURGE as usize..]) != 0,
+            log_start: mach::mach_read_from_2(&buf[TRX_UNDO_LOG_START as usize..]),
+            xid_exists,
+            dict_trans,
+            dict_table_id,
+            next_log: mach::mach_read_from_2(&buf[TRX_UNDO_NEXT_LOG as usize..]),
+            prev_log: mach::mach_read_from_2(&buf[TRX_UNDO_PREV_LOG as usize..]),
+            history_node: fut0lst::flst_node_t::from_buf(&buf[TRX_UNDO_HISTORY_NODE as usize..]),
+            xid,
+        }
+    }
+}
+
+/// Reads the undo log headers of an undo log segment header page, from the last one at
+/// `TRX_UNDO_LAST_LOG` back through `TRX_UNDO_PREV_LOG`. Returns each header with its
+/// offset on the page.
+pub fn undo_log_headers(page: &[u8]) -> Vec<(u16, trx_undo_log_hdr_t)> {
+    let seg_hdr = trx_undo_seg_hdr_t::from_page(page);
+    let mut headers = Vec::new();
+    let mut offset = seg_hdr.last_log;
+
+    // the headers are stored in ascending order, so a link that does not go backwards is
+    // corrupted.
+    while offset != 0 && offset as usize + TRX_UNDO_LOG_OLD_HDR_SIZE as usize <= page.len() {
+        let log_hdr = trx_undo_log_hdr_t::from_page(page, offset);
+        let prev_log = log_hdr.prev_log;
+        headers.push((offset, log_hdr));
+
+        if prev_log >= offset {
+            break;
+        }
+        offset = prev_log;
+    }
+
+    headers
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_buf::{make_page_footer, make_undo_log_page};
+
+    #[test]
+    fn test_scan_undo_segments() {
+        let page_size = 16 * 1024;
+        let mut buf = vec![0u8; page_size * 7];
+
+        let node_addr = |page_no| {
+            fil0fil::fil_addr_t::new(page_no, (TRX_UNDO_PAGE_HDR + TRX_UNDO_PAGE_NODE) as u16)
+        };
+
+        // segment 1: pages 3 and 4, segment 2: page 5.
+        for (page_no, prev, state, list) in [
+            (3, None, TRX_UNDO_ACTIVE, Some((2, 3, 4))),
+            (4, Some(3), 0, None),
+            (5, None, TRX_UNDO_CACHED, Some((1, 5, 5))),
+        ] {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_undo_log_page(page, 1, page_no, 100, 0x15).unwrap();
+
+            if let Some(prev) = prev {
+                let node = (TRX_UNDO_PAGE_HDR + TRX_UNDO_PAGE_NODE) as usize;
+                node_addr(prev)
+                    .write_to(&mut page[node + fut0lst::FLST_PREV as usize..])
+                    .unwrap();
+            }
+
+            if let Some((len, first, last)) = list {
+                let seg = TRX_UNDO_SEG_HDR as usize;
+                mach::mach_write_to_2(&mut page[seg + TRX_UNDO_STATE as usize..], state).unwrap();
+                mach::mach_write_to_2(&mut page[seg + TRX_UNDO_LAST_LOG as usize..], 86).unwrap();
+
+                let list = seg + TRX_UNDO_PAGE_LIST as usize;
+                mach::mach_write_to_4(&mut page[list..], len).unwrap();
+                node_addr(first).write_to(&mut page[list + 4..]).unwrap();
+                node_addr(last)
+                    .write_to(&mut page[list + 4 + fil0fil::FIL_ADDR_SIZE as usize..])
+                    .unwrap();
+            }
+
+            make_page_footer(page).unwrap();
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let segments = scan_undo_segments(&reader);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(
+            (
+                segments[0].page_no,
+                segments[0].state,
+                segments[0].page_count
+            ),
+            (3, TRX_UNDO_ACTIVE, 2)
+        );
+        assert_eq!(segments[0].last_log, 86);
+        assert_eq!(
+            (
+                segments[1].page_no,
+                segments[1].state,
+                segments[1].page_count
+            ),
+            (5, TRX_UNDO_CACHED, 1)
+        );
+        assert_eq!(
+            segments[1].to_string(),
+            "undo segment at page 5: state=CACHED (2), last_log=86, pages=1"
+        );
+
+        // a last undo log header running past the page end is reported, not read.
+        let page = &mut buf[5 * page_size..6 * page_size];
+        let last_log = (page_size - 10) as u16;
+        mach::mach_write_to_2(
+            &mut page[(TRX_UNDO_SEG_HDR + TRX_UNDO_LAST_LOG) as usize..],
+            last_log,
+        )
+        .unwrap();
+        make_page_footer(page).unwrap();
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let segments = scan_undo_segments(&reader);
+        assert_eq!(segments.len(), 2);
+        assert!(segments[0].error.is_none());
+        assert_eq!(segments[1].last_log, last_log);
+        assert_eq!(segments[1].dict_table_id, None);
+        assert_eq!(
+            segments[1].error.as_deref(),
+            Some("last undo log header at 16374 is beyond the page end")
+        );
+    }
+
+    #[test]
+    fn test_verify_undo_page_list() {
+        let page_size = 16 * 1024;
+        let node = (TRX_UNDO_PAGE_HDR + TRX_UNDO_PAGE_NODE) as usize;
+        let node_addr = |page_no| fil0fil::fil_addr_t::new(page_no, node as u16);
+
+        // segment of pages 3 -> 4 -> 6.
+        let pages = [3u32, 4, 6];
+        let mut buf = vec![0u8; page_size * 7];
+        for (i, &page_no) in pages.iter().enumerate() {
+            let page = &mut buf[page_no as usize * page_size..(page_no as usize + 1) * page_size];
+            make_undo_log_page(page, 1, page_no, 100, 0x15).unwrap();
+
+            if i > 0 {
+                node_addr(pages[i - 1])
+                    .write_to(&mut page[node + fut0lst::FLST_PREV as usize..])
+                    .unwrap();
+            }
+            if i + 1 < pages.len() {
+                node_addr(pages[i + 1])
+                    .write_to(&mut page[node + fut0lst::FLST_NEXT as usize..])
+                    .unwrap();
+            }
+
+            if i == 0 {
+                let list = (TRX_UNDO_SEG_HDR + TRX_UNDO_PAGE_LIST) as usize;
+                mach::mach_write_to_4(&mut page[list..], pages.len() as u32).unwrap();
+                node_addr(3).write_to(&mut page[list + 4..]).unwrap();
+                node_addr(6)
+                    .write_to(&mut page[list + 4 + fil0fil::FIL_ADDR_SIZE as usize..])
+                    .unwrap();
+            }
+        }
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        assert_eq!(verify_undo_page_list(&reader, 3).unwrap(), vec![]);
+
+        // page 4 next points to page 5 (all zeroes) instead of page 6.
+        let page4 = 4 * page_size + node + fut0lst::FLST_NEXT as usize;
+        node_addr(5).write_to(&mut buf[page4..]).unwrap();
+
+        let reader = TablespaceReader::new(&buf, page_size);
+        let breaks = verify_undo_page_list(&reader, 3).unwrap();
+        assert!(!breaks.is_empty());
+        assert_eq!(breaks[0].page_no, 5);
+        assert!(breaks.iter().any(|b| b.reason.starts_with("prev ")));
+        assert!(breaks.iter().any(|b| b.reason.starts_with("page type ")));
+        assert!(breaks.iter().any(|b| b.reason.starts_with("last page ")));
+    }
+
+    #[test]
+    fn test_undo_page_records() {
+        let page_size = 16 * 1024;
+        let mut page = vec![0u8; page_size];
+        make_undo_log_page(&mut page, 1, 3, 100, 0x15).unwrap();
+
+        let hdr = TRX_UNDO_PAGE_HDR as usize;
+        let start = mach::mach_read_from_2(&page[hdr + TRX_UNDO_PAGE_START as usize..]) as usize;
+
+        // an insert of 10 bytes, then an update with extern fields of 12 bytes.
+        let recs = [
+            (TRX_UNDO_INSERT_REC, 10),
+            (
+                TRX_UNDO_UPD_EXIST_REC | TRX_UNDO_CMPL_INFO_MULT | TRX_UNDO_UPD_EXTERN,
+                12,
+            ),
+        ];
+        let mut offset = start;
+        for (type_cmpl, len) in recs {
+            mach::mach_write_to_2(&mut page[offset..], (offset + len) as u16).unwrap();
+            page[offset + 2] = type_cmpl;
+            // the record ends with the offset of its start.
+            mach::mach_write_to_2(&mut page[offset + len - 2..], offset as u16).unwrap();
+            offset += len;
+        }
+        mach::mach_write_to_2(
+            &mut page[hdr + TRX_UNDO_PAGE_FREE as usize..],
+            offset as u16,
+        )
+        .unwrap();
+
+        let undo_page = trx_undo_page_t::from_page(&page);
+        let records = undo_page.records(&page);
+
+        assert_eq!(
+            records,
+            vec![
+                UndoRec {
+                    offset: start as u16,
+                    len: 10,
+                    rec_type: TRX_UNDO_INSERT_REC,
+                    cmpl_info: 0,
+                    extern_update: false,
+                    next: start as u16 + 10,
+                },
+                UndoRec {
+                    offset: start as u16 + 10,
+                    len: 12,
+                    rec_type: TRX_UNDO_UPD_EXIST_REC,
+                    cmpl_info: 1,
+                    extern_update: true,
+                    next: start as u16 + 22,
+                },
+            ]
+        );
+        assert_eq!(records[1].bytes(&page).len(), 12);
+        assert_eq!(
+            records[0].to_string(),
+            format!(
+                "undo rec at {start}: type=INSERT_REC (11), cmpl_info=0, extern=false, len=10, \
+                 next={}",
+                start + 10
+            )
+        );
+
+        // a next pointer going backwards ends the walk.
+        mach::mach_write_to_2(&mut page[start + 10..], start as u16).unwrap();
+        assert_eq!(undo_page.records(&page).len(), 1);
+    }
+
+    #[test]
+    fn test_undo_log_hdr_dict_table_id() {
+        let mut buf = vec![0u8; TRX_UNDO_LOG_XA_HDR_SIZE as usize];
+        mach::mach_write_to_8(&mut buf[TRX_UNDO_TRX_ID as usize..], 0x1234).unwrap();
+        mach::mach_write_to_2(&mut buf[TRX_UNDO_LOG_START as usize..], 0xa0).unwrap();
+        mach::mach_write_to_8(&mut buf[TRX_UNDO_TABLE_ID as usize..], 42).unwrap();
+
+        // TRX_UNDO_TABLE_ID is ignored unless TRX_UNDO_DICT_TRANS is set.
+        let hdr = trx_undo_log_hdr_t::from_buf(&buf);
+        assert!(!hdr.dict_trans);
+        assert_eq!(hdr.dict_table_id, None);
+
+        buf[TRX_UNDO_DICT_TRANS as usize] = 1;
+        let hdr = trx_undo_log_hdr_t::from_buf(&buf);
+        assert_eq!(hdr.trx_id, 0x1234);
+        assert_eq!(hdr.log_start, 0xa0);
+        assert!(hdr.dict_trans);
+        assert_eq!(hdr.dict_table_id, Some(42));
+    }
+
+    #[test]
+    fn test_undo_log_headers() {
+        let mut page = vec![0u8; 16384];
+        let seg = TRX_UNDO_SEG_HDR as usize;
+        let first = (seg + TRX_UNDO_SEG_HDR_SIZE as usize) as u16;
+        let second = first + TRX_UNDO_LOG_OLD_HDR_SIZE as u16;
+        mach::mach_write_to_2(
+            &mut page[seg + TRX_UNDO_STATE as usize..],
+            TRX_UNDO_PREPARED,
+        )
+        .unwrap();
+        mach::mach_write_to_2(&mut page[seg + TRX_UNDO_LAST_LOG as usize..], second).unwrap();
+
+        // a committed log without a XID followed by a prepared XA transaction.
+        let hdr = first as usize;
+        mach::mach_write_to_8(&mut page[hdr + TRX_UNDO_TRX_ID as usize..], 0x10).unwrap();
+        mach::mach_write_to_8(&mut page[hdr + TRX_UNDO_TRX_NO as usize..], 0x11).unwrap();
+        mach::mach_write_to_2(&mut page[hdr + TRX_UNDO_NEXT_LOG as usize..], second).unwrap();
+
+        let hdr = second as usize;
+        mach::mach_write_to_8(&mut page[hdr + TRX_UNDO_TRX_ID as usize..], 0x20).unwrap();
+        mach::mach_write_to_2(&mut page[hdr + TRX_UNDO_PREV_LOG as usize..], first).unwrap();
+        page[hdr + TRX_UNDO_XID_EXISTS as usize] = 1;
+        mach::mach_write_to_4(&mut page[hdr + TRX_UNDO_XA_FORMAT as usize..], 1).unwrap();
+        mach::mach_write_to_4(&mut page[hdr + TRX_UNDO_XA_TRID_LEN as usize..], 3).unwrap();
+        mach::mach_write_to_4(&mut page[hdr + TRX_UNDO_XA_BQUAL_LEN as usize..], 2).unwrap();
+        page[hdr + TRX_UNDO_XA_XID as usize..hdr + TRX_UNDO_XA_XID as usize + 5]
+            .copy_from_slice(b"trxbq");
+
+        let seg_hdr = trx_undo_seg_hdr_t::from_page(&page);
+        assert_eq!(seg_hdr.state, TRX_UNDO_PREPARED);
+        assert_eq!(seg_hdr.last_log, second);
+
+        let headers = undo_log_headers(&page);
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers[0].0, second);
+        assert_eq!(headers[0].1.trx_id, 0x20);
+        assert!(headers[0].1.xid_exists);
+        let xid = headers[0].1.xid.as_ref().unwrap();
+        assert_eq!((xid.format, xid.gtrid_len, xid.bqual_len), (1, 3, 2));
+        assert_eq!(&xid.xid_data[..5], b"trxbq");
+        assert_eq!(headers[1].0, first);
+        assert_eq!((headers[1].1.trx_id, headers[1].1.trx_no), (0x10, 0x11));
+        assert!(headers[1].1.xid.is_none());
+
+        // a previous log link that does not go backwards ends the walk.
+        mach::mach_write_to_2(&mut page[hdr + TRX_UNDO_PREV_LOG as usize..], second).unwrap();
+        assert_eq!(undo_log_headers(&page).len(), 1);
+    }
+
+    #[test]
+    fn test_decode_sys_cols() {
+        let rec = [
+            0x00, 0x00, 0x00, 0x00, 0x12, 0x34, // DB_TRX_ID
+            0x83, 0x00, 0x00, 0x01, 0x23, 0x01, 0x10, // DB_ROLL_PTR
+        ];
+
+        let (trx_id, roll_ptr) = decode_sys_cols(&rec);
+        assert_eq!(trx_id, 0x1234);
+        assert_eq!(
+            roll_ptr,
+            RollPtr {
+                is_insert: true,
+                rseg_id: 3,
+                page_no: 0x123,
+                offset: 0x110,
+            }
+        );
+        assert_eq!(roll_ptr.addr(), fil0fil::fil_addr_t::new(0x123, 0x110));
+        assert_eq!(
+            roll_ptr.to_string(),
+            "roll_ptr(insert=true, rseg_id=3, page_no=291, offset=272)"
+        );
+
+        let mut buf = vec![];
+        mach::mach_write_to_7(&mut buf, 0x7f_ffff_ffff_ffff).unwrap();
+        let roll_ptr = RollPtr::from_buf(&buf);
+        assert!(!roll_ptr.is_insert);
+        assert_eq!(roll_ptr.rseg_id, 0x7f);
+    }
+}
@@ -24,3 +24,30 @@ pub const fn UT_BITS_IN_BYTES(bits: u32) -> u32 {
 pub const fn UT_IS_2POW(n: u32) -> bool {
     (n & (n.wrapping_sub(1))) == 0
 }
+
+/// Random mask of the folding functions.
+pub const UT_HASH_RANDOM_MASK: usize = 1463735687;
+/// Second random mask of the folding functions.
+pub const UT_HASH_RANDOM_MASK2: usize = 1653893711;
+
+/// Folds a pair of ulints.
+///
+/// # Returns
+/// Folded value
+#[inline]
+pub const fn ut_fold_ulint_pair(n1: usize, n2: usize) -> usize {
+    (((n1 ^ n2 ^ UT_HASH_RANDOM_MASK2) << 8).wrapping_add(n1) ^ UT_HASH_RANDOM_MASK)
+        .wrapping_add(n2)
+}
+
+/// Folds a binary string.
+///
+/// # Arguments
+/// * `s` - String of bytes
+///
+/// # Returns
+/// Folded value
+pub fn ut_fold_binary(s: &[u8]) -> usize {
+    s.iter()
+        .fold(0, |fold, &b| ut_fold_ulint_pair(fold, b as usize))
+}
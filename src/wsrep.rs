@@ -12,31 +12,134 @@ pub const XIDDATASIZE: u32 = MYSQL_XIDDATASIZE;
 //  Reference: include/mysql/plugin.h
 pub const MYSQL_XIDDATASIZE: u32 = 128;
 
+// Reference: sql/wsrep_xid.cc
+pub const WSREP_XID_PREFIX: &[u8] = b"WSREPXi";
+pub const WSREP_XID_PREFIX_LEN: usize = 7;
+pub const WSREP_XID_VERSION_OFFSET: usize = WSREP_XID_PREFIX_LEN;
+pub const WSREP_XID_VERSION_1: u8 = b'd';
+pub const WSREP_XID_VERSION_2: u8 = b'e';
+pub const WSREP_XID_VERSION_3: u8 = b'f';
+pub const WSREP_XID_UUID_OFFSET: usize = 8;
+pub const WSREP_XID_SEQNO_OFFSET: usize = WSREP_XID_UUID_OFFSET + 16;
+pub const WSREP_XID_GTRID_LEN: usize = WSREP_XID_SEQNO_OFFSET + 8;
+
+/// Galera cluster state UUID.
+pub type WsrepUuid = [u8; 16];
+
 /// WSREP XID info structure. Present in the trx_sys_t or trx_rseg_t header.
 #[allow(non_camel_case_types)]
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct wsrep_xid_t {
     pub format: u32,
     pub gtrid_len: u32,
     pub bqual_len: u32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::serde_hex::serialize")
+    )]
     pub xid_data: [u8; XIDDATASIZE as usize],
 }
 
+impl wsrep_xid_t {
+    /// Returns the Galera cluster state UUID and seqno encoded in a WSREP XID, or None if
+    /// the XID is not a WSREP one. Reference: wsrep_xid.cc:wsrep_is_wsrep_xid().
+    pub fn gtid(&self) -> Option<(WsrepUuid, i64)> {
+        let data = &self.xid_data;
+        if self.format != 1
+            || self.gtrid_len as usize != WSREP_XID_GTRID_LEN
+            || self.bqual_len != 0
+            || &data[..WSREP_XID_PREFIX_LEN] != WSREP_XID_PREFIX
+            || !matches!(
+                data[WSREP_XID_VERSION_OFFSET],
+                WSREP_XID_VERSION_1 | WSREP_XID_VERSION_2 | WSREP_XID_VERSION_3
+            )
+        {
+            return None;
+        }
+
+        let mut uuid = WsrepUuid::default();
+        uuid.copy_from_slice(&data[WSREP_XID_UUID_OFFSET..WSREP_XID_SEQNO_OFFSET]);
+
+        // the seqno is stored in the little-endian byte order (host order of version 1).
+        let mut seqno = [0u8; 8];
+        seqno.copy_from_slice(&data[WSREP_XID_SEQNO_OFFSET..WSREP_XID_GTRID_LEN]);
+
+        Some((uuid, i64::from_le_bytes(seqno)))
+    }
+}
+
+/// Formats a Galera GTID as `uuid:seqno`.
+pub fn gtid_to_string(uuid: &WsrepUuid, seqno: i64) -> String {
+    let hex = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    };
+
+    format!(
+        "{}-{}-{}-{}-{}:{seqno}",
+        hex(&uuid[..4]),
+        hex(&uuid[4..6]),
+        hex(&uuid[6..8]),
+        hex(&uuid[8..10]),
+        hex(&uuid[10..])
+    )
+}
+
 impl Debug for wsrep_xid_t {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("trx_sys_wsrep_xid_t")
-            .field("format", &self.format)
+        let mut s = f.debug_struct("trx_sys_wsrep_xid_t");
+        s.field("format", &self.format)
             .field("gtrid_len", &self.gtrid_len)
-            .field("bqual_len", &self.bqual_len)
-            .field(
-                "xid_data",
-                &self
-                    .xid_data
-                    .iter()
-                    .map(|b| format!("{:02x}", b))
-                    .collect::<Vec<String>>()
-                    .join(""),
-            )
-            .finish()
+            .field("bqual_len", &self.bqual_len);
+
+        if let Some((uuid, seqno)) = self.gtid() {
+            s.field("gtid", &gtid_to_string(&uuid, seqno));
+        }
+
+        s.field(
+            "xid_data",
+            &self
+                .xid_data
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<String>>()
+                .join(""),
+        )
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gtid() {
+        let uuid = [
+            0x6b, 0x7c, 0x7c, 0x2a, 0x3b, 0x22, 0x11, 0xee, 0x9a, 0x1e, 0x02, 0x42, 0xac, 0x11,
+            0x00, 0x02,
+        ];
+
+        let mut xid = wsrep_xid_t {
+            format: 1,
+            gtrid_len: WSREP_XID_GTRID_LEN as u32,
+            bqual_len: 0,
+            xid_data: [0u8; XIDDATASIZE as usize],
+        };
+        xid.xid_data[..WSREP_XID_PREFIX_LEN].copy_from_slice(WSREP_XID_PREFIX);
+        xid.xid_data[WSREP_XID_VERSION_OFFSET] = WSREP_XID_VERSION_3;
+        xid.xid_data[WSREP_XID_UUID_OFFSET..WSREP_XID_SEQNO_OFFSET].copy_from_slice(&uuid);
+        xid.xid_data[WSREP_XID_SEQNO_OFFSET..WSREP_XID_GTRID_LEN]
+            .copy_from_slice(&1234i64.to_le_bytes());
+
+        assert_eq!(xid.gtid(), Some((uuid, 1234)));
+        assert!(format!("{xid:?}").contains("gtid: \"6b7c7c2a-3b22-11ee-9a1e-0242ac110002:1234\""));
+
+        xid.xid_data[0] = b'X';
+        assert_eq!(xid.gtid(), None);
+        assert!(!format!("{xid:?}").contains("gtid"));
     }
 }
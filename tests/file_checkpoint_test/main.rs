@@ -1,18 +1,15 @@
-use std::{
-    io::{Seek, Write},
-    path::Path,
-};
+use std::path::Path;
 
 use bolero::check;
 use mdbutil::{
     Lsn,
-    log::{CHECKPOINT_1, CHECKPOINT_2, FIRST_LSN, Redo, RedoHeader},
+    log::{FIRST_LSN, Redo},
     mtr::Mtr,
     mtr0types::MtrOperation,
 };
 
 fn main() {
-    let size = 1024 * 1024; // 1 MiB of storage
+    let size = 2 * 1024 * 1024; // 2 MiB of storage
 
     let temp_file = tempfile::NamedTempFile::new().expect("Failed to create temp file");
     let path = temp_file.path();
@@ -26,40 +23,20 @@ fn main() {
         }
         res.expect("Failed to create redo log file");
 
-        parse_redo_log_file(path, *lsn).expect("Failed to parse redo log file");
+        parse_redo_log_file(path, size, *lsn).expect("Failed to parse redo log file");
     });
 }
 
-fn make_redo_log_file(path: &Path, size: u64, lsn: Lsn) -> std::io::Result<()> {
-    let first_lsn = FIRST_LSN;
-    let capacity = size - first_lsn;
-
-    let mut log = Redo::writer(path, first_lsn as usize, size).map_err(std::io::Error::other)?;
-    let mut writer = log.writer();
-
-    let header = RedoHeader::build_unencrypted_header_10_8(first_lsn, "test_creator")?;
-    writer.seek(std::io::SeekFrom::Start(0))?;
-    writer.write_all(&header)?;
-
-    let checkpoint = RedoHeader::build_unencrypted_header_10_8_checkpoint(lsn, lsn)?;
-    writer.seek(std::io::SeekFrom::Start(CHECKPOINT_1 as u64))?;
-    writer.write_all(&checkpoint)?;
-
-    writer.seek(std::io::SeekFrom::Start(CHECKPOINT_2 as u64))?;
-    writer.write_all(&checkpoint)?;
-
-    let mut file_checkpoint = vec![];
-    Mtr::build_file_checkpoint(&mut file_checkpoint, first_lsn, capacity, lsn)?;
-    file_checkpoint.push(0x0); // end marker
-
-    writer.seek(std::io::SeekFrom::Start(lsn))?;
-    writer.write_all(&file_checkpoint)?;
+fn make_redo_log_file(path: &Path, size: u64, lsn: Lsn) -> anyhow::Result<()> {
+    let mut log = Redo::create(path, size, FIRST_LSN)?;
+    log.write_checkpoint(lsn)?;
 
     Ok(())
 }
 
-fn parse_redo_log_file(path: &Path, lsn: Lsn) -> anyhow::Result<()> {
+fn parse_redo_log_file(path: &Path, size: u64, lsn: Lsn) -> anyhow::Result<()> {
     let log = Redo::open(path)?;
+    let capacity = size - FIRST_LSN;
 
     assert_eq!(log.header().first_lsn, FIRST_LSN);
     assert!(!log.checkpoint().encrypted);
@@ -70,23 +47,20 @@ fn parse_redo_log_file(path: &Path, lsn: Lsn) -> anyhow::Result<()> {
     let mut reader = log.reader();
     let mut mtrs = 0usize;
 
-    loop {
-        let chain = match reader.parse_next() {
-            Ok(chain) => chain,
-            Err(err) => {
-                // test for EOM.
-                if let Some(err) = err.downcast_ref::<std::io::Error>()
-                    && err.kind() == std::io::ErrorKind::NotFound
-                {
-                    break;
-                }
-
-                panic!("Failed to parse MTR: {err:#?}");
-            }
-        };
+    for chain in reader.chains() {
+        let chain = chain.unwrap_or_else(|err| panic!("Failed to parse MTR: {err:#?}"));
 
         mtrs += chain.mtr.len();
 
+        // the parsed chain must serialize back into exactly the built bytes.
+        let mut expected = vec![];
+        Mtr::build_file_checkpoint(&mut expected, FIRST_LSN, capacity, chain.lsn)?;
+        assert_eq!(
+            chain.to_bytes(FIRST_LSN, capacity)?,
+            expected,
+            "MTR chain at {lsn} does not round-trip"
+        );
+
         for mtr in chain.mtr {
             if mtr.op == MtrOperation::FileCheckpoint {
                 file_checkpoint_lsn = mtr.file_checkpoint_lsn;
@@ -0,0 +1,20 @@
+use bolero::check;
+use mdbutil::{mtr::MtrChain, ring::RingReader};
+
+/// Feeds arbitrary ring buffers, header sizes and start positions into
+/// MtrChain::parse_next(). It must never panic, only return a chain or an error.
+fn main() {
+    check!()
+        .with_type()
+        .for_each(|(hdr, pos, buf): &(u16, u64, Vec<u8>)| {
+            let hdr = *hdr as usize % (buf.len() + 2);
+            let mut r = RingReader::buf_at(buf.as_slice(), hdr, *pos);
+
+            // a few consecutive chains at most, as a valid one moves the reader forward.
+            for _ in 0..4 {
+                if MtrChain::parse_next(&mut r).is_err() {
+                    break;
+                }
+            }
+        });
+}
@@ -4,7 +4,7 @@ use mdbutil::{Lsn, mtr::MtrChain, ring::RingReader};
 fn main() {
     let buf = vec![
         // MTR Chain count=2, len=123, lsn=163
-        //   1: Mtr { space_id: 3, page_no: 45, op: Extended }
+        //   1: Mtr { space_id: 3, page_no: 45, op: Extended(UNDO_APPEND) }
         //   2: Mtr { space_id: 3, page_no: 45, op: Option }
         0x20, 0x5e, 0x3, 0x2d, 0x3, 0xd, 0x3, 0xf, 0x20, 0x0, 0x0, 0x0, 0x0, 0x17, 0xc6, 0x0, 0x0,
         0x0, 0x2d, 0x1, 0x78, 0x4, 0x74, 0x65, 0x73, 0x74, 0x1, 0x61, 0x7, 0x50, 0x52, 0x49, 0x4d,
@@ -25,7 +25,7 @@ fn main() {
     ];
 
     check!().with_type().for_each(|lsn: &Lsn| {
-        let mut r0 = RingReader::buf_at(buf.as_slice(), 0, *lsn as usize);
+        let mut r0 = RingReader::buf_at(buf.as_slice(), 0, *lsn);
 
         let chain = match MtrChain::parse_next(&mut r0) {
             Ok(chain) => chain,